@@ -154,8 +154,10 @@ impl Cli {
         };
 
         let fut = async move {
-            let client = Client::new(config.server_endpoint).await?;
-            let clips = client.list(PREVIEW_LENGTH).await?;
+            let client =
+                Client::with_credentials(config.server_endpoint.clone(), &config.credentials())
+                    .await?;
+            let clips = client.list(PREVIEW_LENGTH, &[]).await?;
 
             match commands {
                 Some(Commands::Version { .. }) => print_version(&client).await,
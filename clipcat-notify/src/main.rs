@@ -1,9 +1,10 @@
 mod error;
 
-use std::io::Write;
+use std::{collections::HashSet, io::Write};
 
 use clap::{CommandFactory, Parser, Subcommand};
 use clipcat_base::ClipboardKind;
+use clipcat_server::backend::ClipboardEvent;
 use serde::Serialize;
 use snafu::ResultExt;
 use time::OffsetDateTime;
@@ -62,12 +63,19 @@ impl Cli {
 
                 Runtime::new().context(error::InitializeTokioRuntimeSnafu)?.block_on(
                     async move {
-                        let backend = clipcat_server::backend::new(&[])
+                        let sensitive_mime_types = clipcat_base::DEFAULT_SENSITIVE_MIME_TYPES
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<HashSet<_>>();
+                        let backend = clipcat_server::backend::new(&[], &sensitive_mime_types)
                             .context(error::InitializeClipboardBackendSnafu)?;
                         let mut subscriber =
                             backend.subscribe().context(error::SubscribeClipboardSnafu)?;
 
-                        while let Some((kind, mime)) = subscriber.next().await {
+                        while let Some((kind, event)) = subscriber.next().await {
+                            let ClipboardEvent::Changed { mime, .. } = event else {
+                                continue;
+                            };
                             match kind {
                                 ClipboardKind::Clipboard if enable_clipboard => {}
                                 ClipboardKind::Primary if enable_primary => {}
@@ -1,10 +1,14 @@
-use std::{io::Write, num::ParseIntError, path::PathBuf};
+use std::{io::Write, num::ParseIntError, path::PathBuf, time::Duration};
 
 use clap::{CommandFactory, Parser, Subcommand};
-use clipcat_base::{ClipEntryMetadata, ClipboardKind, ClipboardWatcherState};
+use clipcat_base::{
+    ClipEntryMetadata, ClipboardKind, ClipboardWatcherState, SearchMode, SearchQuery,
+};
 use clipcat_client::{Client, Manager as _, System, Watcher as _};
 use clipcat_external_editor::ExternalEditor;
+use futures::StreamExt;
 use snafu::ResultExt;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     runtime::Runtime,
@@ -66,6 +70,13 @@ pub enum Commands {
         )]
         kind: ClipboardKind,
 
+        #[clap(
+            long = "ttl",
+            value_parser = humantime::parse_duration,
+            help = "Remove the clip after the specified duration, e.g. \"30s\", \"5m\""
+        )]
+        ttl: Option<Duration>,
+
         data: String,
     },
 
@@ -118,6 +129,56 @@ pub enum Commands {
     List {
         #[clap(long)]
         no_id: bool,
+
+        #[clap(
+            long = "kind",
+            short = 'k',
+            help = "Only print clips of the specified kinds (\"clipboard\", \"primary\", \
+                    \"secondary\")"
+        )]
+        kinds: Vec<ClipboardKind>,
+    },
+
+    #[clap(aliases = &["find"], about = "Search clips with <pattern>")]
+    Search {
+        #[clap(long = "regex", short = 'r', help = "Treat <pattern> as a regular expression")]
+        regex: bool,
+
+        #[clap(long = "case-sensitive", short = 's', help = "Search case-sensitively")]
+        case_sensitive: bool,
+
+        #[clap(
+            long = "kind",
+            short = 'k',
+            help = "Only search clips of the specified kinds (\"clipboard\", \"primary\", \
+                    \"secondary\")"
+        )]
+        kinds: Vec<ClipboardKind>,
+
+        #[clap(
+            long = "mime",
+            short = 'm',
+            help = "Only search clips of the specified MIME types, e.g. \"text/plain\", \
+                    \"image/*\""
+        )]
+        mimes: Vec<mime::Mime>,
+
+        #[clap(long = "since", value_parser = parse_rfc3339, help = "Only search clips copied since the specified time (RFC 3339)")]
+        since: Option<OffsetDateTime>,
+
+        #[clap(long = "until", value_parser = parse_rfc3339, help = "Only search clips copied until the specified time (RFC 3339)")]
+        until: Option<OffsetDateTime>,
+
+        #[clap(long = "offset", default_value = "0", help = "Skip the first <offset> results")]
+        offset: usize,
+
+        #[clap(long = "limit", short = 'n', help = "Print at most <limit> results")]
+        limit: Option<usize>,
+
+        #[clap(long)]
+        no_id: bool,
+
+        pattern: String,
     },
 
     #[clap(about = "Update clip with <id>")]
@@ -156,6 +217,18 @@ pub enum Commands {
         id: u64,
     },
 
+    #[clap(about = "Pin clip with <id>, pinned clips are never evicted or cleared")]
+    Pin {
+        #[clap(value_parser = parse_hex)]
+        id: u64,
+    },
+
+    #[clap(about = "Unpin clip with <id>")]
+    Unpin {
+        #[clap(value_parser = parse_hex)]
+        id: u64,
+    },
+
     #[clap(
         aliases = &["remove-all"],
         about = "Remove all clips in clipboard"
@@ -168,6 +241,9 @@ pub enum Commands {
     )]
     Length,
 
+    #[clap(about = "Print clipboard history events as JSON lines")]
+    Watch,
+
     #[clap(aliases = &["enable"], about = "Enable clipboard watcher")]
     EnableWatcher,
 
@@ -231,11 +307,13 @@ impl Cli {
             _ => {}
         }
 
-        let Config { server_endpoint, log } = self.load_config();
+        let config = self.load_config();
+        let credentials = config.credentials();
+        let Config { server_endpoint, log, .. } = config;
         log.registry();
 
         let fut = async move {
-            let client = Client::new(server_endpoint).await?;
+            let client = Client::with_credentials(server_endpoint, &credentials).await?;
             let server_version = client
                 .get_version()
                 .await
@@ -256,28 +334,57 @@ impl Cli {
                     return Ok(0);
                 }
                 None => {
-                    print_list(&client, false).await?;
+                    print_list(&client, false, &[]).await?;
+                }
+                Some(Commands::List { no_id, kinds }) => {
+                    print_list(&client, no_id, &kinds).await?;
                 }
-                Some(Commands::List { no_id }) => {
-                    print_list(&client, no_id).await?;
+                Some(Commands::Search {
+                    regex,
+                    case_sensitive,
+                    kinds,
+                    mimes,
+                    since,
+                    until,
+                    offset,
+                    limit,
+                    no_id,
+                    pattern,
+                }) => {
+                    let query = SearchQuery {
+                        pattern,
+                        mode: if regex { SearchMode::Regex } else { SearchMode::Substring },
+                        case_sensitive,
+                        kinds,
+                        mimes,
+                        since,
+                        until,
+                        offset,
+                        limit,
+                        preview_length: PREVIEW_LENGTH,
+                    };
+                    let (metadata_list, _total) = client.search(query).await?;
+                    print_metadata_list(metadata_list, no_id);
                 }
                 Some(Commands::Get { id }) => {
                     let data = if let Some(id) = id {
                         client.get(id).await?.printable_data(None)
                     } else {
                         client
-                            .list(PREVIEW_LENGTH)
+                            .list(PREVIEW_LENGTH, &[ClipboardKind::Clipboard])
                             .await?
                             .into_iter()
-                            .find(|metadata| metadata.kind == ClipboardKind::Clipboard)
+                            .next()
                             .map(|metadata| metadata.preview)
                             .unwrap_or_default()
                     };
 
                     println!("{data}");
                 }
-                Some(Commands::Insert { kind, data }) => {
-                    let _id = client.insert(data.as_bytes(), mime::TEXT_PLAIN_UTF_8, kind).await?;
+                Some(Commands::Insert { kind, ttl, data }) => {
+                    let _id = client
+                        .insert_with_ttl(data.as_bytes(), mime::TEXT_PLAIN_UTF_8, kind, ttl)
+                        .await?;
                 }
                 Some(Commands::Length) => {
                     println!("{len}", len = client.length().await?);
@@ -308,6 +415,18 @@ impl Cli {
                     }
                     drop(client.batch_remove(&ids).await?);
                 }
+                Some(Commands::Pin { id }) => {
+                    if !client.pin(id).await? {
+                        eprintln!("Clip {id:016x} does not exist");
+                        return Ok(1);
+                    }
+                }
+                Some(Commands::Unpin { id }) => {
+                    if !client.unpin(id).await? {
+                        eprintln!("Clip {id:016x} is not pinned");
+                        return Ok(1);
+                    }
+                }
                 Some(Commands::Clear) => {
                     client.clear().await?;
                 }
@@ -352,6 +471,9 @@ impl Cli {
                         }
                     }
                 }
+                Some(Commands::Watch) => {
+                    print_events(&client).await?;
+                }
                 Some(Commands::EnableWatcher) => {
                     print_watcher_state(client.enable_watcher().await?);
                 }
@@ -439,8 +561,12 @@ fn print_watcher_state(state: ClipboardWatcherState) {
     println!("{msg}");
 }
 
-async fn print_list(client: &Client, no_id: bool) -> Result<(), Error> {
-    let metadata_list = client.list(PREVIEW_LENGTH).await?;
+async fn print_list(client: &Client, no_id: bool, kinds: &[ClipboardKind]) -> Result<(), Error> {
+    print_metadata_list(client.list(PREVIEW_LENGTH, kinds).await?, no_id);
+    Ok(())
+}
+
+fn print_metadata_list(metadata_list: Vec<ClipEntryMetadata>, no_id: bool) {
     for metadata in metadata_list {
         let ClipEntryMetadata { id, preview, .. } = metadata;
         if no_id {
@@ -449,8 +575,21 @@ async fn print_list(client: &Client, no_id: bool) -> Result<(), Error> {
             println!("{id:016x}: {preview}");
         }
     }
+}
+
+async fn print_events(client: &Client) -> Result<(), Error> {
+    let mut events = client.subscribe(PREVIEW_LENGTH).await?;
+    while let Some(event) = events.next().await {
+        let line = serde_json::to_string(&event?).context(error::SerializeEventSnafu)?;
+        println!("{line}");
+    }
     Ok(())
 }
 
 #[inline]
 fn parse_hex(src: &str) -> Result<u64, ParseIntError> { u64::from_str_radix(src, 16) }
+
+#[inline]
+fn parse_rfc3339(src: &str) -> Result<OffsetDateTime, time::error::Parse> {
+    OffsetDateTime::parse(src, &Rfc3339)
+}
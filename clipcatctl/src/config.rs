@@ -8,6 +8,14 @@ pub struct Config {
     #[serde(default = "clipcat_base::config::default_server_endpoint", with = "http_serde::uri")]
     pub server_endpoint: http::Uri,
 
+    // bearer token required by server on HTTP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+
+    // certificate of authority which signs the certificate of server on HTTPS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_ca_certificate: Option<PathBuf>,
+
     #[serde(default)]
     pub log: clipcat_cli::config::LogConfig,
 }
@@ -16,12 +24,22 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             server_endpoint: clipcat_base::config::default_server_endpoint(),
+            access_token: None,
+            tls_ca_certificate: None,
             log: clipcat_cli::config::LogConfig::default(),
         }
     }
 }
 
 impl Config {
+    #[inline]
+    pub fn credentials(&self) -> clipcat_client::Credentials {
+        clipcat_client::Credentials {
+            access_token: self.access_token.clone(),
+            ca_certificate: self.tls_ca_certificate.clone(),
+        }
+    }
+
     #[inline]
     pub fn default_path() -> PathBuf {
         [
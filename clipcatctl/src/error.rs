@@ -25,13 +25,16 @@ pub enum Error {
     Client { source: clipcat_client::Error },
 
     #[snafu(display("Error occurs while interacting with server, error: {error}"))]
-    OperationError { error: String },
+    Operation { error: String },
 
     #[snafu(display("{error}"))]
     EncodeData { error: clipcat_base::ClipEntryError },
 
     #[snafu(display("{source}"))]
     CheckUtf8String { source: Utf8Error },
+
+    #[snafu(display("Could not serialize event, error: {source}"))]
+    SerializeEvent { source: serde_json::Error },
 }
 
 impl From<clipcat_external_editor::Error> for Error {
@@ -44,85 +47,109 @@ impl From<clipcat_client::Error> for Error {
 
 impl From<clipcat_client::error::InsertClipError> for Error {
     fn from(err: clipcat_client::error::InsertClipError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::GetClipError> for Error {
     fn from(err: clipcat_client::error::GetClipError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::GetCurrentClipError> for Error {
     fn from(err: clipcat_client::error::GetCurrentClipError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::GetLengthError> for Error {
     fn from(err: clipcat_client::error::GetLengthError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::ClearClipError> for Error {
     fn from(err: clipcat_client::error::ClearClipError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::RemoveClipError> for Error {
     fn from(err: clipcat_client::error::RemoveClipError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::BatchRemoveClipError> for Error {
     fn from(err: clipcat_client::error::BatchRemoveClipError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::MarkClipError> for Error {
     fn from(err: clipcat_client::error::MarkClipError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
+    }
+}
+
+impl From<clipcat_client::error::PinClipError> for Error {
+    fn from(err: clipcat_client::error::PinClipError) -> Self {
+        Self::Operation { error: err.to_string() }
+    }
+}
+
+impl From<clipcat_client::error::UnpinClipError> for Error {
+    fn from(err: clipcat_client::error::UnpinClipError) -> Self {
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::UpdateClipError> for Error {
     fn from(err: clipcat_client::error::UpdateClipError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::ListClipError> for Error {
     fn from(err: clipcat_client::error::ListClipError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
+    }
+}
+
+impl From<clipcat_client::error::SearchClipError> for Error {
+    fn from(err: clipcat_client::error::SearchClipError) -> Self {
+        Self::Operation { error: err.to_string() }
+    }
+}
+
+impl From<clipcat_client::error::SubscribeEventError> for Error {
+    fn from(err: clipcat_client::error::SubscribeEventError) -> Self {
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::EnableWatcherError> for Error {
     fn from(err: clipcat_client::error::EnableWatcherError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::DisableWatcherError> for Error {
     fn from(err: clipcat_client::error::DisableWatcherError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::ToggleWatcherError> for Error {
     fn from(err: clipcat_client::error::ToggleWatcherError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
 impl From<clipcat_client::error::GetWatcherStateError> for Error {
     fn from(err: clipcat_client::error::GetWatcherStateError) -> Self {
-        Self::OperationError { error: err.to_string() }
+        Self::Operation { error: err.to_string() }
     }
 }
 
@@ -61,6 +61,12 @@ pub enum Commands {
 
     #[clap(about = "Output default configuration")]
     DefaultConfig,
+
+    #[clap(about = "Encrypt existing history in place with the configured encryption key")]
+    EncryptHistory,
+
+    #[clap(about = "Decrypt existing history in place with the configured encryption key")]
+    DecryptHistory,
 }
 
 impl Default for Cli {
@@ -91,6 +97,8 @@ impl Cli {
                     .expect("failed to write to stdout");
                 Ok(())
             }
+            Some(Commands::EncryptHistory) => rewrite_history(&self.load_config()?, true),
+            Some(Commands::DecryptHistory) => rewrite_history(&self.load_config()?, false),
             None => {
                 let config = self.load_config()?;
                 run_clipcatd(config, self.replace)
@@ -139,6 +147,13 @@ impl Cli {
 fn run_clipcatd(config: Config, replace: bool) -> Result<(), Error> {
     config.log.registry();
 
+    // load key before daemonizing, the key command may ask for a passphrase
+    let history_encryption_key = if config.history_encryption.enable {
+        Some(config.history_encryption.load_key()?)
+    } else {
+        None
+    };
+
     let pid_file = PidFile::from(config.pid_file.clone());
     if pid_file.exists() {
         let pid = pid_file.try_load()?;
@@ -175,7 +190,8 @@ fn run_clipcatd(config: Config, replace: bool) -> Result<(), Error> {
     }
 
     let snippets = config.load_snippets();
-    let config = clipcat_server::Config::from(config);
+    let mut config = clipcat_server::Config::from(config);
+    config.history_encryption_key = history_encryption_key;
 
     tracing::info!(
         "{} is initializing, pid: {}",
@@ -200,6 +216,50 @@ fn run_clipcatd(config: Config, replace: bool) -> Result<(), Error> {
     exit_status
 }
 
+fn rewrite_history(config: &Config, encrypt: bool) -> Result<(), Error> {
+    // the daemon would overwrite the history on shutdown
+    let pid_file = PidFile::from(config.pid_file.clone());
+    if pid_file.exists() {
+        let pid = pid_file.try_load()?;
+        if is_running(pid) {
+            return Err(Error::DaemonIsRunning { pid });
+        }
+        tracing::warn!(
+            "PID file `{path}` is stale, process {pid} is not running",
+            path = pid_file.path().display()
+        );
+    }
+
+    let encryption_key = config.history_encryption.load_key()?;
+    let file_path = &config.history_file_path;
+    let backend = config.history_backend.into();
+    let runtime = Runtime::new().context(error::InitializeTokioRuntimeSnafu)?;
+    let count = if encrypt {
+        runtime.block_on(clipcat_server::encrypt_history(file_path, backend, &encryption_key))?
+    } else {
+        runtime.block_on(clipcat_server::decrypt_history(file_path, backend, &encryption_key))?
+    };
+
+    println!(
+        "{count} clip(s) in `{path}` are {action}",
+        path = file_path.display(),
+        action = if encrypt { "encrypted" } else { "decrypted" }
+    );
+    Ok(())
+}
+
+#[allow(unsafe_code)]
+#[inline]
+fn is_running(pid: libc::pid_t) -> bool {
+    if pid <= 0 {
+        return false;
+    }
+    // signal 0 only checks whether the process exists, a process of another
+    // user exists even though it could not be signaled
+    let ret = unsafe { libc::kill(pid, 0) };
+    ret == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
 #[allow(unsafe_code)]
 #[inline]
 fn kill_other(pid: libc::pid_t) -> Result<(), Error> {
@@ -17,12 +17,21 @@ pub struct Config {
     #[serde(default = "Config::default_pid_file_path")]
     pub pid_file: PathBuf,
 
-    #[serde(default = "Config::default_max_history")]
-    pub max_history: usize,
+    #[serde(default)]
+    pub max_history: MaxHistory,
+
+    #[serde(default, with = "humantime_serde", skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<Duration>,
 
     #[serde(default = "Config::default_history_file_path")]
     pub history_file_path: PathBuf,
 
+    #[serde(default)]
+    pub history_backend: HistoryBackend,
+
+    #[serde(default)]
+    pub history_encryption: HistoryEncryptionConfig,
+
     #[serde(default)]
     pub log: clipcat_cli::config::LogConfig,
 
@@ -32,6 +41,15 @@ pub struct Config {
     #[serde(default)]
     pub grpc: GrpcConfig,
 
+    #[serde(default)]
+    pub webui: WebUiConfig,
+
+    #[serde(default)]
+    pub rest: RestConfig,
+
+    #[serde(default)]
+    pub dbus: DBusConfig,
+
     #[serde(default)]
     pub desktop_notification: DesktopNotificationConfig,
 
@@ -39,6 +57,137 @@ pub struct Config {
     pub snippets: Vec<SnippetConfig>,
 }
 
+/// Capacity of history, either shared by all kinds of clipboard or specified
+/// for each of them, e.g. `max_history.primary = 20`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum MaxHistory {
+    Uniform(usize),
+
+    PerKind {
+        #[serde(default = "Config::default_max_history")]
+        clipboard: usize,
+
+        #[serde(default = "Config::default_max_history")]
+        primary: usize,
+
+        #[serde(default = "Config::default_max_history")]
+        secondary: usize,
+    },
+}
+
+impl MaxHistory {
+    /// Replaces zero capacities with the default one.
+    #[must_use]
+    pub const fn or_default(self) -> Self {
+        const fn or_default(capacity: usize) -> usize {
+            if capacity == 0 {
+                Config::default_max_history()
+            } else {
+                capacity
+            }
+        }
+
+        match self {
+            Self::Uniform(capacity) => Self::Uniform(or_default(capacity)),
+            Self::PerKind { clipboard, primary, secondary } => Self::PerKind {
+                clipboard: or_default(clipboard),
+                primary: or_default(primary),
+                secondary: or_default(secondary),
+            },
+        }
+    }
+}
+
+impl Default for MaxHistory {
+    fn default() -> Self { Self::Uniform(Config::default_max_history()) }
+}
+
+impl From<MaxHistory> for clipcat_server::config::HistoryCapacity {
+    fn from(max_history: MaxHistory) -> Self {
+        match max_history {
+            MaxHistory::Uniform(capacity) => Self::from(capacity),
+            MaxHistory::PerKind { clipboard, primary, secondary } => {
+                Self { clipboard, primary, secondary }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryBackend {
+    #[default]
+    #[serde(alias = "filesystem")]
+    File,
+
+    Sqlite,
+}
+
+impl From<HistoryBackend> for clipcat_server::config::HistoryBackend {
+    fn from(backend: HistoryBackend) -> Self {
+        match backend {
+            HistoryBackend::File => Self::FileSystem,
+            HistoryBackend::Sqlite => Self::Sqlite,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct HistoryEncryptionConfig {
+    #[serde(default)]
+    pub enable: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<PathBuf>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_command: Option<String>,
+}
+
+impl HistoryEncryptionConfig {
+    pub fn load_key(&self) -> Result<clipcat_server::config::EncryptionKey, Error> {
+        let mut material = match (&self.key_file, &self.key_command) {
+            (Some(file_path), command) => {
+                if command.is_some() {
+                    tracing::warn!(
+                        "Loading encryption key, both `key_file` and `key_command` are provided, \
+                         prefer `key_file`"
+                    );
+                }
+                std::fs::read(file_path)
+                    .context(ReadEncryptionKeySnafu { filename: file_path.clone() })?
+            }
+            (None, Some(command)) => {
+                let output = std::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .stderr(std::process::Stdio::inherit())
+                    .output()
+                    .context(RunEncryptionKeyCommandSnafu { command: command.clone() })?;
+                if !output.status.success() {
+                    return Err(Error::EncryptionKeyCommandFailed {
+                        command: command.clone(),
+                        status: output.status,
+                    });
+                }
+                output.stdout
+            }
+            (None, None) => return Err(Error::NoEncryptionKey),
+        };
+
+        // ignore the trailing newline of key file or command output
+        while material.last().is_some_and(u8::is_ascii_whitespace) {
+            let _unused = material.pop();
+        }
+        if material.is_empty() {
+            return Err(Error::EmptyEncryptionKey);
+        }
+
+        Ok(clipcat_server::config::EncryptionKey::new(material))
+    }
+}
+
 // SAFETY: user may use bool to enable/disable the functions
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -55,11 +204,95 @@ pub struct WatcherConfig {
     #[serde(default)]
     pub capture_image: bool,
 
+    #[serde(default)]
+    pub reclaim_clipboard: bool,
+
+    #[serde(default)]
+    pub reclaim_primary: bool,
+
+    #[serde(default, with = "humantime_serde", skip_serializing_if = "Option::is_none")]
+    pub primary_coalescing_window: Option<Duration>,
+
+    #[serde(default)]
+    pub ignore_applications: Vec<String>,
+
     #[serde(default = "WatcherConfig::default_filter_min_size")]
     pub filter_min_size: usize,
 
     #[serde(default = "WatcherConfig::default_filter_max_size")]
     pub filter_max_size: usize,
+
+    #[serde(default)]
+    pub filters: WatcherFiltersConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct WatcherFiltersConfig {
+    #[serde(default)]
+    pub denied_text_patterns: Vec<String>,
+
+    #[serde(default)]
+    pub allowed_mimes: Vec<String>,
+
+    #[serde(default)]
+    pub denied_mimes: Vec<String>,
+
+    // X11 target atoms are named after MIME types as well
+    #[serde(
+        default = "WatcherFiltersConfig::default_sensitive_mime_types",
+        alias = "sensitive_x11_atoms"
+    )]
+    pub sensitive_mime_types: Vec<String>,
+}
+
+impl WatcherFiltersConfig {
+    pub fn default_sensitive_mime_types() -> Vec<String> {
+        clipcat_base::DEFAULT_SENSITIVE_MIME_TYPES.iter().map(ToString::to_string).collect()
+    }
+}
+
+impl Default for WatcherFiltersConfig {
+    fn default() -> Self {
+        Self {
+            denied_text_patterns: Vec::new(),
+            allowed_mimes: Vec::new(),
+            denied_mimes: Vec::new(),
+            sensitive_mime_types: Self::default_sensitive_mime_types(),
+        }
+    }
+}
+
+impl From<WatcherFiltersConfig> for clipcat_server::ClipboardWatcherFilterOptions {
+    fn from(
+        WatcherFiltersConfig {
+            denied_text_patterns,
+            allowed_mimes,
+            denied_mimes,
+            sensitive_mime_types,
+        }: WatcherFiltersConfig,
+    ) -> Self {
+        let parse_mimes = |mimes: Vec<String>| {
+            mimes
+                .into_iter()
+                .filter_map(|mime| match mime.parse::<mime::Mime>() {
+                    Ok(mime) => Some(mime),
+                    Err(err) => {
+                        tracing::warn!(
+                            "Ignore invalid MIME type `{mime}` in filters, error: {err}"
+                        );
+                        None
+                    }
+                })
+                .collect()
+        };
+
+        Self {
+            denied_text_patterns,
+            allowed_mimes: parse_mimes(allowed_mimes),
+            denied_mimes: parse_mimes(denied_mimes),
+            sensitive_mime_types: sensitive_mime_types.into_iter().collect(),
+        }
+    }
 }
 
 impl From<WatcherConfig> for clipcat_server::ClipboardWatcherOptions {
@@ -69,8 +302,13 @@ impl From<WatcherConfig> for clipcat_server::ClipboardWatcherOptions {
             enable_clipboard,
             enable_primary,
             capture_image,
+            reclaim_clipboard,
+            reclaim_primary,
+            primary_coalescing_window,
+            ignore_applications,
             filter_min_size,
             filter_max_size,
+            filters,
         }: WatcherConfig,
     ) -> Self {
         Self {
@@ -78,8 +316,13 @@ impl From<WatcherConfig> for clipcat_server::ClipboardWatcherOptions {
             enable_clipboard,
             enable_primary,
             capture_image,
+            reclaim_clipboard,
+            reclaim_primary,
+            primary_coalescing_window,
+            ignore_applications,
             filter_min_size,
             filter_max_size,
+            filters: filters.into(),
         }
     }
 }
@@ -109,6 +352,35 @@ pub struct GrpcConfig {
 
     #[serde(default = "clipcat_base::config::default_unix_domain_socket")]
     pub local_socket: PathBuf,
+
+    // users and groups which are allowed to connect to local sockets besides
+    // the user running the daemon
+    #[serde(default)]
+    pub allowed_uids: Vec<u32>,
+
+    #[serde(default)]
+    pub allowed_gids: Vec<u32>,
+
+    // serve gRPC over TLS on HTTP if it is provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<GrpcTlsConfig>,
+
+    // clients are required to present this token on HTTP if it is provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GrpcTlsConfig {
+    pub cert_file: PathBuf,
+
+    pub key_file: PathBuf,
+}
+
+impl From<GrpcTlsConfig> for clipcat_server::config::GrpcTlsConfig {
+    fn from(GrpcTlsConfig { cert_file, key_file }: GrpcTlsConfig) -> Self {
+        Self { cert_file, key_file }
+    }
 }
 
 impl GrpcConfig {
@@ -128,6 +400,111 @@ impl GrpcConfig {
     pub const fn default_port() -> u16 { clipcat_base::DEFAULT_GRPC_PORT }
 }
 
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct WebUiConfig {
+    #[serde(default)]
+    pub enable: bool,
+
+    #[serde(default = "WebUiConfig::default_host")]
+    pub host: IpAddr,
+
+    #[serde(default = "WebUiConfig::default_port")]
+    pub port: u16,
+
+    // the web page asks for this token, it is required if the web UI is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+}
+
+impl WebUiConfig {
+    #[inline]
+    pub const fn socket_address(&self) -> SocketAddr { SocketAddr::new(self.host, self.port) }
+
+    #[inline]
+    pub const fn default_host() -> IpAddr { clipcat_base::DEFAULT_WEBUI_HOST }
+
+    #[inline]
+    pub const fn default_port() -> u16 { clipcat_base::DEFAULT_WEBUI_PORT }
+}
+
+impl Default for WebUiConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            host: clipcat_base::DEFAULT_WEBUI_HOST,
+            port: clipcat_base::DEFAULT_WEBUI_PORT,
+            access_token: None,
+        }
+    }
+}
+
+/// JSON API which mirrors the gRPC services, for scripts which do not speak
+/// gRPC.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RestConfig {
+    #[serde(default)]
+    pub enable_http: bool,
+
+    #[serde(default)]
+    pub enable_local_socket: bool,
+
+    #[serde(default = "RestConfig::default_host")]
+    pub host: IpAddr,
+
+    #[serde(default = "RestConfig::default_port")]
+    pub port: u16,
+
+    #[serde(default = "clipcat_base::config::default_rest_unix_domain_socket")]
+    pub local_socket: PathBuf,
+
+    // clients are required to present this token on HTTP, it is required if HTTP is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+}
+
+impl RestConfig {
+    #[inline]
+    pub const fn socket_address(&self) -> SocketAddr { SocketAddr::new(self.host, self.port) }
+
+    #[inline]
+    pub const fn default_host() -> IpAddr { clipcat_base::DEFAULT_REST_HOST }
+
+    #[inline]
+    pub const fn default_port() -> u16 { clipcat_base::DEFAULT_REST_PORT }
+}
+
+impl Default for RestConfig {
+    fn default() -> Self {
+        Self {
+            enable_http: false,
+            enable_local_socket: false,
+            host: clipcat_base::DEFAULT_REST_HOST,
+            port: clipcat_base::DEFAULT_REST_PORT,
+            local_socket: clipcat_base::config::default_rest_unix_domain_socket(),
+            access_token: None,
+        }
+    }
+}
+
+/// D-Bus interfaces on the session bus, for desktop integrations.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DBusConfig {
+    #[serde(default)]
+    pub enable: bool,
+
+    #[serde(default = "DBusConfig::default_name")]
+    pub name: String,
+}
+
+impl DBusConfig {
+    #[inline]
+    pub fn default_name() -> String { clipcat_base::DEFAULT_DBUS_NAME.to_string() }
+}
+
+impl Default for DBusConfig {
+    fn default() -> Self { Self { enable: false, name: Self::default_name() } }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct SnippetConfig {
     name: String,
@@ -198,11 +575,17 @@ impl Default for Config {
         Self {
             daemonize: true,
             pid_file: Self::default_pid_file_path(),
-            max_history: Self::default_max_history(),
+            max_history: MaxHistory::default(),
+            max_age: None,
             history_file_path: Self::default_history_file_path(),
+            history_backend: HistoryBackend::default(),
+            history_encryption: HistoryEncryptionConfig::default(),
             log: clipcat_cli::config::LogConfig::default(),
             watcher: WatcherConfig::default(),
             grpc: GrpcConfig::default(),
+            webui: WebUiConfig::default(),
+            rest: RestConfig::default(),
+            dbus: DBusConfig::default(),
             desktop_notification: DesktopNotificationConfig::default(),
             snippets: Vec::new(),
         }
@@ -216,8 +599,13 @@ impl Default for WatcherConfig {
             enable_clipboard: true,
             enable_primary: true,
             capture_image: true,
+            reclaim_clipboard: false,
+            reclaim_primary: false,
+            primary_coalescing_window: None,
+            ignore_applications: Vec::new(),
             filter_min_size: Self::default_filter_min_size(),
             filter_max_size: Self::default_filter_max_size(),
+            filters: WatcherFiltersConfig::default(),
         }
     }
 }
@@ -230,6 +618,10 @@ impl Default for GrpcConfig {
             host: clipcat_base::DEFAULT_GRPC_HOST,
             port: clipcat_base::DEFAULT_GRPC_PORT,
             local_socket: clipcat_base::config::default_unix_domain_socket(),
+            allowed_uids: Vec::new(),
+            allowed_gids: Vec::new(),
+            tls: None,
+            access_token: None,
         }
     }
 }
@@ -281,9 +673,7 @@ impl Config {
                 .context(ParseConfigSnafu { filename: path.as_ref().to_path_buf() })?
         };
 
-        if config.max_history == 0 {
-            config.max_history = Self::default_max_history();
-        }
+        config.max_history = config.max_history.or_default();
 
         Ok(config)
     }
@@ -363,18 +753,54 @@ impl From<DesktopNotificationConfig> for clipcat_server::config::DesktopNotifica
 
 impl From<Config> for clipcat_server::Config {
     fn from(
-        Config { grpc, max_history, history_file_path, watcher, desktop_notification, .. }: Config,
+        Config {
+            grpc,
+            webui,
+            rest,
+            dbus,
+            max_history,
+            max_age,
+            history_file_path,
+            history_backend,
+            watcher,
+            desktop_notification,
+            ..
+        }: Config,
     ) -> Self {
         let grpc_listen_address = grpc.enable_http.then_some(grpc.socket_address());
         let grpc_local_socket = grpc.enable_local_socket.then_some(grpc.local_socket);
+        let local_socket_access = clipcat_server::config::LocalSocketAccess {
+            allowed_uids: grpc.allowed_uids,
+            allowed_gids: grpc.allowed_gids,
+        };
+        let grpc_tls = grpc.tls.map(clipcat_server::config::GrpcTlsConfig::from);
+        let grpc_access_token = grpc.access_token.map(clipcat_server::config::AccessToken::new);
+        let webui_listen_address = webui.enable.then_some(webui.socket_address());
+        let webui_access_token = webui.access_token.map(clipcat_server::config::AccessToken::new);
+        let rest_listen_address = rest.enable_http.then_some(rest.socket_address());
+        let rest_access_token = rest.access_token.map(clipcat_server::config::AccessToken::new);
+        let rest_local_socket = rest.enable_local_socket.then_some(rest.local_socket);
+        let dbus_name = dbus.enable.then_some(dbus.name);
         let watcher = clipcat_server::ClipboardWatcherOptions::from(watcher);
         let desktop_notification =
             clipcat_server::config::DesktopNotificationConfig::from(desktop_notification);
         Self {
             grpc_listen_address,
             grpc_local_socket,
-            max_history,
+            local_socket_access,
+            grpc_tls,
+            grpc_access_token,
+            webui_listen_address,
+            webui_access_token,
+            rest_listen_address,
+            rest_access_token,
+            rest_local_socket,
+            dbus_name,
+            max_history: max_history.into(),
+            max_age,
             history_file_path,
+            history_backend: history_backend.into(),
+            history_encryption_key: None,
             watcher,
             desktop_notification,
         }
@@ -388,4 +814,19 @@ pub enum Error {
 
     #[snafu(display("Count not parse config from {}: {source}", filename.display()))]
     ParseConfig { filename: PathBuf, source: toml::de::Error },
+
+    #[snafu(display("Could not read encryption key from {}: {source}", filename.display()))]
+    ReadEncryptionKey { filename: PathBuf, source: std::io::Error },
+
+    #[snafu(display("Could not run encryption key command `{command}`: {source}"))]
+    RunEncryptionKeyCommand { command: String, source: std::io::Error },
+
+    #[snafu(display("Encryption key command `{command}` failed with {status}"))]
+    EncryptionKeyCommandFailed { command: String, status: std::process::ExitStatus },
+
+    #[snafu(display("Neither `key_file` nor `key_command` is provided for history encryption"))]
+    NoEncryptionKey,
+
+    #[snafu(display("Encryption key is empty"))]
+    EmptyEncryptionKey,
 }
@@ -22,6 +22,9 @@ pub enum Error {
 
     #[snafu(display("Failed to send `SIGTERM` to PID `{pid}`"))]
     SendSignalTermination { pid: libc::pid_t },
+
+    #[snafu(display("Another instance (PID: {pid}) is running, please terminate `{pid}` first"))]
+    DaemonIsRunning { pid: libc::pid_t },
 }
 
 impl From<daemonize::Error> for Error {
@@ -53,6 +56,7 @@ impl CommandError for Error {
             | Self::Daemonize { .. }
             | Self::SendSignalTermination { .. }
             | Self::PidFile { .. } => exitcode::IOERR,
+            Self::DaemonIsRunning { .. } => exitcode::TEMPFAIL,
         }
     }
 }
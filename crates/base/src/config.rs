@@ -5,12 +5,23 @@ use directories::BaseDirs;
 /// # Panics
 /// This function should never panic
 #[inline]
-pub fn default_unix_domain_socket() -> PathBuf {
+#[must_use]
+pub fn default_unix_domain_socket() -> PathBuf { runtime_socket_path("grpc.sock") }
+
+/// Path of Unix domain socket on which the JSON API is served.
+///
+/// # Panics
+/// This function should never panic
+#[inline]
+#[must_use]
+pub fn default_rest_unix_domain_socket() -> PathBuf { runtime_socket_path("rest.sock") }
+
+fn runtime_socket_path(file_name: &str) -> PathBuf {
     let base_dirs = BaseDirs::new().expect("`BaseDirs::new` always success");
     [
         base_dirs.runtime_dir().map_or_else(std::env::temp_dir, Path::to_path_buf),
         PathBuf::from(crate::PROJECT_NAME),
-        PathBuf::from("grpc.sock"),
+        PathBuf::from(file_name),
     ]
     .into_iter()
     .collect()
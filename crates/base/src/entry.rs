@@ -6,10 +6,12 @@ use std::{
 };
 
 use image::ImageEncoder as _;
+use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
 use time::{format_description::well_known::Rfc3339, OffsetDateTime, UtcOffset};
+use xxhash_rust::xxh3::Xxh3;
 
-use crate::{ClipboardContent, ClipboardKind};
+use crate::{ClipSource, ClipboardContent, ClipboardKind, Representations};
 
 #[derive(Clone, Debug, Eq)]
 pub struct Entry {
@@ -20,6 +22,14 @@ pub struct Entry {
     clipboard_kind: ClipboardKind,
 
     timestamp: OffsetDateTime,
+
+    pinned: bool,
+
+    // the clip is removed after this moment
+    expires_at: Option<OffsetDateTime>,
+
+    // the application which owned the selection, if it is known
+    source: Option<ClipSource>,
 }
 
 impl Entry {
@@ -31,34 +41,19 @@ impl Entry {
         clipboard_kind: ClipboardKind,
         timestamp: Option<OffsetDateTime>,
     ) -> Result<Self, Error> {
-        let content = if mime.type_() == mime::TEXT {
+        let content = if mime.type_() == mime::TEXT && mime.subtype() == mime::PLAIN {
             ClipboardContent::Plaintext(String::from_utf8_lossy(data).to_string())
-        } else if mime.type_() == mime::IMAGE {
-            let image_format = match mime.subtype() {
-                mime::PNG => image::ImageFormat::Png,
-                mime::JPEG => image::ImageFormat::Jpeg,
-                mime::GIF => image::ImageFormat::Gif,
-                mime::BMP => image::ImageFormat::Bmp,
-                _ => return Err(Error::FormatNotAvailable),
-            };
-
-            let cursor = std::io::Cursor::new(&data);
-            let mut reader = image::io::Reader::new(cursor);
-            reader.set_format(image_format);
-            reader
-                .decode()
-                .map(|img| {
-                    let image = img.into_rgba8();
-                    let (w, h) = image.dimensions();
-                    ClipboardContent::Image {
-                        width: w as usize,
-                        height: h as usize,
-                        bytes: bytes::Bytes::from(image.into_raw()),
-                    }
-                })
-                .context(ConvertImageSnafu {})?
+        } else if is_supported_image(mime) {
+            // keep the original encoding, only make sure that it looks like an image
+            let mime = mime.essence_str().parse().unwrap_or_else(|_| mime.clone());
+            let _dimensions = image_dimensions(&mime, data)?;
+            ClipboardContent::Image { mime, bytes: bytes::Bytes::copy_from_slice(data) }
         } else {
-            return Err(Error::FormatNotAvailable);
+            // keep data in other formats as it is, e.g. `text/html`, `text/uri-list`
+            ClipboardContent::Rich(Representations::from([(
+                mime.to_string(),
+                bytes::Bytes::copy_from_slice(data),
+            )]))
         };
 
         Ok(Self {
@@ -66,6 +61,9 @@ impl Entry {
             content,
             clipboard_kind,
             timestamp: timestamp.unwrap_or_else(OffsetDateTime::now_utc),
+            pinned: false,
+            expires_at: None,
+            source: None,
         })
     }
 
@@ -75,6 +73,30 @@ impl Entry {
             .unwrap_or_default()
     }
 
+    /// Create a clip from content offered in several formats, a single
+    /// representation is handled like [`Entry::new`].
+    ///
+    /// # Errors
+    pub fn from_representations(
+        mut representations: Representations,
+        clipboard_kind: ClipboardKind,
+        timestamp: Option<OffsetDateTime>,
+    ) -> Result<Self, Error> {
+        match representations.len() {
+            0 => Err(Error::FormatNotAvailable),
+            1 => {
+                let (mime, data) = representations.pop_first().ok_or(Error::FormatNotAvailable)?;
+                let mime = mime.parse().unwrap_or(mime::APPLICATION_OCTET_STREAM);
+                Self::new(&data, &mime, clipboard_kind, timestamp)
+            }
+            _ => Ok(Self::from_clipboard_content(
+                ClipboardContent::Rich(representations),
+                clipboard_kind,
+                timestamp,
+            )),
+        }
+    }
+
     #[inline]
     pub fn from_clipboard_content(
         content: ClipboardContent,
@@ -86,14 +108,65 @@ impl Entry {
             content,
             clipboard_kind,
             timestamp: timestamp.unwrap_or_else(OffsetDateTime::now_utc),
+            pinned: false,
+            expires_at: None,
+            source: None,
         }
     }
 
-    #[inline]
+    /// Computes the identifier of content, it is the 64-bit XXH3 hash of the
+    /// canonical encoding of content, so it is stable across releases.
+    ///
+    /// Content is encoded as a sequence of fields, every field is prefixed
+    /// with its length as little-endian `u64`:
+    /// - plain text: `text/plain; charset=utf-8`, text in UTF-8
+    /// - image: MIME type and data of the original encoding
+    /// - rich content: MIME type and data of every representation, ordered by
+    ///   MIME type
     #[must_use]
     pub fn compute_id(data: &ClipboardContent) -> u64 {
+        let mut hasher = Xxh3::new();
+        let mut write_field = |field: &[u8]| {
+            hasher.update(&u64::try_from(field.len()).unwrap_or(u64::MAX).to_le_bytes());
+            hasher.update(field);
+        };
+        match data {
+            ClipboardContent::Plaintext(text) => {
+                write_field(mime::TEXT_PLAIN_UTF_8.as_ref().as_bytes());
+                write_field(text.as_bytes());
+            }
+            ClipboardContent::Image { mime, bytes } => {
+                write_field(mime.essence_str().as_bytes());
+                write_field(bytes);
+            }
+            ClipboardContent::Rich(representations) => {
+                for (mime, bytes) in representations {
+                    write_field(mime.as_bytes());
+                    write_field(bytes);
+                }
+            }
+        }
+        hasher.digest()
+    }
+
+    /// Computes the identifier used by releases before identifiers became
+    /// stable, it is only used for resolving identifiers of clips in history
+    /// which was saved by those releases.
+    #[inline]
+    #[must_use]
+    pub fn compute_legacy_id(data: &ClipboardContent) -> u64 {
         let mut s = DefaultHasher::new();
-        data.hash(&mut s);
+        if let Ok(image) = data.decode_image() {
+            // those releases kept images as RGBA pixels, hash them the way
+            // `#[derive(Hash)]` hashed `Image { width, height, bytes }`
+            let (width, height) = image.dimensions();
+            1_isize.hash(&mut s);
+            usize::try_from(width).unwrap_or_default().hash(&mut s);
+            usize::try_from(height).unwrap_or_default().hash(&mut s);
+            image.as_raw().as_slice().hash(&mut s);
+        } else {
+            data.hash(&mut s);
+        }
         s.finish()
     }
 
@@ -114,28 +187,64 @@ impl Entry {
 
     #[inline]
     #[must_use]
-    pub const fn is_utf8_string(&self) -> bool {
-        matches!(self.content, ClipboardContent::Plaintext(_))
+    pub const fn is_pinned(&self) -> bool { self.pinned }
+
+    #[inline]
+    pub fn set_pinned(&mut self, pinned: bool) { self.pinned = pinned; }
+
+    #[inline]
+    #[must_use]
+    pub const fn expires_at(&self) -> Option<OffsetDateTime> { self.expires_at }
+
+    #[inline]
+    pub fn set_expires_at(&mut self, expires_at: Option<OffsetDateTime>) {
+        self.expires_at = expires_at;
     }
 
     #[inline]
     #[must_use]
-    pub fn as_utf8_string(&self) -> String {
-        if let ClipboardContent::Plaintext(text) = &self.content {
-            text.clone()
+    pub const fn source(&self) -> Option<&ClipSource> { self.source.as_ref() }
+
+    #[inline]
+    pub fn set_source(&mut self, source: Option<ClipSource>) {
+        self.source = source.filter(|source| !source.is_empty());
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_utf8_string(&self) -> bool {
+        match &self.content {
+            ClipboardContent::Plaintext(_) => true,
+            ClipboardContent::Image { .. } => false,
+            ClipboardContent::Rich(_) => self.content.text().is_some(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn as_utf8_string(&self) -> String { self.content.text().unwrap_or_default() }
+
+    /// Returns all representations of rich content, `None` for content in a
+    /// single format.
+    #[inline]
+    #[must_use]
+    pub const fn representations(&self) -> Option<&Representations> {
+        if let ClipboardContent::Rich(representations) = &self.content {
+            Some(representations)
         } else {
-            String::new()
+            None
         }
     }
 
     #[must_use]
     pub fn basic_information(&self) -> String {
-        let (content_type, size) = match &self.content {
-            ClipboardContent::Plaintext(text) => (mime::TEXT_PLAIN_UTF_8, text.len()),
-            ClipboardContent::Image { width: _, height: _, bytes } => {
-                (mime::IMAGE_PNG, bytes.len())
-            }
-        };
+        let (content_type, size) = (self.mime(), self.content.len());
 
         let timestamp = self
             .timestamp
@@ -158,6 +267,9 @@ impl Entry {
         let data = match &self.content {
             ClipboardContent::Plaintext(text) => text.clone(),
             ClipboardContent::Image { .. } => self.basic_information(),
+            ClipboardContent::Rich(_) => {
+                self.content.text().unwrap_or_else(|| self.basic_information())
+            }
         };
 
         let data = match line_length {
@@ -206,6 +318,9 @@ impl Entry {
         match &self.content {
             ClipboardContent::Plaintext(text) => text.as_bytes(),
             ClipboardContent::Image { bytes, .. } => bytes,
+            ClipboardContent::Rich(representations) => {
+                crate::primary_representation(representations).map_or(&[], |(_, bytes)| bytes)
+            }
         }
     }
 
@@ -214,20 +329,14 @@ impl Entry {
     pub fn encoded(&self) -> Result<Vec<u8>, Error> {
         match &self.content {
             ClipboardContent::Plaintext(text) => Ok(text.as_bytes().to_vec()),
-            ClipboardContent::Image { width, height, bytes } => {
-                encode_as_png(*width, *height, bytes)
-            }
+            ClipboardContent::Image { bytes, .. } => Ok(bytes.to_vec()),
+            ClipboardContent::Rich(_) => Ok(self.as_bytes().to_vec()),
         }
     }
 
     #[inline]
     #[must_use]
-    pub const fn mime(&self) -> mime::Mime {
-        match self.content {
-            ClipboardContent::Plaintext(_) => mime::TEXT_PLAIN_UTF_8,
-            ClipboardContent::Image { .. } => mime::IMAGE_PNG,
-        }
-    }
+    pub fn mime(&self) -> mime::Mime { self.content.mime() }
 
     #[inline]
     pub fn metadata(&self, preview_length: Option<usize>) -> Metadata {
@@ -237,6 +346,8 @@ impl Entry {
             timestamp: self.timestamp,
             mime: self.mime(),
             preview: self.printable_data(preview_length),
+            pinned: self.pinned,
+            source: self.source.clone(),
         }
     }
 }
@@ -248,6 +359,9 @@ impl Default for Entry {
             content: ClipboardContent::Plaintext(String::new()),
             clipboard_kind: ClipboardKind::Clipboard,
             timestamp: OffsetDateTime::now_utc(),
+            pinned: false,
+            expires_at: None,
+            source: None,
         }
     }
 }
@@ -277,17 +391,26 @@ impl AsRef<ClipboardContent> for Entry {
     fn as_ref(&self) -> &ClipboardContent { &self.content }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Metadata {
     pub id: u64,
 
+    #[serde(with = "crate::serde::clipboard_kind")]
     pub kind: ClipboardKind,
 
+    #[serde(with = "time::serde::rfc3339")]
     pub timestamp: OffsetDateTime,
 
+    #[serde(with = "crate::serde::mime")]
     pub mime: mime::Mime,
 
     pub preview: String,
+
+    #[serde(default)]
+    pub pinned: bool,
+
+    #[serde(default)]
+    pub source: Option<ClipSource>,
 }
 
 impl PartialOrd for Metadata {
@@ -303,9 +426,57 @@ impl Ord for Metadata {
     }
 }
 
-fn encode_as_png(width: usize, height: usize, bytes: &[u8]) -> Result<Vec<u8>, Error> {
-    let (width, height) =
-        (u32::try_from(width).unwrap_or_default(), u32::try_from(height).unwrap_or_default());
+/// Image formats which are kept in their original encoding, the others are
+/// kept as rich content.
+const SUPPORTED_IMAGE_SUBTYPES: &[&str] = &["png", "jpeg", "gif", "bmp", "webp", "avif", "svg+xml"];
+
+fn is_supported_image(mime: &mime::Mime) -> bool {
+    mime.type_() == mime::IMAGE
+        && SUPPORTED_IMAGE_SUBTYPES.iter().any(|subtype| mime.subtype() == *subtype)
+}
+
+/// Returns the format of an image which can be decoded, `None` for images
+/// which are only passed through, e.g. SVG and AVIF.
+fn decodable_format(mime: &mime::Mime) -> Option<image::ImageFormat> {
+    match (mime.type_(), mime.subtype().as_str()) {
+        (mime::IMAGE, "png") => Some(image::ImageFormat::Png),
+        (mime::IMAGE, "jpeg") => Some(image::ImageFormat::Jpeg),
+        (mime::IMAGE, "gif") => Some(image::ImageFormat::Gif),
+        (mime::IMAGE, "bmp") => Some(image::ImageFormat::Bmp),
+        (mime::IMAGE, "webp") => Some(image::ImageFormat::WebP),
+        _ => None,
+    }
+}
+
+fn image_reader<'a>(
+    mime: &mime::Mime,
+    data: &'a [u8],
+) -> Option<image::io::Reader<std::io::Cursor<&'a [u8]>>> {
+    decodable_format(mime).map(|format| {
+        let mut reader = image::io::Reader::new(std::io::Cursor::new(data));
+        reader.set_format(format);
+        reader
+    })
+}
+
+pub fn image_dimensions(mime: &mime::Mime, data: &[u8]) -> Result<Option<(u32, u32)>, Error> {
+    if data.is_empty() {
+        return Err(Error::EmptyImage);
+    }
+    image_reader(mime, data)
+        .map(|reader| reader.into_dimensions().context(ConvertImageSnafu {}))
+        .transpose()
+}
+
+pub fn decode_image(mime: &mime::Mime, data: &[u8]) -> Result<image::RgbaImage, Error> {
+    if data.is_empty() {
+        return Err(Error::EmptyImage);
+    }
+    let reader = image_reader(mime, data).ok_or(Error::FormatNotAvailable)?;
+    reader.decode().map(image::DynamicImage::into_rgba8).context(ConvertImageSnafu {})
+}
+
+pub fn encode_as_png(width: u32, height: u32, bytes: &[u8]) -> Result<Vec<u8>, Error> {
     if bytes.is_empty() || width == 0 || height == 0 {
         return Err(Error::EmptyImage);
     }
@@ -330,3 +501,79 @@ pub enum Error {
     #[snafu(display("Error occurs while converting image, error: {source}"))]
     ConvertImage { source: image::ImageError },
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use bytes::Bytes;
+
+    use crate::{ClipEntry, ClipboardContent, ClipboardKind, Representations};
+
+    fn png_pixel() -> Vec<u8> { super::encode_as_png(1, 1, &[0, 0, 0, 255]).unwrap() }
+
+    #[test]
+    fn test_stable_id() {
+        // identifiers are printed and persisted, they must never change
+        let text = ClipboardContent::Plaintext("Clipcat".to_string());
+        assert_eq!(ClipEntry::compute_id(&text), 5_584_357_458_077_692_540);
+
+        let image =
+            ClipboardContent::Image { mime: mime::IMAGE_PNG, bytes: vec![0, 0, 0, 255].into() };
+        assert_eq!(ClipEntry::compute_id(&image), 2_491_674_421_909_421_535);
+
+        let rich = ClipboardContent::Rich(
+            [
+                ("text/html".to_string(), "<b>Clipcat</b>".into()),
+                ("text/plain".to_string(), "Clipcat".into()),
+            ]
+            .into(),
+        );
+        assert_eq!(ClipEntry::compute_id(&rich), 10_507_470_425_388_831_375);
+        assert_ne!(ClipEntry::compute_id(&rich), ClipEntry::compute_id(&text));
+    }
+
+    #[test]
+    fn test_keep_original_image() {
+        let png = png_pixel();
+        let clip = ClipEntry::new(&png, &mime::IMAGE_PNG, ClipboardKind::Clipboard, None).unwrap();
+        assert_eq!(clip.mime(), mime::IMAGE_PNG);
+        assert_eq!(clip.encoded().unwrap(), png);
+        assert_eq!(clip.to_clipboard_content().image_dimensions(), Some((1, 1)));
+        assert_eq!(clip.to_clipboard_content().to_png().unwrap(), png);
+
+        // images which could not be decoded are passed through
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="1" height="1"/>"#;
+        let mime = "image/svg+xml".parse::<mime::Mime>().unwrap();
+        let clip = ClipEntry::new(svg, &mime, ClipboardKind::Clipboard, None).unwrap();
+        assert_eq!(clip.mime(), mime);
+        assert_eq!(clip.encoded().unwrap(), svg);
+        assert_eq!(clip.to_clipboard_content().image_dimensions(), None);
+        assert!(clip.to_clipboard_content().to_png().is_err());
+
+        assert!(ClipEntry::new(b"not an image", &mime::IMAGE_PNG, ClipboardKind::Clipboard, None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_legacy_image_id() {
+        // the way images were kept and hashed before keeping original bytes
+        #[allow(dead_code)]
+        #[derive(Hash)]
+        enum LegacyContent {
+            Plaintext(String),
+            Image { width: usize, height: usize, bytes: Bytes },
+            Rich(Representations),
+        }
+
+        let legacy = LegacyContent::Image { width: 1, height: 1, bytes: vec![0, 0, 0, 255].into() };
+        let mut hasher = DefaultHasher::new();
+        legacy.hash(&mut hasher);
+
+        let image = ClipboardContent::Image { mime: mime::IMAGE_PNG, bytes: png_pixel().into() };
+        assert_eq!(ClipEntry::compute_legacy_id(&image), hasher.finish());
+    }
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ClipEntryMetadata;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Inserted(ClipEntryMetadata),
+
+    Removed { id: u64 },
+
+    Marked(ClipEntryMetadata),
+
+    Pinned(ClipEntryMetadata),
+
+    Unpinned(ClipEntryMetadata),
+
+    Cleared,
+}
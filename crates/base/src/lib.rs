@@ -1,11 +1,15 @@
 pub mod config;
 mod entry;
+mod event;
 mod kind;
+mod search;
 pub mod serde;
+mod source;
 pub mod utils;
 mod watcher_state;
 
 use std::{
+    collections::BTreeMap,
     net::{IpAddr, Ipv4Addr},
     path::PathBuf,
 };
@@ -16,7 +20,10 @@ use lazy_static::lazy_static;
 
 pub use self::{
     entry::{Entry as ClipEntry, Error as ClipEntryError, Metadata as ClipEntryMetadata},
+    event::Event as ClipEvent,
     kind::Kind as ClipboardKind,
+    search::{Mode as SearchMode, Query as SearchQuery},
+    source::Source as ClipSource,
     watcher_state::WatcherState as ClipboardWatcherState,
 };
 
@@ -49,12 +56,22 @@ pub const MENU_CONFIG_NAME: &str = "clipcat-menu.toml";
 
 pub const NOTIFY_PROGRAM_NAME: &str = "clipcat-notify";
 
+// formats, X11 target atoms or Wayland MIME types, which are offered along with
+// sensitive content, e.g. passwords copied from KeePassXC or KWallet
+pub const DEFAULT_SENSITIVE_MIME_TYPES: &[&str] = &["x-kde-passwordManagerHint"];
+
 pub const DEFAULT_GRPC_PORT: u16 = 45045;
 pub const DEFAULT_GRPC_HOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
 
 pub const DEFAULT_WEBUI_PORT: u16 = 45046;
 pub const DEFAULT_WEBUI_HOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
 
+pub const DEFAULT_REST_PORT: u16 = 45047;
+pub const DEFAULT_REST_HOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+pub const DEFAULT_DBUS_NAME: &str = "org.clipcat.Daemon";
+pub const DBUS_OBJECT_PATH: &str = "/org/clipcat/Daemon";
+
 pub const DEFAULT_MENU_PROMPT: &str = "Clipcat";
 
 lazy_static::lazy_static! {
@@ -64,10 +81,20 @@ pub static ref PROJECT_CONFIG_DIR: PathBuf = ProjectDirs::from("", PROJECT_NAME,
             .to_path_buf();
 }
 
+/// Representations of content offered in several formats at once, keyed by
+/// MIME type, e.g. `text/html` along with `text/plain` copied from a browser.
+pub type Representations = BTreeMap<String, Bytes>;
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum ClipboardContent {
     Plaintext(String),
-    Image { width: usize, height: usize, bytes: Bytes },
+    /// Image in its original encoding, e.g. PNG or JPEG, pixels are decoded
+    /// only when they are required.
+    Image {
+        mime: mime::Mime,
+        bytes: Bytes,
+    },
+    Rich(Representations),
 }
 
 impl Default for ClipboardContent {
@@ -80,6 +107,7 @@ impl ClipboardContent {
         match self {
             Self::Plaintext(s) => s.is_empty(),
             Self::Image { bytes, .. } => bytes.is_empty(),
+            Self::Rich(representations) => representations.values().all(Bytes::is_empty),
         }
     }
 
@@ -88,6 +116,7 @@ impl ClipboardContent {
         match self {
             Self::Plaintext(s) => s.len(),
             Self::Image { bytes, .. } => bytes.len(),
+            Self::Rich(representations) => representations.values().map(Bytes::len).sum(),
         }
     }
 
@@ -97,12 +126,104 @@ impl ClipboardContent {
     #[inline]
     pub const fn is_image(&self) -> bool { matches!(&self, Self::Image { .. }) }
 
+    #[inline]
+    pub const fn is_rich(&self) -> bool { matches!(&self, Self::Rich(_)) }
+
+    /// MIME type of the primary representation, the one which is previewed
+    /// and returned if only one representation is requested.
+    pub fn mime(&self) -> mime::Mime {
+        match self {
+            Self::Plaintext(_) => mime::TEXT_PLAIN_UTF_8,
+            Self::Image { mime, .. } => mime.clone(),
+            Self::Rich(representations) => primary_representation(representations)
+                .and_then(|(mime, _)| mime.parse().ok())
+                .unwrap_or(mime::APPLICATION_OCTET_STREAM),
+        }
+    }
+
+    /// Returns the text of content, rich content is represented by its plain
+    /// text representation if there is one.
+    pub fn text(&self) -> Option<String> {
+        match self {
+            Self::Plaintext(text) => Some(text.clone()),
+            Self::Image { .. } => None,
+            Self::Rich(representations) => primary_representation(representations)
+                .filter(|(mime, _)| mime.starts_with("text/"))
+                .map(|(_, bytes)| String::from_utf8_lossy(bytes).to_string()),
+        }
+    }
+
+    /// Returns the width and height of an image by reading its header only,
+    /// `None` if content is not an image or the format is not decodable, e.g.
+    /// SVG.
+    pub fn image_dimensions(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::Image { mime, bytes } => entry::image_dimensions(mime, bytes).ok().flatten(),
+            Self::Plaintext(_) | Self::Rich(_) => None,
+        }
+    }
+
+    /// Decodes an image into RGBA pixels.
+    ///
+    /// # Errors
+    /// This function returns an error if content is not an image or the image
+    /// could not be decoded.
+    pub fn decode_image(&self) -> Result<image::RgbaImage, ClipEntryError> {
+        match self {
+            Self::Image { mime, bytes } => entry::decode_image(mime, bytes),
+            Self::Plaintext(_) | Self::Rich(_) => Err(ClipEntryError::FormatNotAvailable),
+        }
+    }
+
+    /// Returns an image encoded as PNG, the original data is returned as it
+    /// is if the image is already a PNG.
+    ///
+    /// # Errors
+    /// This function returns an error if content is not an image or the image
+    /// could not be converted.
+    pub fn to_png(&self) -> Result<Bytes, ClipEntryError> {
+        match self {
+            Self::Image { mime, bytes } if *mime == mime::IMAGE_PNG => Ok(bytes.clone()),
+            _ => {
+                let image = self.decode_image()?;
+                let (width, height) = image.dimensions();
+                entry::encode_as_png(width, height, image.as_raw()).map(Bytes::from)
+            }
+        }
+    }
+
     pub fn basic_information(&self) -> String {
-        let (content_type, size) = match &self {
-            Self::Plaintext(text) => (mime::TEXT_PLAIN_UTF_8, text.len()),
-            Self::Image { width: _, height: _, bytes } => (mime::IMAGE_PNG, bytes.len()),
-        };
-        let size = humansize::format_size(size, humansize::BINARY);
-        format!("{content_type}, {size}")
+        let size = humansize::format_size(self.len(), humansize::BINARY);
+        match self {
+            Self::Rich(representations) if representations.len() > 1 => {
+                format!(
+                    "{content_type} (+{n} formats), {size}",
+                    content_type = self.mime(),
+                    n = representations.len() - 1
+                )
+            }
+            _ => format!("{content_type}, {size}", content_type = self.mime()),
+        }
     }
 }
+
+/// Returns the representation which is preferred for previewing, plain text
+/// is preferred over other text, and text is preferred over anything else.
+#[must_use]
+pub fn primary_representation(representations: &Representations) -> Option<(&str, &Bytes)> {
+    let rank = |mime: &str| {
+        if mime.starts_with("text/plain") {
+            0
+        } else if mime.starts_with("text/") {
+            1
+        } else if mime.starts_with("image/") {
+            2
+        } else {
+            3
+        }
+    };
+    representations
+        .iter()
+        .min_by_key(|(mime, _)| rank(mime))
+        .map(|(mime, bytes)| (mime.as_str(), bytes))
+}
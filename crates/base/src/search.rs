@@ -0,0 +1,61 @@
+use time::OffsetDateTime;
+
+use crate::{ClipEntry, ClipboardKind};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Mode {
+    #[default]
+    Substring,
+    Regex,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Query {
+    pub pattern: String,
+
+    pub mode: Mode,
+
+    pub case_sensitive: bool,
+
+    // match all kinds if empty
+    pub kinds: Vec<ClipboardKind>,
+
+    // match all MIME types if empty, `image/*` matches all images
+    pub mimes: Vec<mime::Mime>,
+
+    pub since: Option<OffsetDateTime>,
+
+    pub until: Option<OffsetDateTime>,
+
+    pub offset: usize,
+
+    pub limit: Option<usize>,
+
+    pub preview_length: usize,
+}
+
+impl Query {
+    /// Checks whether the attributes of `clip` satisfy the filters,
+    /// the content of `clip` is not inspected.
+    #[must_use]
+    pub fn filter_attributes(&self, clip: &ClipEntry) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&clip.kind()) {
+            return false;
+        }
+
+        if !self.mimes.is_empty() {
+            let clip_mime = clip.mime();
+            let matched = self.mimes.iter().any(|mime| {
+                mime.type_() == clip_mime.type_()
+                    && (mime.subtype() == mime::STAR || mime.subtype() == clip_mime.subtype())
+            });
+            if !matched {
+                return false;
+            }
+        }
+
+        let timestamp = clip.timestamp();
+        self.since.map_or(true, |since| since <= timestamp)
+            && self.until.map_or(true, |until| timestamp <= until)
+    }
+}
@@ -0,0 +1,47 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// The application which owned the selection when a clip was captured.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Source {
+    /// Class of the owner window, e.g. `firefox`, it is the class part of
+    /// `WM_CLASS` on X11.
+    #[serde(default)]
+    pub class: Option<String>,
+
+    /// Process identifier of the owner.
+    #[serde(default)]
+    pub pid: Option<u32>,
+
+    /// Name of the owner process, e.g. `keepassxc`.
+    #[serde(default)]
+    pub process_name: Option<String>,
+}
+
+impl Source {
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.class.is_none() && self.pid.is_none() && self.process_name.is_none()
+    }
+
+    /// Returns the name of application, the window class is preferred over
+    /// the process name.
+    #[inline]
+    #[must_use]
+    pub fn application(&self) -> Option<&str> {
+        self.class.as_deref().or(self.process_name.as_deref())
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.application(), self.pid) {
+            (Some(application), Some(pid)) => write!(f, "{application} ({pid})"),
+            (Some(application), None) => f.write_str(application),
+            (None, Some(pid)) => write!(f, "({pid})"),
+            (None, None) => f.write_str("unknown"),
+        }
+    }
+}
@@ -6,6 +6,17 @@ pub enum WatcherState {
     Disabled = 1,
 }
 
+impl WatcherState {
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &str {
+        match self {
+            Self::Enabled => "Enabled",
+            Self::Disabled => "Disabled",
+        }
+    }
+}
+
 impl From<i32> for WatcherState {
     fn from(state: i32) -> Self {
         match state {
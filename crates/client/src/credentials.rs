@@ -0,0 +1,52 @@
+use std::{fmt, path::PathBuf};
+
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    service::Interceptor,
+    Request, Status,
+};
+
+/// Credentials for a server which requires authentication or is served
+/// over TLS.
+#[derive(Clone, Default)]
+pub struct Credentials {
+    /// Access token which is sent as a bearer token.
+    pub access_token: Option<String>,
+
+    /// PEM encoded certificate of the authority which signs the certificate
+    /// of server, certificates trusted by the system are used if it is not
+    /// provided.
+    pub ca_certificate: Option<PathBuf>,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("access_token", &self.access_token.as_ref().map(|_| "<redacted>"))
+            .field("ca_certificate", &self.ca_certificate)
+            .finish()
+    }
+}
+
+/// Attaches the access token to every request.
+#[derive(Clone, Default)]
+pub struct Authorization(Option<MetadataValue<Ascii>>);
+
+impl Authorization {
+    pub const fn new(value: Option<MetadataValue<Ascii>>) -> Self { Self(value) }
+}
+
+impl fmt::Debug for Authorization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Authorization(<redacted>)")
+    }
+}
+
+impl Interceptor for Authorization {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(value) = &self.0 {
+            let _unused = request.metadata_mut().insert("authorization", value.clone());
+        }
+        Ok(request)
+    }
+}
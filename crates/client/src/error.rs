@@ -30,6 +30,15 @@ pub enum Error {
         source: tonic::transport::Error,
         backtrace: Backtrace,
     },
+
+    #[snafu(display("Could not read certificate of authority `{}`, error: {source}", path.display()))]
+    ReadCaCertificate { path: PathBuf, source: std::io::Error, backtrace: Backtrace },
+
+    #[snafu(display("Could not configure TLS, error: {source}"))]
+    ConfigureTls { source: tonic::transport::Error, backtrace: Backtrace },
+
+    #[snafu(display("Access token contains characters which are not allowed in HTTP headers"))]
+    InvalidAccessToken,
 }
 
 #[derive(Debug)]
@@ -101,6 +110,32 @@ impl fmt::Display for MarkClipError {
     }
 }
 
+#[derive(Debug)]
+pub enum PinClipError {
+    Status { source: tonic::Status, id: u64 },
+}
+
+impl fmt::Display for PinClipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Status { source, .. } => source.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum UnpinClipError {
+    Status { source: tonic::Status, id: u64 },
+}
+
+impl fmt::Display for UnpinClipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Status { source, .. } => source.fmt(f),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum RemoveClipError {
     Status { source: tonic::Status },
@@ -166,6 +201,32 @@ impl fmt::Display for ListClipError {
     }
 }
 
+#[derive(Debug)]
+pub enum SearchClipError {
+    Status { source: tonic::Status },
+}
+
+impl fmt::Display for SearchClipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Status { source } => source.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SubscribeEventError {
+    Status { source: tonic::Status },
+}
+
+impl fmt::Display for SubscribeEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Status { source } => source.fmt(f),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum EnableWatcherError {
     Status { source: tonic::Status },
@@ -1,51 +1,81 @@
+mod credentials;
 pub mod error;
 mod manager;
 mod system;
 mod watcher;
 
-use std::path::Path;
+use std::{path::Path, str::FromStr};
 
 use snafu::ResultExt;
 use tokio::net::UnixStream;
+use tonic::{
+    codegen::InterceptedService,
+    metadata::MetadataValue,
+    transport::{Certificate, Channel, ClientTlsConfig},
+};
 
+use self::credentials::Authorization;
 pub use self::{
+    credentials::Credentials,
     error::{Error, Result},
-    manager::Manager,
+    manager::{EventStream, Manager},
     system::System,
     watcher::Watcher,
 };
 
 #[derive(Clone, Debug)]
 pub struct Client {
-    channel: tonic::transport::Channel,
+    channel: InterceptedService<Channel, Authorization>,
 }
 
 impl Client {
     /// # Errors
     pub async fn new(grpc_endpoint: http::Uri) -> Result<Self> {
+        Self::with_credentials(grpc_endpoint, &Credentials::default()).await
+    }
+
+    /// # Errors
+    pub async fn with_credentials(
+        grpc_endpoint: http::Uri,
+        credentials: &Credentials,
+    ) -> Result<Self> {
         tracing::info!("Connect to server via endpoint `{}`", grpc_endpoint);
         let scheme = grpc_endpoint.scheme();
-        if scheme == Some(&http::uri::Scheme::HTTP) {
-            Self::connect_http(grpc_endpoint).await
+        if scheme == Some(&http::uri::Scheme::HTTP) || scheme == Some(&http::uri::Scheme::HTTPS) {
+            Self::connect_http(grpc_endpoint, credentials).await
         } else {
-            Self::connect_local_socket(grpc_endpoint.path()).await
+            Self::connect_local_socket(grpc_endpoint.path(), credentials).await
         }
     }
 
+    /// Connects to server via HTTP, TLS is used if the scheme of
+    /// `grpc_endpoint` is `https` or a certificate of authority is provided.
+    ///
     /// # Errors
     ///
     /// This function will an error if the server is not connected.
     // SAFETY: it will never panic because `grpc_endpoint` is a valid URL
     #[allow(clippy::missing_panics_doc)]
-    pub async fn connect_http(grpc_endpoint: http::Uri) -> Result<Self> {
-        let channel = tonic::transport::Endpoint::from_shared(grpc_endpoint.to_string())
-            .expect("`grpc_endpoint` is a valid URL; qed")
-            .connect()
-            .await
-            .with_context(|_| error::ConnectToClipcatServerViaHttpSnafu {
-                endpoint: grpc_endpoint.clone(),
-            })?;
-        Ok(Self { channel })
+    pub async fn connect_http(grpc_endpoint: http::Uri, credentials: &Credentials) -> Result<Self> {
+        let authorization = new_authorization(credentials)?;
+        let mut endpoint = tonic::transport::Endpoint::from_shared(grpc_endpoint.to_string())
+            .expect("`grpc_endpoint` is a valid URL; qed");
+        if grpc_endpoint.scheme() == Some(&http::uri::Scheme::HTTPS)
+            || credentials.ca_certificate.is_some()
+        {
+            let mut tls_config = ClientTlsConfig::new();
+            if let Some(path) = &credentials.ca_certificate {
+                let pem = tokio::fs::read(path)
+                    .await
+                    .context(error::ReadCaCertificateSnafu { path: path.clone() })?;
+                tls_config = tls_config.ca_certificate(Certificate::from_pem(pem));
+            }
+            endpoint = endpoint.tls_config(tls_config).context(error::ConfigureTlsSnafu)?;
+        }
+        let channel = endpoint.connect().await.with_context(|_| {
+            error::ConnectToClipcatServerViaHttpSnafu { endpoint: grpc_endpoint.clone() }
+        })?;
+        Ok(Self { channel: InterceptedService::new(channel, authorization) })
     }
 
     /// # Errors
@@ -53,10 +83,11 @@ impl Client {
     /// This function will an error if the server is not connected.
     // SAFETY: it will never panic because `dummy_uri` is a valid URL
     #[allow(clippy::missing_panics_doc)]
-    pub async fn connect_local_socket<P>(socket_path: P) -> Result<Self>
+    pub async fn connect_local_socket<P>(socket_path: P, credentials: &Credentials) -> Result<Self>
     where
         P: AsRef<Path> + Send,
     {
+        let authorization = new_authorization(credentials)?;
         let socket_path = socket_path.as_ref().to_path_buf();
         // We will ignore this uri because uds do not use it
         let dummy_uri = "http://[::]:50051";
@@ -74,6 +105,18 @@ impl Client {
             .with_context(|_| error::ConnectToClipcatServerViaLocalSocketSnafu {
                 socket: socket_path.clone(),
             })?;
-        Ok(Self { channel })
+        Ok(Self { channel: InterceptedService::new(channel, authorization) })
     }
 }
+
+fn new_authorization(credentials: &Credentials) -> Result<Authorization> {
+    let value = credentials
+        .access_token
+        .as_ref()
+        .map(|token| {
+            MetadataValue::from_str(&format!("Bearer {token}"))
+                .map_err(|_err| Error::InvalidAccessToken)
+        })
+        .transpose()?;
+    Ok(Authorization::new(value))
+}
@@ -1,16 +1,22 @@
+use std::{pin::Pin, time::Duration};
+
 use async_trait::async_trait;
-use clipcat_base::{ClipEntry, ClipEntryMetadata, ClipboardKind};
+use clipcat_base::{ClipEntry, ClipEntryMetadata, ClipEvent, ClipboardKind, SearchQuery};
 use clipcat_proto as proto;
+use futures::{Stream, StreamExt};
 use tonic::Request;
 
 use crate::{
     error::{
         BatchRemoveClipError, ClearClipError, GetClipError, GetCurrentClipError, GetLengthError,
-        InsertClipError, ListClipError, MarkClipError, RemoveClipError, UpdateClipError,
+        InsertClipError, ListClipError, MarkClipError, PinClipError, RemoveClipError,
+        SearchClipError, SubscribeEventError, UnpinClipError, UpdateClipError,
     },
     Client,
 };
 
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<ClipEvent, SubscribeEventError>> + Send>>;
+
 #[async_trait]
 pub trait Manager {
     async fn get(&self, id: u64) -> Result<ClipEntry, GetClipError>;
@@ -27,11 +33,26 @@ pub trait Manager {
 
     async fn mark(&self, id: u64, kind: ClipboardKind) -> Result<bool, MarkClipError>;
 
+    async fn pin(&self, id: u64) -> Result<bool, PinClipError>;
+
+    async fn unpin(&self, id: u64) -> Result<bool, UnpinClipError>;
+
     async fn insert(
         &self,
         data: &[u8],
         mime: mime::Mime,
         clipboard_kind: ClipboardKind,
+    ) -> Result<u64, InsertClipError> {
+        self.insert_with_ttl(data, mime, clipboard_kind, None).await
+    }
+
+    /// Insert a clip which is removed after `ttl`.
+    async fn insert_with_ttl(
+        &self,
+        data: &[u8],
+        mime: mime::Mime,
+        clipboard_kind: ClipboardKind,
+        ttl: Option<Duration>,
     ) -> Result<u64, InsertClipError>;
 
     async fn insert_clipboard(
@@ -48,13 +69,26 @@ pub trait Manager {
 
     async fn length(&self) -> Result<usize, GetLengthError>;
 
-    async fn list(&self, preview_length: usize) -> Result<Vec<ClipEntryMetadata>, ListClipError>;
+    /// Lists clips of the specified kinds, clips of all kinds are listed if
+    /// `kinds` is empty.
+    async fn list(
+        &self,
+        preview_length: usize,
+        kinds: &[ClipboardKind],
+    ) -> Result<Vec<ClipEntryMetadata>, ListClipError>;
+
+    async fn search(
+        &self,
+        query: SearchQuery,
+    ) -> Result<(Vec<ClipEntryMetadata>, usize), SearchClipError>;
 
     async fn remove(&self, id: u64) -> Result<bool, RemoveClipError>;
 
     async fn batch_remove(&self, ids: &[u64]) -> Result<Vec<u64>, BatchRemoveClipError>;
 
     async fn clear(&self) -> Result<(), ClearClipError>;
+
+    async fn subscribe(&self, preview_length: usize) -> Result<EventStream, SubscribeEventError>;
 }
 
 #[async_trait]
@@ -109,17 +143,39 @@ impl Manager for Client {
         Ok(ok)
     }
 
-    async fn insert(
+    async fn pin(&self, id: u64) -> Result<bool, PinClipError> {
+        let proto::PinResponse { ok } = proto::ManagerClient::new(self.channel.clone())
+            .pin(Request::new(proto::PinRequest { id }))
+            .await
+            .map_err(|source| PinClipError::Status { source, id })?
+            .into_inner();
+        Ok(ok)
+    }
+
+    async fn unpin(&self, id: u64) -> Result<bool, UnpinClipError> {
+        let proto::UnpinResponse { ok } = proto::ManagerClient::new(self.channel.clone())
+            .unpin(Request::new(proto::UnpinRequest { id }))
+            .await
+            .map_err(|source| UnpinClipError::Status { source, id })?
+            .into_inner();
+        Ok(ok)
+    }
+
+    async fn insert_with_ttl(
         &self,
         data: &[u8],
         mime: mime::Mime,
         clipboard_kind: ClipboardKind,
+        ttl: Option<Duration>,
     ) -> Result<u64, InsertClipError> {
         let proto::InsertResponse { id } = proto::ManagerClient::new(self.channel.clone())
             .insert(Request::new(proto::InsertRequest {
                 kind: clipboard_kind.into(),
                 data: data.to_owned(),
                 mime: mime.essence_str().to_owned(),
+                // round up, a short time-to-live should not become "never"
+                ttl_ms: ttl
+                    .map_or(0, |ttl| u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1)),
             }))
             .await
             .map_err(|source| InsertClipError::Status { source })?
@@ -136,10 +192,15 @@ impl Manager for Client {
         Ok(usize::try_from(length).unwrap_or(0))
     }
 
-    async fn list(&self, preview_length: usize) -> Result<Vec<ClipEntryMetadata>, ListClipError> {
+    async fn list(
+        &self,
+        preview_length: usize,
+        kinds: &[ClipboardKind],
+    ) -> Result<Vec<ClipEntryMetadata>, ListClipError> {
         let mut list: Vec<_> = proto::ManagerClient::new(self.channel.clone())
             .list(Request::new(proto::ListRequest {
                 preview_length: u64::try_from(preview_length).unwrap_or(30),
+                kinds: kinds.iter().map(|&kind| proto::ClipboardKind::from(kind).into()).collect(),
             }))
             .await
             .map_err(|source| ListClipError::Status { source })?
@@ -152,6 +213,20 @@ impl Manager for Client {
         Ok(list)
     }
 
+    async fn search(
+        &self,
+        query: SearchQuery,
+    ) -> Result<(Vec<ClipEntryMetadata>, usize), SearchClipError> {
+        let proto::SearchResponse { metadata, total } =
+            proto::ManagerClient::new(self.channel.clone())
+                .search(Request::new(proto::SearchRequest::from(query)))
+                .await
+                .map_err(|source| SearchClipError::Status { source })?
+                .into_inner();
+        let metadata = metadata.into_iter().map(ClipEntryMetadata::from).collect();
+        Ok((metadata, usize::try_from(total).unwrap_or(usize::MAX)))
+    }
+
     async fn remove(&self, id: u64) -> Result<bool, RemoveClipError> {
         let proto::RemoveResponse { ok } = proto::ManagerClient::new(self.channel.clone())
             .remove(Request::new(proto::RemoveRequest { id }))
@@ -177,4 +252,21 @@ impl Manager for Client {
             .map(|_| ())
             .map_err(|source| ClearClipError::Status { source })
     }
+
+    async fn subscribe(&self, preview_length: usize) -> Result<EventStream, SubscribeEventError> {
+        let stream = proto::ManagerClient::new(self.channel.clone())
+            .subscribe(Request::new(proto::SubscribeRequest {
+                preview_length: u64::try_from(preview_length).unwrap_or(30),
+            }))
+            .await
+            .map_err(|source| SubscribeEventError::Status { source })?
+            .into_inner()
+            .filter_map(|event| async move {
+                match event {
+                    Ok(proto::ClipEvent { event }) => event.map(|event| Ok(event.into())),
+                    Err(source) => Some(Err(SubscribeEventError::Status { source })),
+                }
+            });
+        Ok(Box::pin(stream))
+    }
 }
@@ -1,9 +1,11 @@
+use std::collections::HashSet;
+
 use clipcat_base::ClipboardContent;
 use clipcat_clipboard::{Clipboard, ClipboardKind, ClipboardLoad, Error};
 use snafu::ErrorCompat;
 
 fn main() -> Result<(), Error> {
-    let clipboard = Clipboard::new(ClipboardKind::Clipboard, Vec::new())?;
+    let clipboard = Clipboard::new(ClipboardKind::Clipboard, HashSet::new(), Vec::new())?;
     match clipboard.load(None) {
         Ok(ClipboardContent::Plaintext(text)) => {
             println!("size: {}", text.len());
@@ -14,6 +16,12 @@ fn main() -> Result<(), Error> {
             println!("image, size: {}", bytes.len());
             Ok(())
         }
+        Ok(ClipboardContent::Rich(representations)) => {
+            for (mime, data) in representations {
+                println!("{mime}, size: {}", data.len());
+            }
+            Ok(())
+        }
         Err(Error::Empty) => {
             eprintln!("error: clipboard is empty");
             Err(Error::Empty)
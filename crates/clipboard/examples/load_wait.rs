@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use clipcat_base::ClipboardContent;
 use clipcat_clipboard::{Clipboard, ClipboardKind, ClipboardLoadWait, Error};
 use snafu::ErrorCompat;
@@ -6,7 +8,7 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 fn main() -> Result<(), Error> {
     init_tracing();
 
-    let clipboard = Clipboard::new(ClipboardKind::Clipboard, Vec::new())?;
+    let clipboard = Clipboard::new(ClipboardKind::Clipboard, HashSet::new(), Vec::new())?;
     println!("Waiting for new clipboard event...");
     println!("You can to copy some text from other window...");
     for _ in 0..10 {
@@ -18,6 +20,11 @@ fn main() -> Result<(), Error> {
             Ok(ClipboardContent::Image { bytes, .. }) => {
                 println!("image, size: {}", bytes.len());
             }
+            Ok(ClipboardContent::Rich(representations)) => {
+                for (mime, data) in representations {
+                    println!("{mime}, size: {}", data.len());
+                }
+            }
             Err(Error::Empty) => {
                 eprintln!("error: clipboard is empty");
             }
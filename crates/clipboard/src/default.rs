@@ -1,6 +1,9 @@
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 use arboard::{ClearExtLinux, GetExtLinux, SetExtLinux};
@@ -9,13 +12,18 @@ use clipcat_base::ClipboardContent;
 
 use crate::{
     listener::{WaylandListener, X11Listener},
+    rich,
     traits::EventObserver,
-    ClipboardKind, ClipboardLoad, ClipboardStore, ClipboardSubscribe, Error, Subscriber,
+    ClipboardKind, ClipboardLoad, ClipboardStore, ClipboardSubscribe, Error, ListenerKind,
+    Subscriber,
 };
 
 #[derive(Clone)]
 pub struct Clipboard {
     listener: Arc<dyn ClipboardSubscribe<Subscriber = Subscriber>>,
+    listener_kind: ListenerKind,
+    display_name: Option<String>,
+    kind: ClipboardKind,
     clipboard_kind: arboard::LinuxClipboardKind,
     clear_on_drop: Arc<AtomicBool>,
 }
@@ -24,37 +32,110 @@ impl Clipboard {
     /// # Errors
     pub fn new(
         clipboard_kind: ClipboardKind,
+        sensitive_mime_types: HashSet<String>,
         event_observers: Vec<Arc<dyn EventObserver>>,
     ) -> Result<Self, Error> {
-        let listener: Arc<dyn ClipboardSubscribe<Subscriber = Subscriber>> =
-            if let Ok(display_name) = std::env::var("WAYLAND_DISPLAY") {
+        let (listener, listener_kind, display_name): (
+            Arc<dyn ClipboardSubscribe<Subscriber = Subscriber>>,
+            _,
+            _,
+        ) = if let Ok(display_name) = std::env::var("WAYLAND_DISPLAY") {
+            tracing::info!(
+                "Build Wayland listener ({clipboard_kind}) with display `{display_name}`"
+            );
+            (
+                Arc::new(WaylandListener::new(
+                    display_name.clone(),
+                    clipboard_kind,
+                    sensitive_mime_types,
+                    event_observers,
+                )),
+                ListenerKind::Wayland,
+                Some(display_name),
+            )
+        } else {
+            let display_name = std::env::var("DISPLAY").ok();
+            if let Some(display_name) = &display_name {
                 tracing::info!(
-                    "Build Wayland listener ({clipboard_kind}) with display `{display_name}`"
+                    "Build X11 listener ({clipboard_kind}) with display `{display_name}`"
                 );
-                Arc::new(WaylandListener::new(clipboard_kind)?)
-            } else {
-                match std::env::var("DISPLAY") {
-                    Ok(display_name) => {
-                        tracing::info!(
-                            "Build X11 listener ({clipboard_kind}) with display `{display_name}`"
-                        );
-                        Arc::new(X11Listener::new(
-                            Some(display_name),
-                            clipboard_kind,
-                            event_observers,
-                        )?)
-                    }
-                    Err(_) => Arc::new(X11Listener::new(None, clipboard_kind, event_observers)?),
-                }
-            };
+            }
+            (
+                Arc::new(X11Listener::new(
+                    display_name.clone(),
+                    clipboard_kind,
+                    sensitive_mime_types,
+                    event_observers,
+                )?),
+                ListenerKind::X11,
+                display_name,
+            )
+        };
 
         let clear_on_drop = Arc::new(AtomicBool::from(false));
+        let kind = clipboard_kind;
         let clipboard_kind = match clipboard_kind {
             ClipboardKind::Clipboard => arboard::LinuxClipboardKind::Clipboard,
             ClipboardKind::Primary => arboard::LinuxClipboardKind::Primary,
             ClipboardKind::Secondary => arboard::LinuxClipboardKind::Secondary,
         };
-        Ok(Self { listener, clipboard_kind, clear_on_drop })
+        Ok(Self { listener, listener_kind, display_name, kind, clipboard_kind, clear_on_drop })
+    }
+
+    fn load_rich(&self) -> Result<Option<clipcat_base::Representations>, rich::Error> {
+        match self.listener_kind {
+            ListenerKind::X11 => rich::x11::load(self.display_name.as_deref(), self.kind),
+            ListenerKind::Wayland => rich::wayland::load(self.kind),
+        }
+    }
+
+    fn load_format(&self, mime: &str) -> Result<Option<Bytes>, rich::Error> {
+        match self.listener_kind {
+            ListenerKind::X11 => {
+                rich::x11::load_format(self.display_name.as_deref(), self.kind, mime)
+            }
+            ListenerKind::Wayland => rich::wayland::load_format(self.kind, mime).map(Some),
+        }
+    }
+
+    fn load_any(&self) -> Result<ClipboardContent, Error> {
+        match self.load_rich() {
+            Ok(Some(representations)) => return Ok(ClipboardContent::Rich(representations)),
+            Ok(None) => {}
+            Err(err) => tracing::warn!("Could not load rich content, error: {err}"),
+        }
+        self.load_text().map_or_else(|_| self.load(Some(mime::IMAGE_PNG)), Ok)
+    }
+
+    fn load_text(&self) -> Result<ClipboardContent, Error> {
+        match arboard::Clipboard::new()?.get().clipboard(self.clipboard_kind).text() {
+            Ok(text) => Ok(ClipboardContent::Plaintext(text)),
+            Err(arboard::Error::ClipboardNotSupported) => unreachable!(),
+            Err(err) => {
+                tracing::warn!("{err}");
+                Err(Error::Empty)
+            }
+        }
+    }
+
+    fn store_rich(&self, representations: &clipcat_base::Representations) -> Result<(), Error> {
+        match self.listener_kind {
+            ListenerKind::X11 => {
+                let join_handle =
+                    rich::x11::store(self.display_name.as_deref(), self.kind, representations)?;
+                let clear_on_drop = self.clear_on_drop.clone();
+                clear_on_drop.store(true, Ordering::Relaxed);
+                let _join_handle = std::thread::spawn(move || {
+                    let _unused = join_handle.join();
+                    clear_on_drop.store(false, Ordering::Relaxed);
+                });
+            }
+            ListenerKind::Wayland => {
+                rich::wayland::store(self.kind, representations)?;
+                self.clear_on_drop.store(true, Ordering::Relaxed);
+            }
+        }
+        Ok(())
     }
 }
 
@@ -67,38 +148,42 @@ impl ClipboardSubscribe for Clipboard {
 impl ClipboardLoad for Clipboard {
     fn load(&self, mime: Option<mime::Mime>) -> Result<ClipboardContent, Error> {
         match mime {
-            None => self
-                .load(Some(mime::TEXT_PLAIN_UTF_8))
-                .map_or_else(|_| self.load(Some(mime::IMAGE_PNG)), Ok),
+            None => self.load_any(),
             Some(mime) => {
-                let mut arboard = arboard::Clipboard::new()?;
-
                 if mime.type_() == mime::TEXT {
-                    match arboard.get().clipboard(self.clipboard_kind).text() {
-                        Ok(text) => Ok(ClipboardContent::Plaintext(text)),
-                        Err(arboard::Error::ClipboardNotSupported) => unreachable!(),
-                        Err(err) => {
-                            tracing::warn!("{err}");
-                            Err(Error::Empty)
+                    if mime.subtype() != mime::PLAIN {
+                        // formats like `text/html` are offered along with plain text, keep all
+                        // of them, `load_rich` returns `None` if there is plain text only
+                        match self.load_rich() {
+                            Ok(Some(representations)) => {
+                                return Ok(ClipboardContent::Rich(representations));
+                            }
+                            Ok(None) => {}
+                            Err(err) => tracing::warn!("Could not load rich content, error: {err}"),
                         }
                     }
+                    self.load_text()
                 } else if mime.type_() == mime::IMAGE {
-                    match arboard.get().clipboard(self.clipboard_kind).image() {
-                        Ok(arboard::ImageData { width, height, bytes }) => {
-                            Ok(ClipboardContent::Image {
-                                width,
-                                height,
-                                bytes: Bytes::from(bytes.into_owned()),
-                            })
+                    // keep the image in the format offered by the selection owner
+                    match self.load_format(mime.essence_str()) {
+                        Ok(Some(bytes)) if !bytes.is_empty() => {
+                            Ok(ClipboardContent::Image { mime, bytes })
                         }
-                        Err(arboard::Error::ClipboardNotSupported) => unreachable!(),
+                        Ok(_) => Err(Error::Empty),
                         Err(err) => {
                             tracing::warn!("{err}");
                             Err(Error::Empty)
                         }
                     }
                 } else {
-                    Err(Error::Empty)
+                    match self.load_rich() {
+                        Ok(Some(representations))
+                            if representations.contains_key(mime.essence_str()) =>
+                        {
+                            Ok(ClipboardContent::Rich(representations))
+                        }
+                        _ => Err(Error::Empty),
+                    }
                 }
             }
         }
@@ -108,6 +193,27 @@ impl ClipboardLoad for Clipboard {
 impl ClipboardStore for Clipboard {
     #[inline]
     fn store(&self, content: ClipboardContent) -> Result<(), Error> {
+        match &content {
+            ClipboardContent::Rich(representations) => return self.store_rich(representations),
+            ClipboardContent::Image { mime, bytes } => {
+                let mut representations = clipcat_base::Representations::from([(
+                    mime.essence_str().to_owned(),
+                    bytes.clone(),
+                )]);
+                if *mime != mime::IMAGE_PNG {
+                    // most applications paste images only in PNG
+                    match content.to_png() {
+                        Ok(png) => {
+                            let _unused = representations.insert(mime::IMAGE_PNG.to_string(), png);
+                        }
+                        Err(err) => tracing::debug!("Could not convert image to PNG, error: {err}"),
+                    }
+                }
+                return self.store_rich(&representations);
+            }
+            ClipboardContent::Plaintext(_) => {}
+        }
+
         let mut arboard = arboard::Clipboard::new()?;
         let clipboard_kind = self.clipboard_kind;
         let clear_on_drop = self.clear_on_drop.clone();
@@ -119,11 +225,7 @@ impl ClipboardStore for Clipboard {
                 ClipboardContent::Plaintext(text) => {
                     arboard.set().clipboard(clipboard_kind).wait().text(text)
                 }
-                ClipboardContent::Image { width, height, bytes } => arboard
-                    .set()
-                    .clipboard(clipboard_kind)
-                    .wait()
-                    .image(arboard::ImageData { width, height, bytes: bytes.to_vec().into() }),
+                ClipboardContent::Image { .. } | ClipboardContent::Rich(_) => unreachable!(),
             };
 
             clear_on_drop.store(false, Ordering::Relaxed);
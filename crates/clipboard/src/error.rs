@@ -12,6 +12,9 @@ pub enum Error {
     #[snafu(display("{error}"))]
     WaylandListener { error: crate::listener::wayland::Error },
 
+    #[snafu(display("{error}"))]
+    Rich { error: crate::rich::Error },
+
     #[snafu(display("Clipboard is empty"))]
     Empty,
 
@@ -33,3 +36,7 @@ impl From<crate::listener::x11::Error> for Error {
 impl From<crate::listener::wayland::Error> for Error {
     fn from(error: crate::listener::wayland::Error) -> Self { Self::WaylandListener { error } }
 }
+
+impl From<crate::rich::Error> for Error {
+    fn from(error: crate::rich::Error) -> Self { Self::Rich { error } }
+}
@@ -3,6 +3,7 @@ mod error;
 mod listener;
 mod mock;
 mod pubsub;
+mod rich;
 mod traits;
 
 pub use clipcat_base::ClipboardKind;
@@ -12,7 +13,7 @@ pub use self::{
     error::Error,
     listener::{WaylandListenerError, X11ListenerError},
     mock::Clipboard as MockClipboard,
-    pubsub::Subscriber,
+    pubsub::{Event as ClipboardEvent, Subscriber},
     traits::{
         EventObserver, Load as ClipboardLoad, LoadExt as ClipboardLoadExt,
         LoadWait as ClipboardLoadWait, Store as ClipboardStore, StoreExt as ClipboardStoreExt,
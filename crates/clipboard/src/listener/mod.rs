@@ -1,7 +1,126 @@
 pub mod wayland;
 pub mod x11;
 
+use std::collections::HashSet;
+
+use clipcat_base::ClipSource;
+
 pub use self::{
     wayland::{Error as WaylandListenerError, Listener as WaylandListener},
     x11::{Error as X11ListenerError, Listener as X11Listener},
 };
+use crate::pubsub;
+
+/// Filters out the selections offering any of the sensitive formats, password
+/// managers announce their secrets with hints like `x-kde-passwordManagerHint`.
+#[derive(Clone, Debug, Default)]
+struct ClipFilter {
+    sensitive_formats: HashSet<String>,
+}
+
+impl ClipFilter {
+    const fn new(sensitive_formats: HashSet<String>) -> Self { Self { sensitive_formats } }
+
+    #[inline]
+    fn is_sensitive<S>(&self, formats: &[S]) -> bool
+    where
+        S: AsRef<str>,
+    {
+        formats.iter().any(|format| self.sensitive_formats.contains(format.as_ref()))
+    }
+}
+
+/// Notifies subscribers with the most preferred format among the formats
+/// offered by the owner of selection.
+fn notify_preferred_format(
+    notifier: &pubsub::Publisher,
+    formats: Vec<String>,
+    source: Option<ClipSource>,
+) {
+    if let Some(mime) = preferred_format(formats) {
+        notifier.notify_all(mime, source);
+    }
+}
+
+fn preferred_format(mut formats: Vec<String>) -> Option<mime::Mime> {
+    // sort available formats by type, some applications provide
+    // image in `text/html` format, we prefer to use `image`,
+    // rich text is preferred to plain text which is offered along with it,
+    // the order of the owner is kept among formats of the same rank
+    formats.sort_by_key(|format| {
+        if format.starts_with("image/png") {
+            1
+        } else if format.starts_with("image") {
+            2
+        } else if format.starts_with("text/plain") {
+            4
+        } else if format.starts_with("text") {
+            3
+        } else if format == "UTF8_STRING" {
+            5
+        } else {
+            u32::MAX
+        }
+    });
+
+    formats.into_iter().find_map(|format| {
+        if format == "UTF8_STRING" {
+            Some(mime::TEXT_PLAIN_UTF_8)
+        } else {
+            format.parse().ok()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{preferred_format, ClipFilter};
+
+    fn new_filter() -> ClipFilter {
+        ClipFilter::new(
+            clipcat_base::DEFAULT_SENSITIVE_MIME_TYPES
+                .iter()
+                .map(ToString::to_string)
+                .collect::<HashSet<_>>(),
+        )
+    }
+
+    #[test]
+    fn test_filter_password_manager_offer() {
+        let filter = new_filter();
+
+        // formats offered by KeePassXC on Wayland
+        let offer = ["text/plain;charset=utf-8", "text/plain", "x-kde-passwordManagerHint"];
+        assert!(filter.is_sensitive(&offer));
+
+        let offer = ["text/plain;charset=utf-8", "UTF8_STRING", "TEXT", "STRING"];
+        assert!(!filter.is_sensitive(&offer));
+
+        let offer: [&str; 0] = [];
+        assert!(!filter.is_sensitive(&offer));
+    }
+
+    #[test]
+    fn test_filter_without_sensitive_formats() {
+        let filter = ClipFilter::default();
+        assert!(!filter.is_sensitive(&["x-kde-passwordManagerHint"]));
+    }
+
+    #[test]
+    fn test_preferred_format() {
+        let formats = |formats: &[&str]| formats.iter().map(ToString::to_string).collect();
+
+        // formats offered by Firefox
+        let offer = ["text/plain;charset=utf-8", "UTF8_STRING", "text/html", "text/_moz_htmlinfo"];
+        assert_eq!(preferred_format(formats(&offer)), Some(mime::TEXT_HTML));
+        let offer = ["TARGETS", "text/plain;charset=utf-8", "text/plain", "UTF8_STRING"];
+        assert_eq!(preferred_format(formats(&offer)), Some(mime::TEXT_PLAIN_UTF_8));
+        let offer = ["TARGETS", "UTF8_STRING", "STRING"];
+        assert_eq!(preferred_format(formats(&offer)), Some(mime::TEXT_PLAIN_UTF_8));
+        let offer = ["text/html", "image/bmp", "image/png"];
+        assert_eq!(preferred_format(formats(&offer)), Some(mime::IMAGE_PNG));
+        assert_eq!(preferred_format(formats(&["TARGETS"])), None);
+    }
+}
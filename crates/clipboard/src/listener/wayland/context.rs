@@ -0,0 +1,403 @@
+use std::{
+    io,
+    os::{
+        fd::{AsFd, AsRawFd, RawFd},
+        unix::net::UnixStream,
+    },
+    path::PathBuf,
+};
+
+use parking_lot::Mutex;
+use snafu::ResultExt;
+use wayland_client::{
+    backend::WaylandError,
+    event_created_child,
+    globals::{registry_queue_init, BindError, GlobalListContents},
+    protocol::{wl_registry, wl_seat},
+    Connection, Dispatch, EventQueue, Proxy,
+};
+use wayland_protocols::ext::data_control::v1::client::{
+    ext_data_control_device_v1::{self, ExtDataControlDeviceV1},
+    ext_data_control_manager_v1::ExtDataControlManagerV1,
+    ext_data_control_offer_v1::{self, ExtDataControlOfferV1},
+};
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+};
+
+use crate::{
+    listener::wayland::{error, Error},
+    ClipboardKind,
+};
+
+/// MIME types announced by a data offer, all of them are announced before the
+/// offer is introduced as a selection.
+type OfferedMimeTypes = Mutex<Vec<String>>;
+
+#[derive(Debug)]
+pub enum SelectionEvent {
+    /// The selection is replaced by a new one which offers these MIME types.
+    Changed(Vec<String>),
+
+    /// The selection is cleared, either because its owner exits or because a
+    /// client clears it on purpose, they could not be told apart.
+    Cleared,
+}
+
+pub struct Context {
+    display_name: String,
+    clipboard_kind: ClipboardKind,
+    connection: Connection,
+    event_queue: EventQueue<State>,
+    state: State,
+}
+
+impl Context {
+    pub fn new(display_name: String, clipboard_kind: ClipboardKind) -> Result<Self, Error> {
+        let (connection, event_queue, state) = new_connection(&display_name, clipboard_kind)?;
+        Ok(Self { display_name, clipboard_kind, connection, event_queue, state })
+    }
+
+    pub fn reconnect(&mut self) -> Result<(), Error> {
+        let (connection, event_queue, state) =
+            new_connection(&self.display_name, self.clipboard_kind)?;
+        self.connection = connection;
+        self.event_queue = event_queue;
+        self.state = state;
+        Ok(())
+    }
+
+    /// Reads events which are ready on the connection, dispatches them and
+    /// returns the changes of selection.
+    ///
+    /// The caller should wait for the readiness of the connection before
+    /// calling this function, otherwise it returns without receiving any
+    /// events.
+    pub fn dispatch(&mut self) -> Result<Vec<SelectionEvent>, Error> {
+        let _unused = self
+            .event_queue
+            .dispatch_pending(&mut self.state)
+            .context(error::DispatchEventsSnafu)?;
+        if let Some(guard) = self.event_queue.prepare_read() {
+            match guard.read() {
+                Ok(_) => {}
+                Err(WaylandError::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => {}
+                Err(source) => return Err(Error::ReadEvents { source }),
+            }
+        }
+        let _unused = self
+            .event_queue
+            .dispatch_pending(&mut self.state)
+            .context(error::DispatchEventsSnafu)?;
+        self.flush()?;
+
+        if self.state.is_device_finished {
+            return Err(Error::DeviceFinished);
+        }
+        Ok(std::mem::take(&mut self.state.events))
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), Error> {
+        self.event_queue.flush().context(error::FlushConnectionSnafu)
+    }
+
+    #[inline]
+    pub fn display_name(&self) -> String { format!("display: {}", self.display_name) }
+}
+
+/// Connects to the socket of `display_name`, it is relative to
+/// `XDG_RUNTIME_DIR` unless it is an absolute path.
+fn connect(display_name: &str) -> Result<Connection, Error> {
+    let mut socket_path = PathBuf::from(display_name);
+    if socket_path.is_relative() {
+        let runtime_dir =
+            std::env::var_os("XDG_RUNTIME_DIR").ok_or(Error::MissingRuntimeDirectory)?;
+        socket_path = PathBuf::from(runtime_dir).join(socket_path);
+    }
+    let stream =
+        UnixStream::connect(&socket_path).context(error::ConnectSocketSnafu { socket_path })?;
+    Connection::from_socket(stream).context(error::ConnectSnafu)
+}
+
+fn new_connection(
+    display_name: &str,
+    clipboard_kind: ClipboardKind,
+) -> Result<(Connection, EventQueue<State>, State), Error> {
+    let connection = connect(display_name)?;
+    let (globals, mut event_queue) =
+        registry_queue_init::<State>(&connection).context(error::InitializeRegistrySnafu)?;
+    let qh = event_queue.handle();
+
+    let seats = globals.contents().with_list(|list| {
+        list.iter()
+            .filter(|global| global.interface == wl_seat::WlSeat::interface().name)
+            .map(|global| {
+                globals.registry().bind::<wl_seat::WlSeat, _, _>(
+                    global.name,
+                    global.version.min(2),
+                    &qh,
+                    (),
+                )
+            })
+            .collect::<Vec<_>>()
+    });
+    if seats.is_empty() {
+        return Err(Error::NoSeats);
+    }
+
+    // prefer the standardized `ext-data-control`, `wlr-data-control` is the
+    // fallback for compositors which have not implemented it yet
+    if let Ok(manager) = globals.bind::<ExtDataControlManagerV1, _, _>(&qh, 1..=1, ()) {
+        for seat in &seats {
+            drop(manager.get_data_device(seat, &qh, ()));
+        }
+    } else {
+        // primary selection is available since version 2 of `wlr-data-control`
+        let min_version = if clipboard_kind == ClipboardKind::Clipboard { 1 } else { 2 };
+        let manager = globals
+            .bind::<ZwlrDataControlManagerV1, _, _>(&qh, min_version..=2, ())
+            .map_err(|err| match err {
+                BindError::UnsupportedVersion => {
+                    Error::ClipboardKindNotSupported { kind: clipboard_kind }
+                }
+                BindError::NotPresent => {
+                    Error::MissingProtocol { name: ZwlrDataControlManagerV1::interface().name }
+                }
+            })?;
+        for seat in &seats {
+            drop(manager.get_data_device(seat, &qh, ()));
+        }
+    }
+
+    let mut state = State::new(clipboard_kind);
+    // receive the current selection, it is not a change of selection
+    let _unused = event_queue.roundtrip(&mut state).context(error::DispatchEventsSnafu)?;
+    state.events.clear();
+    if state.is_device_finished {
+        return Err(Error::DeviceFinished);
+    }
+
+    Ok((connection, event_queue, state))
+}
+
+impl AsRawFd for Context {
+    fn as_raw_fd(&self) -> RawFd { self.connection.as_fd().as_raw_fd() }
+}
+
+#[derive(Debug)]
+enum Offer {
+    Ext(ExtDataControlOfferV1),
+    Wlr(ZwlrDataControlOfferV1),
+}
+
+impl Offer {
+    fn mime_types(&self) -> Vec<String> {
+        let mime_types = match self {
+            Self::Ext(offer) => offer.data::<OfferedMimeTypes>(),
+            Self::Wlr(offer) => offer.data::<OfferedMimeTypes>(),
+        };
+        mime_types.map(|mime_types| mime_types.lock().clone()).unwrap_or_default()
+    }
+
+    fn destroy(&self) {
+        match self {
+            Self::Ext(offer) => offer.destroy(),
+            Self::Wlr(offer) => offer.destroy(),
+        }
+    }
+}
+
+pub struct State {
+    clipboard_kind: ClipboardKind,
+    offer: Option<Offer>,
+    events: Vec<SelectionEvent>,
+    is_device_finished: bool,
+}
+
+impl State {
+    const fn new(clipboard_kind: ClipboardKind) -> Self {
+        // there is no secondary selection on Wayland, fall back to primary selection
+        let clipboard_kind = match clipboard_kind {
+            ClipboardKind::Clipboard => ClipboardKind::Clipboard,
+            ClipboardKind::Primary | ClipboardKind::Secondary => ClipboardKind::Primary,
+        };
+        Self { clipboard_kind, offer: None, events: Vec::new(), is_device_finished: false }
+    }
+
+    fn on_selection(&mut self, kind: ClipboardKind, offer: Option<Offer>) {
+        if kind != self.clipboard_kind {
+            // we are not interested in this offer
+            if let Some(offer) = offer {
+                offer.destroy();
+            }
+            return;
+        }
+
+        // the previous offer is no longer valid
+        if let Some(previous) = self.offer.take() {
+            previous.destroy();
+        }
+
+        if let Some(offer) = offer {
+            self.events.push(SelectionEvent::Changed(offer.mime_types()));
+            self.offer = Some(offer);
+        } else {
+            self.events.push(SelectionEvent::Cleared);
+        }
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_registry::WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qh: &wayland_client::QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_seat::WlSeat,
+        _event: wl_seat::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &wayland_client::QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtDataControlManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ExtDataControlManagerV1,
+        _event: <ExtDataControlManagerV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &wayland_client::QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZwlrDataControlManagerV1,
+        _event: <ZwlrDataControlManagerV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &wayland_client::QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtDataControlDeviceV1, ()> for State {
+    event_created_child!(Self, ExtDataControlDeviceV1, [
+        ext_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ExtDataControlOfferV1, OfferedMimeTypes::default()),
+    ]);
+
+    fn event(
+        state: &mut Self,
+        _proxy: &ExtDataControlDeviceV1,
+        event: ext_data_control_device_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &wayland_client::QueueHandle<Self>,
+    ) {
+        match event {
+            ext_data_control_device_v1::Event::Selection { id } => {
+                state.on_selection(ClipboardKind::Clipboard, id.map(Offer::Ext));
+            }
+            ext_data_control_device_v1::Event::PrimarySelection { id } => {
+                state.on_selection(ClipboardKind::Primary, id.map(Offer::Ext));
+            }
+            ext_data_control_device_v1::Event::Finished => state.is_device_finished = true,
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for State {
+    event_created_child!(Self, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, OfferedMimeTypes::default()),
+    ]);
+
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwlrDataControlDeviceV1,
+        event: zwlr_data_control_device_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &wayland_client::QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_device_v1::Event::Selection { id } => {
+                state.on_selection(ClipboardKind::Clipboard, id.map(Offer::Wlr));
+            }
+            zwlr_data_control_device_v1::Event::PrimarySelection { id } => {
+                state.on_selection(ClipboardKind::Primary, id.map(Offer::Wlr));
+            }
+            zwlr_data_control_device_v1::Event::Finished => state.is_device_finished = true,
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtDataControlOfferV1, OfferedMimeTypes> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ExtDataControlOfferV1,
+        event: ext_data_control_offer_v1::Event,
+        mime_types: &OfferedMimeTypes,
+        _conn: &Connection,
+        _qh: &wayland_client::QueueHandle<Self>,
+    ) {
+        if let ext_data_control_offer_v1::Event::Offer { mime_type } = event {
+            mime_types.lock().push(mime_type);
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, OfferedMimeTypes> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZwlrDataControlOfferV1,
+        event: zwlr_data_control_offer_v1::Event,
+        mime_types: &OfferedMimeTypes,
+        _conn: &Connection,
+        _qh: &wayland_client::QueueHandle<Self>,
+    ) {
+        if let zwlr_data_control_offer_v1::Event::Offer { mime_type } = event {
+            mime_types.lock().push(mime_type);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{
+        listener::wayland::{context::Context, Error},
+        ClipboardKind,
+    };
+
+    #[test]
+    fn test_connect_to_display_name() {
+        let display_name = "/nonexistent/clipcat-wayland-test";
+        match Context::new(display_name.to_string(), ClipboardKind::Clipboard) {
+            Err(Error::ConnectSocket { socket_path, .. }) => {
+                assert_eq!(socket_path, Path::new(display_name));
+            }
+            Err(err) => panic!("Unexpected error: {err}"),
+            Ok(_) => panic!("Connected to a nonexistent display"),
+        }
+    }
+}
@@ -6,4 +6,52 @@ use snafu::Snafu;
 pub enum Error {
     #[snafu(display("Clipboard kind `{kind}` is not supported"))]
     ClipboardKindNotSupported { kind: ClipboardKind },
+
+    #[snafu(display("Could not connect to Wayland server, error: {source}"))]
+    Connect { source: wayland_client::ConnectError },
+
+    #[snafu(display(
+        "Could not connect to Wayland socket {}, error: {source}",
+        socket_path.display()
+    ))]
+    ConnectSocket { socket_path: std::path::PathBuf, source: std::io::Error },
+
+    #[snafu(display("`XDG_RUNTIME_DIR` is not set, could not locate Wayland socket"))]
+    MissingRuntimeDirectory,
+
+    #[snafu(display("Could not initialize Wayland registry, error: {source}"))]
+    InitializeRegistry { source: wayland_client::globals::GlobalError },
+
+    #[snafu(display("A required Wayland protocol `{name}` is not supported by the compositor"))]
+    MissingProtocol { name: &'static str },
+
+    #[snafu(display("No seat is advertised by the compositor"))]
+    NoSeats,
+
+    #[snafu(display("Data control device is no longer valid"))]
+    DeviceFinished,
+
+    #[snafu(display("Could not flush connection, error: {source}"))]
+    FlushConnection { source: wayland_client::backend::WaylandError },
+
+    #[snafu(display("Could not read events from Wayland server, error: {source}"))]
+    ReadEvents { source: wayland_client::backend::WaylandError },
+
+    #[snafu(display("Could not dispatch events of Wayland server, error: {source}"))]
+    DispatchEvents { source: wayland_client::DispatchError },
+
+    #[snafu(display("Retry limit reached, value: {value}"))]
+    RetryLimitReached { value: usize },
+
+    #[snafu(display("Listener is closing"))]
+    ListenerIsClosing,
+
+    #[snafu(display("Error occurred while initializing `mio::Poll`, error: {source}"))]
+    InitializeMioPoll { source: std::io::Error },
+
+    #[snafu(display("Error occurred while registering I/O resource, error: {source}"))]
+    RegisterIoResource { source: std::io::Error },
+
+    #[snafu(display("Error occurred while deregistering I/O resource, error: {source}"))]
+    DeregisterIoResource { source: std::io::Error },
 }
@@ -1,6 +1,9 @@
+mod context;
 mod error;
 
 use std::{
+    collections::HashSet,
+    os::fd::AsRawFd,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -9,17 +12,20 @@ use std::{
     time::Duration,
 };
 
-use wl_clipboard_rs::paste::{
-    get_contents as wl_clipboard_get_contents, Error as WaylandError, MimeType, Seat,
-};
+use clipcat_base::utils::RetryInterval;
+use snafu::ResultExt;
 
+use self::context::{Context, SelectionEvent};
 pub use self::error::Error;
 use crate::{
+    listener::{self, ClipFilter},
     pubsub::{self, Subscriber},
-    ClipboardKind, ClipboardSubscribe,
+    traits::EventObserver,
+    ClipboardKind, ClipboardSubscribe, ListenerKind,
 };
 
-const POLLING_INTERVAL: Duration = Duration::from_millis(250);
+const CONTEXT_TOKEN: mio::Token = mio::Token(0);
+const MAX_RETRY_COUNT: usize = 10 * 24 * 60 * 60;
 
 #[derive(Debug)]
 pub struct Listener {
@@ -29,27 +35,28 @@ pub struct Listener {
 }
 
 impl Listener {
-    pub fn new(clipboard_kind: ClipboardKind) -> Result<Self, crate::Error> {
+    pub fn new(
+        display_name: String,
+        clipboard_kind: ClipboardKind,
+        sensitive_mime_types: HashSet<String>,
+        event_observers: Vec<Arc<dyn EventObserver>>,
+    ) -> Self {
         let (notifier, subscriber) = pubsub::new(clipboard_kind);
         let is_running = Arc::new(AtomicBool::new(true));
 
-        let clipboard_type = match clipboard_kind {
-            ClipboardKind::Clipboard => wl_clipboard_rs::paste::ClipboardType::Regular,
-            _ => wl_clipboard_rs::paste::ClipboardType::Primary,
-        };
-
-        if clipboard_type == wl_clipboard_rs::paste::ClipboardType::Primary {
-            if let Ok(supported) = wl_clipboard_rs::utils::is_primary_selection_supported() {
-                if !supported {
-                    return Err(Error::ClipboardKindNotSupported { kind: clipboard_kind })?;
-                }
-            } else {
-                return Err(Error::ClipboardKindNotSupported { kind: clipboard_kind })?;
-            }
-        }
-
-        let thread = build_thread(is_running.clone(), clipboard_type, notifier);
-        Ok(Self { is_running, thread: Some(thread), subscriber })
+        // the compositor might not be ready yet, e.g. the daemon is started along with
+        // the session, so connect on the thread with the same retry policy as
+        // re-connecting
+        let filter = ClipFilter::new(sensitive_mime_types);
+        let thread = build_thread(
+            is_running.clone(),
+            display_name,
+            clipboard_kind,
+            filter,
+            notifier,
+            event_observers,
+        );
+        Self { is_running, thread: Some(thread), subscriber }
     }
 }
 
@@ -71,42 +78,208 @@ impl Drop for Listener {
 #[allow(clippy::cognitive_complexity)]
 fn build_thread(
     is_running: Arc<AtomicBool>,
-    clipboard_type: wl_clipboard_rs::paste::ClipboardType,
+    display_name: String,
+    clipboard_kind: ClipboardKind,
+    filter: ClipFilter,
     notifier: pubsub::Publisher,
+    event_observers: Vec<Arc<dyn EventObserver>>,
 ) -> thread::JoinHandle<Result<(), Error>> {
+    let retry_interval = RetryInterval::new(MAX_RETRY_COUNT, Duration::from_secs(3))
+        .add_phase(10, Duration::from_millis(100))
+        .add_phase(50, Duration::from_millis(500))
+        .add_phase(100, Duration::from_millis(2500));
+
     thread::spawn(move || {
+        tracing::info!("Connecting Wayland server");
+        let mut context =
+            match connect(&display_name, clipboard_kind, retry_interval.clone(), &is_running) {
+                Ok(context) => context,
+                Err(err) => {
+                    notifier.close();
+                    return Err(err);
+                }
+            };
+        tracing::info!("Connected to Wayland server");
+        for observer in &event_observers {
+            observer.on_connected(ListenerKind::Wayland, &context.display_name());
+        }
+
+        let mut poll = mio::Poll::new().context(error::InitializeMioPollSnafu)?;
+        let mut events = mio::Events::with_capacity(1024);
+
+        poll.registry()
+            .register(
+                &mut mio::unix::SourceFd(&context.as_raw_fd()),
+                CONTEXT_TOKEN,
+                mio::Interest::READABLE,
+            )
+            .context(error::RegisterIoResourceSnafu)?;
+
         while is_running.load(Ordering::Relaxed) {
             tracing::trace!("Wait for readiness events");
 
-            match wl_clipboard_get_contents(clipboard_type, Seat::Unspecified, MimeType::Any) {
-                Ok((_pipe, mime_type)) => {
-                    if let Ok(mime) = mime_type.parse() {
-                        notifier.notify_all(mime);
+            if let Err(err) = poll.poll(&mut events, Some(Duration::from_millis(200))) {
+                tracing::error!("Error occurred while polling for readiness event, error: {err}");
+            }
+
+            // the connection is broken if the compositor exits or restarts
+            let selection_events = match context.dispatch() {
+                Ok(selection_events) => selection_events,
+                Err(err) => {
+                    tracing::warn!("{err}, try to re-connect");
+                    if let Err(err) =
+                        try_reconnect(&poll, &mut context, retry_interval.clone(), &is_running)
+                    {
+                        notifier.close();
+                        return Err(err);
+                    }
+                    for observer in &event_observers {
+                        observer.on_connected(ListenerKind::Wayland, &context.display_name());
                     }
                     continue;
                 }
-                Err(
-                    WaylandError::NoSeats | WaylandError::ClipboardEmpty | WaylandError::NoMimeType,
-                ) => {
-                    tracing::trace!("The clipboard is empty, sleep for a while");
-                }
-                Err(WaylandError::MissingProtocol { name, version }) => {
-                    tracing::error!(
-                        "A required Wayland protocol (name: {name}, version: {version}) is not \
-                         supported by the compositor"
-                    );
+            };
+
+            handle_selection_events(selection_events, &filter, &notifier);
+        }
+
+        notifier.close();
+        Ok(())
+    })
+}
+
+fn handle_selection_events(
+    selection_events: Vec<SelectionEvent>,
+    filter: &ClipFilter,
+    notifier: &pubsub::Publisher,
+) {
+    for event in selection_events {
+        match event {
+            SelectionEvent::Changed(formats) => {
+                // filter sensitive content
+                if filter.is_sensitive(&formats) {
+                    tracing::info!("Sensitive content detected, ignore it");
+                } else {
+                    // data-control protocols do not reveal the client offering the selection
+                    listener::notify_preferred_format(notifier, formats, None);
                 }
-                Err(err) => {
+            }
+            SelectionEvent::Cleared => {
+                // data-control protocols report a selection cleared on purpose, e.g. by a
+                // password manager, the same way as a selection whose owner exits, so the
+                // loss of owner is not notified and the selection is never reclaimed
+                tracing::debug!("Selection is cleared");
+            }
+        }
+    }
+}
+
+// SAFETY: the function is complex because of `tracing`
+#[allow(clippy::cognitive_complexity)]
+fn connect(
+    display_name: &str,
+    clipboard_kind: ClipboardKind,
+    retry_interval: RetryInterval,
+    is_running: &Arc<AtomicBool>,
+) -> Result<Context, Error> {
+    let max_retry_count = retry_interval.limit();
+    for interval in retry_interval {
+        match Context::new(display_name.to_string(), clipboard_kind) {
+            Ok(context) => return Ok(context),
+            Err(err) => {
+                if !is_running.load(Ordering::Relaxed) {
                     tracing::warn!(
-                        "Error occurs while listening to clipboard of Wayland, error: {err}"
+                        "Listener is about to quit, no need to connect to Wayland server"
                     );
+                    return Err(Error::ListenerIsClosing);
                 }
+                tracing::warn!("{err}, try to connect after {n}ms", n = interval.as_millis());
+                std::thread::sleep(interval);
             }
-            // sleep for a while there is no content or error occurred
-            thread::sleep(POLLING_INTERVAL);
         }
+    }
+    tracing::error!("Could not connect to Wayland server");
+    Err(Error::RetryLimitReached { value: max_retry_count })
+}
 
-        notifier.close();
-        Ok(())
-    })
+// SAFETY: the function is complex because of `tracing`
+#[allow(clippy::cognitive_complexity)]
+#[inline]
+fn try_reconnect(
+    poll: &mio::Poll,
+    context: &mut Context,
+    retry_interval: RetryInterval,
+    is_running: &Arc<AtomicBool>,
+) -> Result<(), Error> {
+    poll.registry()
+        .deregister(&mut mio::unix::SourceFd(&context.as_raw_fd()))
+        .context(error::DeregisterIoResourceSnafu)?;
+
+    let max_retry_count = retry_interval.limit();
+    for interval in retry_interval {
+        if let Err(err) = context.reconnect() {
+            if !is_running.load(Ordering::Relaxed) {
+                tracing::warn!(
+                    "Listener is about to quit, no need to re-connect to Wayland server"
+                );
+                return Err(Error::ListenerIsClosing);
+            }
+            tracing::warn!("{err}, try to re-connect after {n}ms", n = interval.as_millis());
+            std::thread::sleep(interval);
+        } else {
+            poll.registry()
+                .register(
+                    &mut mio::unix::SourceFd(&context.as_raw_fd()),
+                    CONTEXT_TOKEN,
+                    mio::Interest::READABLE,
+                )
+                .context(error::RegisterIoResourceSnafu)?;
+
+            tracing::info!("Re-connected to Wayland server!");
+            return Ok(());
+        }
+    }
+    tracing::error!("Could not connect to Wayland server");
+    Err(Error::RetryLimitReached { value: max_retry_count })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        listener::{
+            wayland::{context::SelectionEvent, handle_selection_events},
+            ClipFilter,
+        },
+        pubsub::{self, Event},
+        ClipboardKind,
+    };
+
+    #[test]
+    fn test_drop_sensitive_offer() {
+        let (notifier, subscriber) = pubsub::new(ClipboardKind::Clipboard);
+        let filter = ClipFilter::new(
+            clipcat_base::DEFAULT_SENSITIVE_MIME_TYPES.iter().map(ToString::to_string).collect(),
+        );
+        let offer = |formats: &[&str]| {
+            vec![SelectionEvent::Changed(formats.iter().map(ToString::to_string).collect())]
+        };
+
+        // formats offered by KeePassXC
+        let events =
+            offer(&["text/plain;charset=utf-8", "text/plain", "x-kde-passwordManagerHint"]);
+        handle_selection_events(events, &filter, &notifier);
+        assert_eq!(subscriber.last_event(), None);
+
+        handle_selection_events(
+            offer(&["text/plain;charset=utf-8", "text/plain"]),
+            &filter,
+            &notifier,
+        );
+        match subscriber.last_event() {
+            Some(Event::Changed { mime, source: None }) => {
+                assert_eq!(mime.essence_str(), "text/plain");
+            }
+            event => panic!("Unexpected event: {event:?}"),
+        }
+    }
 }
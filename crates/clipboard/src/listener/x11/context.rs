@@ -4,10 +4,13 @@ use std::{
     time::{Duration, Instant},
 };
 
+use clipcat_base::ClipSource;
 use snafu::ResultExt;
 use x11rb::{
     connection::Connection,
+    properties::WmClass,
     protocol::{
+        res::{self, ConnectionExt as _},
         xfixes,
         xfixes::ConnectionExt as _,
         xproto::{self, ConnectionExt as _},
@@ -173,6 +176,107 @@ impl Context {
         Ok(Vec::new())
     }
 
+    /// Replies requests to the `CLIPBOARD_MANAGER` selection owned by the
+    /// listener. Applications ask the clipboard manager to save the clipboard
+    /// with `SAVE_TARGETS` before exiting, the content is already captured
+    /// when the clipboard changes, so the request is always accepted.
+    pub fn reply_clipboard_manager_request(
+        &self,
+        event: &xproto::SelectionRequestEvent,
+    ) -> Result<(), Error> {
+        if event.selection != self.atom_cache.clipboard_manager {
+            return Ok(());
+        }
+
+        let property = if event.target == self.atom_cache.targets {
+            drop(
+                self.connection
+                    .change_property32(
+                        xproto::PropMode::REPLACE,
+                        event.requestor,
+                        event.property,
+                        xproto::AtomEnum::ATOM,
+                        &[self.atom_cache.targets, self.atom_cache.save_targets],
+                    )
+                    .context(error::ChangePropertySnafu)?,
+            );
+            event.property
+        } else if event.target == self.atom_cache.save_targets {
+            event.property
+        } else {
+            x11rb::NONE
+        };
+
+        let notify = xproto::SelectionNotifyEvent {
+            response_type: xproto::SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: event.time,
+            requestor: event.requestor,
+            selection: event.selection,
+            target: event.target,
+            property,
+        };
+        drop(
+            self.connection
+                .send_event(false, event.requestor, xproto::EventMask::NO_EVENT, notify)
+                .context(error::SendEventSnafu)?,
+        );
+        self.flush()
+    }
+
+    /// Returns the application which owns the selection with the `owner`
+    /// window, `None` if nothing is known about it.
+    pub fn get_selection_source(&self, owner: xproto::Window) -> Option<ClipSource> {
+        if owner == x11rb::NONE {
+            return None;
+        }
+
+        let class = WmClass::get(&self.connection, owner)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|wm_class| String::from_utf8_lossy(wm_class.class()).to_string())
+            .filter(|class| !class.is_empty());
+        let pid = self.get_window_pid(owner);
+        let process_name = pid.and_then(get_process_name);
+        let source = ClipSource { class, pid, process_name };
+        (!source.is_empty()).then_some(source)
+    }
+
+    fn get_window_pid(&self, window: xproto::Window) -> Option<u32> {
+        // `_NET_WM_PID` is set by most toolkits, but the window owning a selection is
+        // usually an invisible window without it, ask X server for the process of
+        // client instead
+        let pid = self
+            .connection
+            .get_property(
+                false,
+                window,
+                self.atom_cache.net_wm_pid,
+                xproto::AtomEnum::CARDINAL,
+                0,
+                1,
+            )
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .and_then(|reply| reply.value32().and_then(|mut values| values.next()));
+        if pid.is_some() {
+            return pid;
+        }
+
+        let spec = res::ClientIdSpec { client: window, mask: res::ClientIdMask::LOCAL_CLIENT_PID };
+        self.connection
+            .res_query_client_ids(&[spec])
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .and_then(|reply| {
+                reply
+                    .ids
+                    .into_iter()
+                    .find(|id| id.spec.mask == res::ClientIdMask::LOCAL_CLIENT_PID)
+                    .and_then(|id| id.value.first().copied())
+            })
+    }
+
     pub fn display_name(&self) -> String {
         let display_name = self.display_name.as_deref().unwrap_or(":0");
         format!("display: {display_name}")
@@ -191,6 +295,8 @@ struct AtomCache {
     primary_selection: xproto::Atom,
     secondary_selection: xproto::Atom,
     targets: xproto::Atom,
+    save_targets: xproto::Atom,
+    net_wm_pid: xproto::Atom,
 }
 
 impl AtomCache {
@@ -202,6 +308,8 @@ impl AtomCache {
             primary_selection: xproto::AtomEnum::PRIMARY.into(),
             secondary_selection: xproto::AtomEnum::SECONDARY.into(),
             targets: get_intern_atom(conn, b"TARGETS")?,
+            save_targets: get_intern_atom(conn, b"SAVE_TARGETS")?,
+            net_wm_pid: get_intern_atom(conn, b"_NET_WM_PID")?,
         })
     }
 }
@@ -217,6 +325,15 @@ pub fn get_intern_atom(conn: &impl Connection, atom_name: &[u8]) -> Result<xprot
         .context(error::ReplySnafu)
 }
 
+// the owner of selection is running on the same host in most cases, its name
+// is available in `procfs`
+fn get_process_name(pid: u32) -> Option<String> {
+    std::fs::read_to_string(format!("/proc/{pid}/comm"))
+        .ok()
+        .map(|name| name.trim_end().to_string())
+        .filter(|name| !name.is_empty())
+}
+
 fn new_connection(display_name: Option<&str>) -> Result<(RustConnection, xproto::Window), Error> {
     let (connection, screen_num) =
         RustConnection::connect(display_name).context(error::ConnectSnafu)?;
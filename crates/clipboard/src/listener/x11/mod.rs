@@ -14,12 +14,12 @@ use std::{
 
 use clipcat_base::utils::RetryInterval;
 use snafu::ResultExt;
-use x11rb::protocol::Event as X11Event;
+use x11rb::protocol::{xfixes, xproto, Event as X11Event};
 
 use self::context::Context;
 pub use self::error::Error;
 use crate::{
-    listener::x11::error::InitializeMioPollSnafu,
+    listener::{self, x11::error::InitializeMioPollSnafu, ClipFilter},
     pubsub::{self, Subscriber},
     traits::EventObserver,
     ClipboardKind, ClipboardSubscribe, ListenerKind,
@@ -39,6 +39,7 @@ impl Listener {
     pub fn new(
         display_name: Option<String>,
         clipboard_kind: ClipboardKind,
+        sensitive_mime_types: HashSet<String>,
         event_observers: Vec<Arc<dyn EventObserver>>,
     ) -> Result<Self, crate::Error> {
         let (notifier, subscriber) = pubsub::new(clipboard_kind);
@@ -52,7 +53,8 @@ impl Listener {
             observer.on_connected(ListenerKind::X11, &context.display_name());
         }
 
-        let thread = build_thread(is_running.clone(), context, notifier, event_observers);
+        let filter = ClipFilter::new(sensitive_mime_types);
+        let thread = build_thread(is_running.clone(), context, filter, notifier, event_observers);
 
         Ok(Self { is_running, thread: Some(thread), subscriber })
     }
@@ -77,10 +79,10 @@ impl Drop for Listener {
 fn build_thread(
     is_running: Arc<AtomicBool>,
     mut context: Context,
+    filter: ClipFilter,
     notifier: pubsub::Publisher,
     event_observers: Vec<Arc<dyn EventObserver>>,
 ) -> thread::JoinHandle<Result<(), Error>> {
-    let filter = ClipFilter::new();
     let retry_interval = RetryInterval::new(MAX_RETRY_COUNT, Duration::from_secs(3))
         .add_phase(10, Duration::from_millis(100))
         .add_phase(50, Duration::from_millis(500))
@@ -108,48 +110,18 @@ fn build_thread(
             for event in &events {
                 if event.token() == CONTEXT_TOKEN {
                     match context.poll_for_event() {
-                        Ok(X11Event::XfixesSelectionNotify(_event)) => {
-                            match context.get_available_formats() {
-                                Ok(mut formats) => {
-                                    // filter sensitive content
-                                    if filter.filter_atom(&formats) {
-                                        tracing::info!("Sensitive content detected, ignore it");
-                                        continue;
-                                    }
-
-                                    // sort available formats by type, some applications provide
-                                    // image in `text/html` format, we prefer to use `image`
-                                    formats.sort_unstable_by_key(|format| {
-                                        if format.starts_with("image/png") {
-                                            1
-                                        } else if format.starts_with("image") {
-                                            2
-                                        } else if format.starts_with("text") {
-                                            3
-                                        } else if format == "UTF8_STRING" {
-                                            4
-                                        } else {
-                                            u32::MAX
-                                        }
-                                    });
-
-                                    for format in formats {
-                                        if format == "UTF8_STRING" {
-                                            notifier.notify_all(mime::TEXT_PLAIN_UTF_8);
-                                            break;
-                                        }
-                                        if let Ok(mime) = format.parse() {
-                                            notifier.notify_all(mime);
-                                            break;
-                                        }
-                                    }
-                                }
-                                Err(err) => {
-                                    tracing::warn!(
-                                        "Clipboard is changed but we could not get available \
-                                         formats, error: {err}"
-                                    );
-                                }
+                        Ok(X11Event::XfixesSelectionNotify(event)) => {
+                            if event.subtype == xfixes::SelectionEvent::SET_SELECTION_OWNER {
+                                notify_available_format(&context, event.owner, &filter, &notifier);
+                            } else {
+                                // the owner window is destroyed or its client is closed
+                                tracing::debug!("Owner of selection is gone");
+                                notifier.notify_owner_lost();
+                            }
+                        }
+                        Ok(X11Event::SelectionRequest(event)) => {
+                            if let Err(err) = context.reply_clipboard_manager_request(&event) {
+                                tracing::warn!("Could not reply selection request, error: {err}");
                             }
                         }
                         Ok(_) | Err(Error::NoEvent) => {}
@@ -178,6 +150,31 @@ fn build_thread(
     })
 }
 
+fn notify_available_format(
+    context: &Context,
+    owner: xproto::Window,
+    filter: &ClipFilter,
+    notifier: &pubsub::Publisher,
+) {
+    let formats = match context.get_available_formats() {
+        Ok(formats) => formats,
+        Err(err) => {
+            tracing::warn!(
+                "Clipboard is changed but we could not get available formats, error: {err}"
+            );
+            return;
+        }
+    };
+
+    // filter sensitive content
+    if filter.is_sensitive(&formats) {
+        tracing::info!("Sensitive content detected, ignore it");
+        return;
+    }
+
+    listener::notify_preferred_format(notifier, formats, context.get_selection_source(owner));
+}
+
 // SAFETY: the function is complex because of `tracing`
 #[allow(clippy::cognitive_complexity)]
 #[inline]
@@ -216,18 +213,3 @@ fn try_reconnect(
     tracing::error!("Could not connect to X11 server");
     Err(Error::RetryLimitReached { value: max_retry_count })
 }
-
-struct ClipFilter {
-    sensitive_atoms: HashSet<String>,
-}
-
-impl ClipFilter {
-    fn new() -> Self {
-        Self { sensitive_atoms: HashSet::from(["x-kde-passwordManagerHint".to_string()]) }
-    }
-
-    #[inline]
-    fn filter_atom(&self, atoms: &[String]) -> bool {
-        atoms.iter().any(|atom| self.sensitive_atoms.contains(atom))
-    }
-}
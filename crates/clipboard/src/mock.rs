@@ -9,17 +9,14 @@ use crate::{
 
 #[derive(Clone, Debug)]
 pub struct Clipboard {
+    kind: ClipboardKind,
     data: Arc<RwLock<Option<ClipboardContent>>>,
     publisher: Arc<Publisher>,
     subscriber: Subscriber,
 }
 
 impl Default for Clipboard {
-    fn default() -> Self {
-        let (publisher, subscriber) = pubsub::new(ClipboardKind::Clipboard);
-        let data = Arc::default();
-        Self { publisher: Arc::new(publisher), subscriber, data }
-    }
+    fn default() -> Self { Self::with_kind(ClipboardKind::Clipboard) }
 }
 
 impl Clipboard {
@@ -31,13 +28,22 @@ impl Clipboard {
     #[must_use]
     pub fn with_content(content: ClipboardContent) -> Self {
         let data = Arc::new(RwLock::new(Some(content)));
-        let (publisher, subscriber) = pubsub::new(ClipboardKind::Clipboard);
-        Self { data, publisher: Arc::new(publisher), subscriber }
+        let kind = ClipboardKind::Clipboard;
+        let (publisher, subscriber) = pubsub::new(kind);
+        Self { kind, data, publisher: Arc::new(publisher), subscriber }
+    }
+
+    /// Creates an empty clipboard whose changes are reported as `kind`.
+    #[inline]
+    #[must_use]
+    pub fn with_kind(kind: ClipboardKind) -> Self {
+        let (publisher, subscriber) = pubsub::new(kind);
+        Self { kind, data: Arc::default(), publisher: Arc::new(publisher), subscriber }
     }
-}
 
-impl Drop for Clipboard {
-    fn drop(&mut self) { self.publisher.close(); }
+    #[inline]
+    #[must_use]
+    pub const fn kind(&self) -> ClipboardKind { self.kind }
 }
 
 impl ClipboardSubscribe for Clipboard {
@@ -55,11 +61,14 @@ impl ClipboardLoad for Clipboard {
         match maybe_data {
             Ok(content) => {
                 if let Some(mime) = mime {
-                    let content_mime = match content {
-                        ClipboardContent::Plaintext(_) => mime::TEXT_PLAIN_UTF_8,
-                        ClipboardContent::Image { .. } => mime::IMAGE_PNG,
+                    // rich content is loaded in any of its representations
+                    let is_offered = match &content {
+                        ClipboardContent::Rich(representations) => {
+                            representations.contains_key(mime.essence_str())
+                        }
+                        _ => content.mime() == mime,
                     };
-                    (content_mime == mime).then_some(content).ok_or(Error::Empty)
+                    is_offered.then_some(content).ok_or(Error::Empty)
                 } else {
                     Ok(content)
                 }
@@ -72,14 +81,11 @@ impl ClipboardLoad for Clipboard {
 impl ClipboardStore for Clipboard {
     #[inline]
     fn store(&self, content: ClipboardContent) -> Result<(), Error> {
-        let mime = match content {
-            ClipboardContent::Plaintext(_) => mime::TEXT_PLAIN_UTF_8,
-            ClipboardContent::Image { .. } => mime::IMAGE_PNG,
-        };
+        let mime = content.mime();
         match self.data.write() {
             Ok(mut data) => {
                 *data = Some(content);
-                self.publisher.notify_all(mime);
+                self.publisher.notify_all(mime, None);
                 Ok(())
             }
             Err(_err) => Err(Error::PrimitivePoisoned),
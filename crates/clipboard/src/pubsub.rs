@@ -1,10 +1,11 @@
 use std::sync::Arc;
 
+use clipcat_base::ClipSource;
 use parking_lot::{Condvar, Mutex};
 
 use crate::{ClipboardKind, ClipboardWait, Error};
 
-type StateData = Mutex<(State, Option<mime::Mime>)>;
+type StateData = Mutex<(State, Option<Event>)>;
 
 pub fn new(kind: ClipboardKind) -> (Publisher, Subscriber) {
     let inner = Arc::new((Mutex::new((State::Running, None)), Condvar::new()));
@@ -13,6 +14,16 @@ pub fn new(kind: ClipboardKind) -> (Publisher, Subscriber) {
     (publisher, subscriber)
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// The selection is changed and offers content in the MIME type, the
+    /// application owning the selection is attached if it is known.
+    Changed { mime: mime::Mime, source: Option<ClipSource> },
+
+    /// The owner of the selection is gone and the content is lost with it.
+    OwnerLost,
+}
+
 #[derive(Clone, Copy, Debug)]
 enum State {
     Running,
@@ -23,9 +34,15 @@ enum State {
 pub struct Publisher(Arc<(StateData, Condvar)>);
 
 impl Publisher {
-    pub fn notify_all(&self, mime: mime::Mime) {
+    pub fn notify_all(&self, mime: mime::Mime, source: Option<ClipSource>) {
+        self.publish(Event::Changed { mime, source });
+    }
+
+    pub fn notify_owner_lost(&self) { self.publish(Event::OwnerLost); }
+
+    fn publish(&self, event: Event) {
         let (lock, condvar) = &*self.0;
-        *lock.lock() = (State::Running, Some(mime));
+        *lock.lock() = (State::Running, Some(event));
         let _unused = condvar.notify_all();
     }
 
@@ -49,16 +66,23 @@ pub struct Subscriber {
 // FIXME:
 #[allow(clippy::significant_drop_in_scrutinee)]
 impl ClipboardWait for Subscriber {
-    fn wait(&self) -> Result<(ClipboardKind, mime::Mime), Error> {
+    fn wait(&self) -> Result<(ClipboardKind, Event), Error> {
         let (lock, condvar) = &*self.inner;
         let result = {
             let mut state = lock.lock();
             condvar.wait(&mut state);
             match *state {
-                (State::Running, Some(ref mime)) => Ok((self.kind, mime.clone())),
+                (State::Running, Some(ref event)) => Ok((self.kind, event.clone())),
                 (State::Running | State::Stopped, _) => Err(Error::NotifierClosed),
             }
         };
         result
     }
 }
+
+#[cfg(test)]
+impl Subscriber {
+    /// Returns the latest event without waiting for it.
+    #[must_use]
+    pub fn last_event(&self) -> Option<Event> { self.inner.0.lock().1.clone() }
+}
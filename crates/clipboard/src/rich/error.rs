@@ -0,0 +1,78 @@
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum Error {
+    #[snafu(display("Reply error: {source}"))]
+    Reply { source: x11rb::errors::ReplyError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not create X11 connection, error: {source}"))]
+    Connect { source: x11rb::errors::ConnectError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not generate X11 identifier, error: {source}"))]
+    GenerateX11Identifier { source: x11rb::errors::ReplyOrIdError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not create new window, error: {source}"))]
+    CreateWindow { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not flush connection, error: {source}"))]
+    FlushConnection { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not send event, error: {source}"))]
+    SendEvent { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not get selection owner, error: {source}"))]
+    GetSelectionOwner { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not claim selection owner, error: {source}"))]
+    ClaimSelectionOwner { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Selection owner is not matched"))]
+    MatchSelectionOwner,
+
+    #[snafu(display("Could not change property, error: {source}"))]
+    ChangeProperty { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not delete property, error: {source}"))]
+    DeleteProperty { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not change window attributes, error: {source}"))]
+    ChangeWindowAttributes { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not get atom identifier by name {atom_name}, error: {source}"))]
+    GetAtomIdentifierByName {
+        atom_name: String,
+        source: x11rb::errors::ConnectionError,
+        backtrace: snafu::Backtrace,
+    },
+
+    #[snafu(display("Could not get atom name, error: {source}"))]
+    GetAtomName { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not get property, error: {source}"))]
+    GetProperty { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Could not convert selection, error: {source}"))]
+    ConvertSelection { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Error occurs while polling for event, error: {source}"))]
+    PollForEvent { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Error occurs while waiting for event, error: {source}"))]
+    WaitForEvent { source: x11rb::errors::ConnectionError, backtrace: snafu::Backtrace },
+
+    #[snafu(display("Timed out while receiving `{target}` incrementally"))]
+    IncrementalTransferTimeout { target: String },
+
+    #[snafu(display("Could not get MIME types from Wayland clipboard, error: {source}"))]
+    GetWaylandMimeTypes { source: wl_clipboard_rs::paste::Error },
+
+    #[snafu(display("Could not get `{mime}` from Wayland clipboard, error: {source}"))]
+    GetWaylandContents { mime: String, source: wl_clipboard_rs::paste::Error },
+
+    #[snafu(display("Could not read `{mime}` from Wayland clipboard, error: {source}"))]
+    ReadWaylandContents { mime: String, source: std::io::Error },
+
+    #[snafu(display("Could not copy to Wayland clipboard, error: {source}"))]
+    CopyToWayland { source: wl_clipboard_rs::copy::Error },
+}
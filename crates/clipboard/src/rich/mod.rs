@@ -0,0 +1,53 @@
+mod error;
+pub mod wayland;
+pub mod x11;
+
+pub use self::error::Error;
+
+/// Picks the formats worth fetching from the formats offered by the selection
+/// owner, returns `None` if the content is plain text or a single image which
+/// are handled without fetching every format.
+pub fn select_formats<'a, I>(formats: I) -> Option<Vec<String>>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut selected = Vec::new();
+    let mut image = None;
+    for format in formats {
+        let Ok(mime) = format.parse::<mime::Mime>() else {
+            // X11 targets such as `TARGETS` or `UTF8_STRING` are not MIME types
+            continue;
+        };
+        if mime.type_() == mime::IMAGE {
+            // images are usually offered in many formats converted on the fly,
+            // keep only one of them and prefer PNG
+            if image.is_none() || mime == mime::IMAGE_PNG {
+                image = Some(format.to_string());
+            }
+        } else if !selected.iter().any(|selected| selected == format) {
+            selected.push(format.to_string());
+        }
+    }
+
+    let is_rich = selected.iter().any(|format| !format.starts_with("text/plain"));
+    is_rich.then(|| selected.into_iter().chain(image).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::select_formats;
+
+    #[test]
+    fn test_select_formats() {
+        assert_eq!(select_formats(["TARGETS", "UTF8_STRING", "text/plain;charset=utf-8"]), None);
+        assert_eq!(select_formats(["TARGETS", "image/png", "image/bmp"]), None);
+        assert_eq!(
+            select_formats(["TARGETS", "text/html", "text/plain", "image/bmp", "image/png"]),
+            Some(vec!["text/html".to_string(), "text/plain".to_string(), "image/png".to_string()])
+        );
+        assert_eq!(
+            select_formats(["text/uri-list", "x-special/gnome-copied-files", "STRING"]),
+            Some(vec!["text/uri-list".to_string(), "x-special/gnome-copied-files".to_string()])
+        );
+    }
+}
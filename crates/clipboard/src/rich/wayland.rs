@@ -0,0 +1,85 @@
+use std::io::Read;
+
+use bytes::Bytes;
+use clipcat_base::Representations;
+use snafu::ResultExt;
+use wl_clipboard_rs::{copy, paste};
+
+use crate::{
+    rich::{error, Error},
+    ClipboardKind,
+};
+
+/// Loads every representation of the selection, returns `None` if the
+/// selection is not offered in formats beyond plain text and images.
+pub fn load(clipboard_kind: ClipboardKind) -> Result<Option<Representations>, Error> {
+    let clipboard_type = match clipboard_kind {
+        ClipboardKind::Clipboard => paste::ClipboardType::Regular,
+        _ => paste::ClipboardType::Primary,
+    };
+    let mime_types = match paste::get_mime_types(clipboard_type, paste::Seat::Unspecified) {
+        Ok(mime_types) => mime_types,
+        Err(paste::Error::ClipboardEmpty | paste::Error::NoSeats) => return Ok(None),
+        Err(source) => return Err(Error::GetWaylandMimeTypes { source }),
+    };
+    let mut mime_types = mime_types.into_iter().collect::<Vec<_>>();
+    mime_types.sort_unstable();
+    let Some(formats) = super::select_formats(mime_types.iter().map(String::as_str)) else {
+        return Ok(None);
+    };
+
+    let mut representations = Representations::new();
+    for mime in formats {
+        match read_contents(clipboard_type, &mime) {
+            Ok(data) => {
+                let _unused = representations.insert(mime, Bytes::from(data));
+            }
+            Err(err) => tracing::warn!("{err}"),
+        }
+    }
+    Ok(super::select_formats(representations.keys().map(String::as_str)).map(|_| representations))
+}
+
+/// Loads the selection in a single format.
+pub fn load_format(clipboard_kind: ClipboardKind, mime: &str) -> Result<Bytes, Error> {
+    let clipboard_type = match clipboard_kind {
+        ClipboardKind::Clipboard => paste::ClipboardType::Regular,
+        _ => paste::ClipboardType::Primary,
+    };
+    read_contents(clipboard_type, mime).map(Bytes::from)
+}
+
+/// Offers every representation to the compositor, the data is served on a
+/// background thread until another client takes over the selection.
+pub fn store(
+    clipboard_kind: ClipboardKind,
+    representations: &Representations,
+) -> Result<(), Error> {
+    let mut options = copy::Options::new();
+    let _unused = options.clipboard(match clipboard_kind {
+        ClipboardKind::Clipboard => copy::ClipboardType::Regular,
+        _ => copy::ClipboardType::Primary,
+    });
+    let sources = representations
+        .iter()
+        .map(|(mime, data)| copy::MimeSource {
+            source: copy::Source::Bytes(data.to_vec().into_boxed_slice()),
+            mime_type: copy::MimeType::Specific(mime.clone()),
+        })
+        .collect();
+    options.copy_multi(sources).context(error::CopyToWaylandSnafu)
+}
+
+fn read_contents(clipboard_type: paste::ClipboardType, mime: &str) -> Result<Vec<u8>, Error> {
+    let (mut pipe, _) = paste::get_contents(
+        clipboard_type,
+        paste::Seat::Unspecified,
+        paste::MimeType::Specific(mime),
+    )
+    .with_context(|_| error::GetWaylandContentsSnafu { mime: mime.to_string() })?;
+    let mut data = Vec::new();
+    let _size = pipe
+        .read_to_end(&mut data)
+        .with_context(|_| error::ReadWaylandContentsSnafu { mime: mime.to_string() })?;
+    Ok(data)
+}
@@ -0,0 +1,414 @@
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use clipcat_base::Representations;
+use snafu::ResultExt;
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    protocol::{
+        xproto::{self, ConnectionExt as _},
+        Event,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+};
+
+use crate::{
+    rich::{error, Error},
+    ClipboardKind,
+};
+
+const LONG_TIMEOUT_DUR: Duration = Duration::from_millis(1000);
+
+/// Loads every representation of the selection, returns `None` if the
+/// selection is not offered in formats beyond plain text and images.
+pub fn load(
+    display_name: Option<&str>,
+    clipboard_kind: ClipboardKind,
+) -> Result<Option<Representations>, Error> {
+    let context = Context::new(display_name, clipboard_kind)?;
+    let targets = context.targets()?;
+    let Some(formats) = super::select_formats(targets.iter().map(|(_, name)| name.as_str())) else {
+        return Ok(None);
+    };
+
+    let mut representations = Representations::new();
+    for (target, name) in targets.into_iter().filter(|(_, name)| formats.contains(name)) {
+        match context.read_selection(target, &name) {
+            Ok(Some(data)) => {
+                let _unused = representations.insert(name, Bytes::from(data));
+            }
+            Ok(None) => tracing::debug!("Selection owner refused to convert to `{name}`"),
+            Err(err) => tracing::warn!("Could not read `{name}` from selection, error: {err}"),
+        }
+    }
+    Ok(super::select_formats(representations.keys().map(String::as_str)).map(|_| representations))
+}
+
+/// Loads the selection in a single format, returns `None` if the selection
+/// owner refuses to convert the selection to `mime`.
+pub fn load_format(
+    display_name: Option<&str>,
+    clipboard_kind: ClipboardKind,
+    mime: &str,
+) -> Result<Option<Bytes>, Error> {
+    let context = Context::new(display_name, clipboard_kind)?;
+    let target = context.intern_atom(mime.as_bytes())?;
+    Ok(context.read_selection(target, mime)?.map(Bytes::from))
+}
+
+/// Takes the ownership of the selection and serves every representation on a
+/// separate thread, the thread exits once another client owns the selection.
+pub fn store(
+    display_name: Option<&str>,
+    clipboard_kind: ClipboardKind,
+    representations: &Representations,
+) -> Result<thread::JoinHandle<()>, Error> {
+    let context = Context::new(display_name, clipboard_kind)?;
+
+    let mut offers = HashMap::with_capacity(representations.len());
+    for (mime, data) in representations {
+        let _unused = offers.insert(context.intern_atom(mime.as_bytes())?, data.clone());
+    }
+    if let Some((_, text)) = representations.iter().find(|(mime, _)| mime.starts_with("text/plain"))
+    {
+        // legacy X11 clients ask for text by these targets instead of MIME types
+        for name in [&b"UTF8_STRING"[..], b"STRING", b"TEXT"] {
+            let _unused = offers.insert(context.intern_atom(name)?, text.clone());
+        }
+    }
+
+    context.claim_selection()?;
+    Ok(thread::spawn(move || {
+        if let Err(err) = context.serve(&offers) {
+            tracing::warn!("Error occurs while serving selection, error: {err}");
+        }
+    }))
+}
+
+struct Context {
+    connection: RustConnection,
+    window: xproto::Window,
+    selection: xproto::Atom,
+    property: xproto::Atom,
+    targets: xproto::Atom,
+    incr: xproto::Atom,
+}
+
+impl Context {
+    fn new(display_name: Option<&str>, clipboard_kind: ClipboardKind) -> Result<Self, Error> {
+        let (connection, screen_num) =
+            RustConnection::connect(display_name).context(error::ConnectSnafu)?;
+        let window = connection.generate_id().context(error::GenerateX11IdentifierSnafu)?;
+        let screen = &connection.setup().roots[screen_num];
+        drop(
+            connection
+                .create_window(
+                    x11rb::COPY_DEPTH_FROM_PARENT,
+                    window,
+                    screen.root,
+                    0,
+                    0,
+                    1,
+                    1,
+                    0,
+                    xproto::WindowClass::INPUT_OUTPUT,
+                    screen.root_visual,
+                    &xproto::CreateWindowAux::default()
+                        .event_mask(xproto::EventMask::PROPERTY_CHANGE),
+                )
+                .context(error::CreateWindowSnafu)?,
+        );
+
+        let selection = match clipboard_kind {
+            ClipboardKind::Clipboard => intern_atom(&connection, b"CLIPBOARD")?,
+            ClipboardKind::Primary => xproto::AtomEnum::PRIMARY.into(),
+            ClipboardKind::Secondary => xproto::AtomEnum::SECONDARY.into(),
+        };
+        let property = intern_atom(&connection, b"CLIPCAT_RICH_CLIPBOARD")?;
+        let targets = intern_atom(&connection, b"TARGETS")?;
+        let incr = intern_atom(&connection, b"INCR")?;
+        Ok(Self { connection, window, selection, property, targets, incr })
+    }
+
+    #[inline]
+    fn intern_atom(&self, name: &[u8]) -> Result<xproto::Atom, Error> {
+        intern_atom(&self.connection, name)
+    }
+
+    fn targets(&self) -> Result<Vec<(xproto::Atom, String)>, Error> {
+        let Some(data) = self.read_selection(self.targets, "TARGETS")? else {
+            return Ok(Vec::new());
+        };
+
+        let mut targets = Vec::new();
+        for atom in data.chunks_exact(4).filter_map(|chunk| chunk.try_into().ok()) {
+            let atom = xproto::Atom::from_ne_bytes(atom);
+            let name = self
+                .connection
+                .get_atom_name(atom)
+                .context(error::GetAtomNameSnafu)?
+                .reply()
+                .context(error::ReplySnafu)?
+                .name;
+            targets.push((atom, String::from_utf8_lossy(&name).to_string()));
+        }
+        Ok(targets)
+    }
+
+    /// Asks the selection owner to convert the selection to `target`, returns
+    /// `None` if the owner refuses or does not respond in time.
+    fn read_selection(&self, target: xproto::Atom, name: &str) -> Result<Option<Vec<u8>>, Error> {
+        drop(
+            self.connection
+                .delete_property(self.window, self.property)
+                .context(error::DeletePropertySnafu)?,
+        );
+        drop(
+            self.connection
+                .convert_selection(
+                    self.window,
+                    self.selection,
+                    target,
+                    self.property,
+                    x11rb::CURRENT_TIME,
+                )
+                .context(error::ConvertSelectionSnafu)?,
+        );
+        self.connection.flush().context(error::FlushConnectionSnafu)?;
+
+        let (window, selection, property) = (self.window, self.selection, self.property);
+        let event = self.wait_for_event(|event| {
+            matches!(event, Event::SelectionNotify(event)
+                if event.requestor == window && event.selection == selection)
+        })?;
+        match event {
+            Some(Event::SelectionNotify(event)) if event.property != x11rb::NONE => {}
+            _ => return Ok(None),
+        }
+
+        let reply = self.take_property()?;
+        if reply.type_ != self.incr {
+            return Ok(Some(reply.value));
+        }
+
+        // deleting the `INCR` property above requests the owner to start
+        // sending chunks, an empty chunk marks the end of transfer
+        let mut data = Vec::new();
+        loop {
+            let event = self.wait_for_event(|event| {
+                matches!(event, Event::PropertyNotify(event)
+                    if event.window == window
+                        && event.atom == property
+                        && event.state == xproto::Property::NEW_VALUE)
+            })?;
+            if event.is_none() {
+                return Err(Error::IncrementalTransferTimeout { target: name.to_string() });
+            }
+
+            let reply = self.take_property()?;
+            if reply.value.is_empty() {
+                return Ok(Some(data));
+            }
+            data.extend_from_slice(&reply.value);
+        }
+    }
+
+    fn take_property(&self) -> Result<xproto::GetPropertyReply, Error> {
+        self.connection
+            .get_property(true, self.window, self.property, xproto::AtomEnum::ANY, 0, u32::MAX)
+            .context(error::GetPropertySnafu)?
+            .reply()
+            .context(error::ReplySnafu)
+    }
+
+    fn wait_for_event<F>(&self, predicate: F) -> Result<Option<Event>, Error>
+    where
+        F: Fn(&Event) -> bool,
+    {
+        let timeout_end = Instant::now() + LONG_TIMEOUT_DUR;
+        while Instant::now() < timeout_end {
+            match self.connection.poll_for_event().context(error::PollForEventSnafu)? {
+                Some(event) if predicate(&event) => return Ok(Some(event)),
+                Some(_) => continue,
+                None => thread::sleep(Duration::from_millis(1)),
+            }
+        }
+        Ok(None)
+    }
+
+    fn claim_selection(&self) -> Result<(), Error> {
+        drop(
+            self.connection
+                .set_selection_owner(self.window, self.selection, x11rb::CURRENT_TIME)
+                .context(error::ClaimSelectionOwnerSnafu)?,
+        );
+        let owner = self
+            .connection
+            .get_selection_owner(self.selection)
+            .context(error::GetSelectionOwnerSnafu)?
+            .reply()
+            .context(error::ReplySnafu)?
+            .owner;
+        if owner == self.window {
+            Ok(())
+        } else {
+            Err(Error::MatchSelectionOwner)
+        }
+    }
+
+    fn serve(&self, offers: &HashMap<xproto::Atom, Bytes>) -> Result<(), Error> {
+        let chunk_size = self.connection.maximum_request_bytes() / 4;
+        let mut transfers = HashMap::new();
+        loop {
+            match self.connection.wait_for_event().context(error::WaitForEventSnafu)? {
+                Event::SelectionClear(event) if event.selection == self.selection => return Ok(()),
+                Event::SelectionRequest(event) => {
+                    self.reply_request(&event, offers, chunk_size, &mut transfers)?;
+                }
+                Event::PropertyNotify(event) if event.state == xproto::Property::DELETE => {
+                    let key = (event.window, event.atom);
+                    if let Some(transfer) = transfers.get_mut(&key) {
+                        if self.send_chunk(event.window, event.atom, transfer, chunk_size)? {
+                            drop(transfers.remove(&key));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn reply_request(
+        &self,
+        event: &xproto::SelectionRequestEvent,
+        offers: &HashMap<xproto::Atom, Bytes>,
+        chunk_size: usize,
+        transfers: &mut HashMap<(xproto::Window, xproto::Atom), Transfer>,
+    ) -> Result<(), Error> {
+        // obsolete clients do not specify the property
+        let property = if event.property == x11rb::NONE { event.target } else { event.property };
+        let accepted = if event.target == self.targets {
+            let mut targets = vec![self.targets];
+            targets.extend(offers.keys());
+            drop(
+                self.connection
+                    .change_property32(
+                        xproto::PropMode::REPLACE,
+                        event.requestor,
+                        property,
+                        xproto::AtomEnum::ATOM,
+                        &targets,
+                    )
+                    .context(error::ChangePropertySnafu)?,
+            );
+            true
+        } else if let Some(data) = offers.get(&event.target) {
+            if data.len() < chunk_size {
+                drop(
+                    self.connection
+                        .change_property8(
+                            xproto::PropMode::REPLACE,
+                            event.requestor,
+                            property,
+                            event.target,
+                            data,
+                        )
+                        .context(error::ChangePropertySnafu)?,
+                );
+            } else {
+                drop(
+                    self.connection
+                        .change_window_attributes(
+                            event.requestor,
+                            &xproto::ChangeWindowAttributesAux::new()
+                                .event_mask(xproto::EventMask::PROPERTY_CHANGE),
+                        )
+                        .context(error::ChangeWindowAttributesSnafu)?,
+                );
+                drop(
+                    self.connection
+                        .change_property32(
+                            xproto::PropMode::REPLACE,
+                            event.requestor,
+                            property,
+                            self.incr,
+                            &[u32::try_from(data.len()).unwrap_or(u32::MAX)],
+                        )
+                        .context(error::ChangePropertySnafu)?,
+                );
+                let transfer = Transfer { target: event.target, data: data.clone(), offset: 0 };
+                let _unused = transfers.insert((event.requestor, property), transfer);
+            }
+            true
+        } else {
+            false
+        };
+
+        let notify = xproto::SelectionNotifyEvent {
+            response_type: xproto::SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: event.time,
+            requestor: event.requestor,
+            selection: event.selection,
+            target: event.target,
+            property: if accepted { property } else { x11rb::NONE },
+        };
+        drop(
+            self.connection
+                .send_event(false, event.requestor, xproto::EventMask::NO_EVENT, notify)
+                .context(error::SendEventSnafu)?,
+        );
+        self.connection.flush().context(error::FlushConnectionSnafu)
+    }
+
+    /// Sends the next chunk of an incremental transfer, returns `true` once
+    /// the terminating empty chunk is sent.
+    fn send_chunk(
+        &self,
+        requestor: xproto::Window,
+        property: xproto::Atom,
+        transfer: &mut Transfer,
+        chunk_size: usize,
+    ) -> Result<bool, Error> {
+        let end = transfer.data.len().min(transfer.offset + chunk_size);
+        let chunk = &transfer.data[transfer.offset..end];
+        drop(
+            self.connection
+                .change_property8(
+                    xproto::PropMode::REPLACE,
+                    requestor,
+                    property,
+                    transfer.target,
+                    chunk,
+                )
+                .context(error::ChangePropertySnafu)?,
+        );
+        self.connection.flush().context(error::FlushConnectionSnafu)?;
+
+        let finished = chunk.is_empty();
+        transfer.offset = end;
+        Ok(finished)
+    }
+}
+
+struct Transfer {
+    target: xproto::Atom,
+    data: Bytes,
+    offset: usize,
+}
+
+fn intern_atom(connection: &RustConnection, name: &[u8]) -> Result<xproto::Atom, Error> {
+    connection
+        .intern_atom(false, name)
+        .with_context(|_| error::GetAtomIdentifierByNameSnafu {
+            atom_name: String::from_utf8_lossy(name).to_string(),
+        })?
+        .reply()
+        .map(|reply| reply.atom)
+        .context(error::ReplySnafu)
+}
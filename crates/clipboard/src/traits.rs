@@ -1,6 +1,6 @@
 use clipcat_base::ClipboardContent;
 
-use crate::{ClipboardKind, Error, ListenerKind};
+use crate::{ClipboardEvent, ClipboardKind, Error, ListenerKind};
 
 pub trait Load {
     /// # Errors
@@ -19,7 +19,7 @@ pub trait Store {
 
 pub trait Wait {
     /// # Errors
-    fn wait(&self) -> Result<(ClipboardKind, mime::Mime), Error>;
+    fn wait(&self) -> Result<(ClipboardKind, ClipboardEvent), Error>;
 }
 
 pub trait Subscribe: Send + Sync {
@@ -54,8 +54,12 @@ impl<C: Store + ?Sized> StoreExt for C {}
 pub trait LoadWait: Load + Subscribe {
     /// # Errors
     fn load_wait(&self) -> Result<ClipboardContent, Error> {
-        let (_, mime) = self.subscribe()?.wait()?;
-        self.load(Some(mime))
+        let subscriber = self.subscribe()?;
+        loop {
+            if let (_, ClipboardEvent::Changed { mime, .. }) = subscriber.wait()? {
+                return self.load(Some(mime));
+            }
+        }
     }
 }
 
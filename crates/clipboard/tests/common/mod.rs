@@ -1,6 +1,7 @@
 use std::time::Duration;
 
-use clipcat_base::ClipboardContent;
+use bytes::Bytes;
+use clipcat_base::{ClipboardContent, Representations};
 use clipcat_clipboard::{
     ClipboardLoad, ClipboardLoadExt, ClipboardStore, ClipboardStoreExt, ClipboardSubscribe,
     ClipboardWait, Error,
@@ -29,11 +30,39 @@ pub trait ClipboardTester {
             println!("Test with data size: {data_size}. Passed");
         }
 
+        println!("Rich content test");
+        self.test_load_rich()?;
+
         println!("Subscribe test");
         self.test_subscribe()?;
         Ok(())
     }
 
+    // the watcher loads the format notified by the listener, `text/html` is
+    // preferred to the plain text offered along with it
+    fn test_load_rich(&self) -> Result<(), Error> {
+        let clipboard = self.new_clipboard()?;
+
+        let representations = Representations::from([
+            ("text/html".to_string(), Bytes::from_static(b"<b>rich</b>")),
+            ("text/plain".to_string(), Bytes::from_static(b"rich")),
+        ]);
+        clipboard.store(ClipboardContent::Rich(representations))?;
+
+        for _ in 0..5 {
+            std::thread::sleep(Duration::from_millis(20));
+            match clipboard.load(Some(mime::TEXT_HTML))? {
+                ClipboardContent::Rich(loaded) => {
+                    assert_eq!(loaded.get("text/html"), Some(&Bytes::from_static(b"<b>rich</b>")));
+                    assert_eq!(loaded.get("text/plain"), Some(&Bytes::from_static(b"rich")));
+                }
+                content => panic!("Content type is not matched: {content:?}"),
+            }
+        }
+
+        Ok(())
+    }
+
     fn test_store_and_load(&self, len: usize) -> Result<(), Error> {
         let clipboard = self.new_clipboard()?;
 
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use clipcat_clipboard::{Clipboard, ClipboardKind, Error, X11ListenerError};
 
 mod common;
@@ -18,7 +20,7 @@ impl ClipboardTester for DefaultClipboardTester {
     type Clipboard = Clipboard;
 
     fn new_clipboard(&self) -> Result<Self::Clipboard, Error> {
-        let clipboard = Clipboard::new(self.kind, Vec::new())?;
+        let clipboard = Clipboard::new(self.kind, HashSet::new(), Vec::new())?;
         Ok(clipboard)
     }
 }
@@ -29,17 +29,19 @@ use std::str::FromStr;
 use time::OffsetDateTime;
 
 pub use self::proto::{
+    clip_event,
     manager_client::ManagerClient,
     manager_server::{Manager, ManagerServer},
     system_client::SystemClient,
     system_server::{System, SystemServer},
     watcher_client::WatcherClient,
     watcher_server::{Watcher, WatcherServer},
-    BatchRemoveRequest, BatchRemoveResponse, ClipEntry, ClipEntryMetadata, ClipboardKind,
-    GetCurrentClipRequest, GetCurrentClipResponse, GetRequest, GetResponse,
+    BatchRemoveRequest, BatchRemoveResponse, ClipEntry, ClipEntryMetadata, ClipEvent, ClipSource,
+    ClipboardKind, GetCurrentClipRequest, GetCurrentClipResponse, GetRequest, GetResponse,
     GetSystemVersionResponse, InsertRequest, InsertResponse, LengthResponse, ListRequest,
-    ListResponse, MarkRequest, MarkResponse, RemoveRequest, RemoveResponse, UpdateRequest,
-    UpdateResponse, WatcherState, WatcherStateReply,
+    ListResponse, MarkRequest, MarkResponse, PinRequest, PinResponse, RemoveRequest,
+    RemoveResponse, SearchMode, SearchRequest, SearchResponse, SubscribeRequest, UnpinRequest,
+    UnpinResponse, UpdateRequest, UpdateResponse, WatcherState, WatcherStateReply,
 };
 
 impl From<ClipboardKind> for clipcat_base::ClipboardKind {
@@ -69,38 +71,215 @@ impl From<clipcat_base::ClipEntry> for ClipEntry {
         let id = entry.id();
         let kind = entry.kind();
         let timestamp = utils::datetime_to_timestamp(&entry.timestamp());
+        let pinned = entry.is_pinned();
+        let representations = entry
+            .representations()
+            .map(|representations| {
+                representations.iter().map(|(mime, data)| (mime.clone(), data.to_vec())).collect()
+            })
+            .unwrap_or_default();
+        let source = entry.source().cloned().map(ClipSource::from);
 
-        Self { id, data, kind: kind.into(), mime, timestamp: Some(timestamp) }
+        Self {
+            id,
+            data,
+            kind: kind.into(),
+            mime,
+            timestamp: Some(timestamp),
+            pinned,
+            representations,
+            source,
+        }
     }
 }
 
 impl From<ClipEntry> for clipcat_base::ClipEntry {
-    fn from(ClipEntry { id: _, data, mime, kind, timestamp }: ClipEntry) -> Self {
+    fn from(
+        ClipEntry { id: _, data, mime, kind, timestamp, pinned, representations, source }: ClipEntry,
+    ) -> Self {
         let timestamp = timestamp.and_then(|ts| utils::timestamp_to_datetime(&ts).ok());
         let kind = clipcat_base::ClipboardKind::from(kind);
-        let mime = mime::Mime::from_str(&mime).unwrap_or(mime::APPLICATION_OCTET_STREAM);
-        Self::new(&data, &mime, kind, timestamp).unwrap_or_default()
+        let mut entry = if representations.is_empty() {
+            let mime = mime::Mime::from_str(&mime).unwrap_or(mime::APPLICATION_OCTET_STREAM);
+            Self::new(&data, &mime, kind, timestamp)
+        } else {
+            let representations =
+                representations.into_iter().map(|(mime, data)| (mime, data.into())).collect();
+            Self::from_representations(representations, kind, timestamp)
+        }
+        .unwrap_or_default();
+        entry.set_pinned(pinned);
+        entry.set_source(source.map(clipcat_base::ClipSource::from));
+        entry
     }
 }
 
 impl From<clipcat_base::ClipEntryMetadata> for ClipEntryMetadata {
     fn from(metadata: clipcat_base::ClipEntryMetadata) -> Self {
-        let clipcat_base::ClipEntryMetadata { id, kind: clipboard_kind, timestamp, mime, preview } =
-            metadata;
+        let clipcat_base::ClipEntryMetadata {
+            id,
+            kind: clipboard_kind,
+            timestamp,
+            mime,
+            preview,
+            pinned,
+            source,
+        } = metadata;
         let mime = mime.essence_str().to_owned();
         let timestamp = utils::datetime_to_timestamp(&timestamp);
-        Self { id, preview, kind: clipboard_kind.into(), mime, timestamp: Some(timestamp) }
+        Self {
+            id,
+            preview,
+            kind: clipboard_kind.into(),
+            mime,
+            timestamp: Some(timestamp),
+            pinned,
+            source: source.map(ClipSource::from),
+        }
     }
 }
 
 impl From<ClipEntryMetadata> for clipcat_base::ClipEntryMetadata {
-    fn from(ClipEntryMetadata { id, mime, kind, timestamp, preview }: ClipEntryMetadata) -> Self {
+    fn from(
+        ClipEntryMetadata { id, mime, kind, timestamp, preview, pinned, source }: ClipEntryMetadata,
+    ) -> Self {
         let timestamp = timestamp
             .and_then(|ts| utils::timestamp_to_datetime(&ts).ok())
             .unwrap_or_else(OffsetDateTime::now_utc);
         let clipboard_kind = clipcat_base::ClipboardKind::from(kind);
         let mime = mime::Mime::from_str(&mime).unwrap_or(mime::APPLICATION_OCTET_STREAM);
-        Self { id, kind: clipboard_kind, timestamp, mime, preview }
+        let source = source.map(clipcat_base::ClipSource::from).filter(|source| !source.is_empty());
+        Self { id, kind: clipboard_kind, timestamp, mime, preview, pinned, source }
+    }
+}
+
+impl From<clipcat_base::ClipSource> for ClipSource {
+    fn from(
+        clipcat_base::ClipSource { class, pid, process_name }: clipcat_base::ClipSource,
+    ) -> Self {
+        Self {
+            class: class.unwrap_or_default(),
+            pid: pid.unwrap_or_default(),
+            process_name: process_name.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<ClipSource> for clipcat_base::ClipSource {
+    fn from(ClipSource { class, pid, process_name }: ClipSource) -> Self {
+        Self {
+            class: Some(class).filter(|class| !class.is_empty()),
+            pid: Some(pid).filter(|&pid| pid != 0),
+            process_name: Some(process_name).filter(|name| !name.is_empty()),
+        }
+    }
+}
+
+impl From<SearchMode> for clipcat_base::SearchMode {
+    fn from(mode: SearchMode) -> Self {
+        match mode {
+            SearchMode::Substring => Self::Substring,
+            SearchMode::Regex => Self::Regex,
+        }
+    }
+}
+
+impl From<clipcat_base::SearchMode> for SearchMode {
+    fn from(mode: clipcat_base::SearchMode) -> Self {
+        match mode {
+            clipcat_base::SearchMode::Substring => Self::Substring,
+            clipcat_base::SearchMode::Regex => Self::Regex,
+        }
+    }
+}
+
+impl From<clipcat_base::SearchQuery> for SearchRequest {
+    fn from(query: clipcat_base::SearchQuery) -> Self {
+        let clipcat_base::SearchQuery {
+            pattern,
+            mode,
+            case_sensitive,
+            kinds,
+            mimes,
+            since,
+            until,
+            offset,
+            limit,
+            preview_length,
+        } = query;
+        Self {
+            pattern,
+            mode: SearchMode::from(mode).into(),
+            case_sensitive,
+            kinds: kinds.into_iter().map(|kind| ClipboardKind::from(kind).into()).collect(),
+            mimes: mimes.into_iter().map(|mime| mime.essence_str().to_owned()).collect(),
+            since: since.as_ref().map(utils::datetime_to_timestamp),
+            until: until.as_ref().map(utils::datetime_to_timestamp),
+            offset: u64::try_from(offset).unwrap_or(u64::MAX),
+            limit: limit.map(|limit| u64::try_from(limit).unwrap_or(u64::MAX)),
+            preview_length: u64::try_from(preview_length).unwrap_or(30),
+        }
+    }
+}
+
+impl From<SearchRequest> for clipcat_base::SearchQuery {
+    fn from(request: SearchRequest) -> Self {
+        let mode = request.mode().into();
+        let kinds = request.kinds().map(clipcat_base::ClipboardKind::from).collect();
+        let SearchRequest {
+            pattern,
+            case_sensitive,
+            mimes,
+            since,
+            until,
+            offset,
+            limit,
+            preview_length,
+            ..
+        } = request;
+        Self {
+            pattern,
+            mode,
+            case_sensitive,
+            kinds,
+            mimes: mimes.iter().filter_map(|mime| mime::Mime::from_str(mime).ok()).collect(),
+            since: since.and_then(|ts| utils::timestamp_to_datetime(&ts).ok()),
+            until: until.and_then(|ts| utils::timestamp_to_datetime(&ts).ok()),
+            offset: usize::try_from(offset).unwrap_or(usize::MAX),
+            limit: limit.map(|limit| usize::try_from(limit).unwrap_or(usize::MAX)),
+            preview_length: usize::try_from(preview_length).unwrap_or(30),
+        }
+    }
+}
+
+impl From<clipcat_base::ClipEvent> for ClipEvent {
+    fn from(event: clipcat_base::ClipEvent) -> Self {
+        let event = match event {
+            clipcat_base::ClipEvent::Inserted(metadata) => {
+                clip_event::Event::Inserted(metadata.into())
+            }
+            clipcat_base::ClipEvent::Removed { id } => clip_event::Event::Removed(id),
+            clipcat_base::ClipEvent::Marked(metadata) => clip_event::Event::Marked(metadata.into()),
+            clipcat_base::ClipEvent::Pinned(metadata) => clip_event::Event::Pinned(metadata.into()),
+            clipcat_base::ClipEvent::Unpinned(metadata) => {
+                clip_event::Event::Unpinned(metadata.into())
+            }
+            clipcat_base::ClipEvent::Cleared => clip_event::Event::Cleared(()),
+        };
+        Self { event: Some(event) }
+    }
+}
+
+impl From<clip_event::Event> for clipcat_base::ClipEvent {
+    fn from(event: clip_event::Event) -> Self {
+        match event {
+            clip_event::Event::Inserted(metadata) => Self::Inserted(metadata.into()),
+            clip_event::Event::Removed(id) => Self::Removed { id },
+            clip_event::Event::Marked(metadata) => Self::Marked(metadata.into()),
+            clip_event::Event::Pinned(metadata) => Self::Pinned(metadata.into()),
+            clip_event::Event::Unpinned(metadata) => Self::Unpinned(metadata.into()),
+            clip_event::Event::Cleared(()) => Self::Cleared,
+        }
     }
 }
 
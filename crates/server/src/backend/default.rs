@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashSet, hash::BuildHasher, sync::Arc};
 
 use async_trait::async_trait;
 use clipcat_base::{ClipboardContent, ClipboardKind};
@@ -15,10 +15,14 @@ pub struct Backend {
 
 impl Backend {
     /// # Errors
-    pub fn new(event_observers: &[Arc<dyn clipcat_clipboard::EventObserver>]) -> Result<Self> {
+    pub fn new<S: BuildHasher>(
+        event_observers: &[Arc<dyn clipcat_clipboard::EventObserver>],
+        sensitive_mime_types: &HashSet<String, S>,
+    ) -> Result<Self> {
+        let sensitive_mime_types = sensitive_mime_types.iter().cloned().collect::<HashSet<_>>();
         let mut clipboards = Vec::with_capacity(ClipboardKind::MAX_LENGTH);
         for kind in [ClipboardKind::Clipboard, ClipboardKind::Primary, ClipboardKind::Secondary] {
-            match Clipboard::new(kind, event_observers.to_vec())
+            match Clipboard::new(kind, sensitive_mime_types.clone(), event_observers.to_vec())
                 .context(error::InitializeClipboardSnafu)
             {
                 Ok(clipboard) => clipboards.push(Arc::new(clipboard)),
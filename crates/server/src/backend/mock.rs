@@ -12,6 +12,10 @@ pub struct Backend(MockClipboard);
 impl Backend {
     #[must_use]
     pub fn new() -> Self { Self::default() }
+
+    /// Creates a backend which serves only the clipboard of `kind`.
+    #[must_use]
+    pub fn with_kind(kind: ClipboardKind) -> Self { Self(MockClipboard::with_kind(kind)) }
 }
 
 #[async_trait]
@@ -61,5 +65,5 @@ impl traits::Backend for Backend {
     }
 
     #[inline]
-    fn supported_clipboard_kinds(&self) -> Vec<ClipboardKind> { vec![ClipboardKind::Clipboard] }
+    fn supported_clipboard_kinds(&self) -> Vec<ClipboardKind> { vec![self.0.kind()] }
 }
@@ -4,9 +4,10 @@ mod mock;
 mod subscriber;
 mod traits;
 
-use std::sync::Arc;
+use std::{collections::HashSet, hash::BuildHasher, sync::Arc};
 
 use clipcat_base::ClipboardKind;
+pub use clipcat_clipboard::ClipboardEvent;
 use clipcat_clipboard::EventObserver;
 
 use self::error::Result;
@@ -17,11 +18,17 @@ pub use self::{
 };
 
 /// # Errors
-pub fn new(event_observers: &[Arc<dyn EventObserver>]) -> Result<Box<dyn traits::Backend>> {
-    Ok(Box::new(DefaultClipboardBackend::new(event_observers)?))
+pub fn new<S: BuildHasher>(
+    event_observers: &[Arc<dyn EventObserver>],
+    sensitive_mime_types: &HashSet<String, S>,
+) -> Result<Box<dyn traits::Backend>> {
+    Ok(Box::new(DefaultClipboardBackend::new(event_observers, sensitive_mime_types)?))
 }
 
 /// # Errors
-pub fn new_shared(event_observers: &[Arc<dyn EventObserver>]) -> Result<Arc<dyn traits::Backend>> {
-    Ok(Arc::new(DefaultClipboardBackend::new(event_observers)?))
+pub fn new_shared<S: BuildHasher>(
+    event_observers: &[Arc<dyn EventObserver>],
+    sensitive_mime_types: &HashSet<String, S>,
+) -> Result<Arc<dyn traits::Backend>> {
+    Ok(Arc::new(DefaultClipboardBackend::new(event_observers, sensitive_mime_types)?))
 }
@@ -1,17 +1,17 @@
 use std::iter::IntoIterator;
 
 use clipcat_base::ClipboardKind;
-use clipcat_clipboard::ClipboardWait;
+use clipcat_clipboard::{ClipboardEvent, ClipboardWait};
 use tokio::{sync::mpsc, task};
 
 #[derive(Debug)]
 pub struct Subscriber {
-    receiver: mpsc::UnboundedReceiver<(ClipboardKind, mime::Mime)>,
+    receiver: mpsc::UnboundedReceiver<(ClipboardKind, ClipboardEvent)>,
     join_handles: task::JoinSet<()>,
 }
 
 impl Subscriber {
-    pub async fn next(&mut self) -> Option<(ClipboardKind, mime::Mime)> {
+    pub async fn next(&mut self) -> Option<(ClipboardKind, ClipboardEvent)> {
         self.receiver.recv().await
     }
 }
@@ -35,12 +35,12 @@ where
                 let _unused = join_handles.spawn_blocking({
                     let event_sender = sender.clone();
                     move || {
-                        while let Ok(kind) = subscriber.wait() {
+                        while let Ok(event) = subscriber.wait() {
                             if event_sender.is_closed() {
                                 break;
                             }
 
-                            if let Err(_err) = event_sender.send(kind) {
+                            if let Err(_err) = event_sender.send(event) {
                                 break;
                             }
                         }
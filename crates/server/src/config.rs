@@ -1,4 +1,6 @@
-use std::{net::SocketAddr, path::PathBuf, time::Duration};
+use std::{fmt, net::SocketAddr, path::PathBuf, time::Duration};
+
+use clipcat_base::ClipboardKind;
 
 use crate::ClipboardWatcherOptions;
 
@@ -8,15 +10,156 @@ pub struct Config {
 
     pub grpc_local_socket: Option<PathBuf>,
 
-    pub max_history: usize,
+    pub local_socket_access: LocalSocketAccess,
+
+    pub grpc_tls: Option<GrpcTlsConfig>,
+
+    pub grpc_access_token: Option<AccessToken>,
+
+    pub webui_listen_address: Option<SocketAddr>,
+
+    // required if the web UI is served
+    pub webui_access_token: Option<AccessToken>,
+
+    pub rest_listen_address: Option<SocketAddr>,
+
+    // required if the JSON API is served over TCP
+    pub rest_access_token: Option<AccessToken>,
+
+    pub rest_local_socket: Option<PathBuf>,
+
+    // well-known name which is owned on the session bus
+    pub dbus_name: Option<String>,
+
+    pub max_history: HistoryCapacity,
+
+    pub max_age: Option<Duration>,
 
     pub history_file_path: PathBuf,
 
+    pub history_backend: HistoryBackend,
+
+    pub history_encryption_key: Option<EncryptionKey>,
+
     pub watcher: ClipboardWatcherOptions,
 
     pub desktop_notification: DesktopNotificationConfig,
 }
 
+/// Maximum number of clips kept for each clipboard kind, pinned clips are not
+/// counted, so that frequent changes of the primary selection do not evict
+/// clips copied to the clipboard.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HistoryCapacity {
+    pub clipboard: usize,
+
+    pub primary: usize,
+
+    pub secondary: usize,
+}
+
+impl HistoryCapacity {
+    #[inline]
+    #[must_use]
+    pub const fn get(&self, kind: ClipboardKind) -> usize {
+        match kind {
+            ClipboardKind::Clipboard => self.clipboard,
+            ClipboardKind::Primary => self.primary,
+            ClipboardKind::Secondary => self.secondary,
+        }
+    }
+}
+
+impl From<usize> for HistoryCapacity {
+    fn from(capacity: usize) -> Self {
+        Self { clipboard: capacity, primary: capacity, secondary: capacity }
+    }
+}
+
+impl fmt::Display for HistoryCapacity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { clipboard, primary, secondary } = self;
+        write!(f, "clipboard: {clipboard}, primary: {primary}, secondary: {secondary}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum HistoryBackend {
+    #[default]
+    FileSystem,
+
+    Sqlite,
+}
+
+/// Key material, e.g. content of a key file or a passphrase. The key which
+/// encrypts clips is derived from it with Argon2id and a random salt stored
+/// along with the history.
+#[derive(Clone, Eq, PartialEq)]
+pub struct EncryptionKey(Vec<u8>);
+
+impl EncryptionKey {
+    #[inline]
+    #[must_use]
+    pub const fn new(material: Vec<u8>) -> Self { Self(material) }
+
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] { &self.0 }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(<redacted>)")
+    }
+}
+
+/// Users and groups which are allowed to connect to Unix domain sockets
+/// besides the owner of the daemon, peers are identified by `SO_PEERCRED`
+/// so only the primary group of a peer is checked.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LocalSocketAccess {
+    pub allowed_uids: Vec<u32>,
+
+    pub allowed_gids: Vec<u32>,
+}
+
+impl LocalSocketAccess {
+    #[must_use]
+    pub fn is_allowed(&self, owner: u32, user: u32, group: u32) -> bool {
+        user == owner || self.allowed_uids.contains(&user) || self.allowed_gids.contains(&group)
+    }
+}
+
+/// Certificate and private key of the gRPC HTTP server, both of them are PEM
+/// encoded.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GrpcTlsConfig {
+    pub cert_file: PathBuf,
+
+    pub key_file: PathBuf,
+}
+
+/// Token which clients present as a bearer token to servers which listen on
+/// TCP, e.g. the gRPC HTTP server, the web UI and the JSON API.
+#[derive(Clone, Eq, PartialEq)]
+pub struct AccessToken(String);
+
+impl AccessToken {
+    #[inline]
+    #[must_use]
+    pub const fn new(token: String) -> Self { Self(token) }
+
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> &str { self.0.as_str() }
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AccessToken(<redacted>)")
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DesktopNotificationConfig {
     pub enable: bool,
@@ -0,0 +1,253 @@
+use std::{collections::HashMap, str::FromStr};
+
+use clipcat_base::{ClipEntryMetadata, ClipEvent, SearchMode, SearchQuery};
+use clipcat_proto::{self as proto, Manager as _};
+use time::OffsetDateTime;
+use tonic::Request;
+use zbus::{dbus_interface, zvariant::OwnedValue, SignalContext};
+
+use crate::{
+    dbus::{parse_kind, Clip, ClipData, Error},
+    grpc::ManagerService,
+    notification,
+};
+
+const DEFAULT_PREVIEW_LENGTH: usize = 100;
+
+pub struct Manager<Notification> {
+    service: ManagerService<Notification>,
+}
+
+impl<Notification> Manager<Notification> {
+    pub const fn new(service: ManagerService<Notification>) -> Self { Self { service } }
+}
+
+// the implementation of `Interface` generated by the macro names the type
+#[allow(clippy::use_self)]
+#[dbus_interface(name = "org.clipcat.Daemon.Manager")]
+impl<Notification> Manager<Notification>
+where
+    Notification: notification::Notification + 'static,
+{
+    /// Lists clips of `kind` with the newest one first, clips of all kinds are
+    /// listed if `kind` is empty.
+    async fn list(&self, kind: &str, preview_length: u64) -> Result<Vec<Clip>, Error> {
+        let kinds = parse_kind(kind)?
+            .into_iter()
+            .map(|kind| proto::ClipboardKind::from(kind).into())
+            .collect();
+        let request = proto::ListRequest { preview_length, kinds };
+        let mut metadata: Vec<_> = self
+            .service
+            .list(Request::new(request))
+            .await?
+            .into_inner()
+            .metadata
+            .into_iter()
+            .map(ClipEntryMetadata::from)
+            .collect();
+        metadata.sort_unstable();
+        Ok(metadata.into_iter().map(Clip::from).collect())
+    }
+
+    /// Searches clips for `pattern`, `options` may hold `regex` (b),
+    /// `case_sensitive` (b), `kind` (s), `mime` (s), `since` (x), `until` (x),
+    /// `offset` (t), `limit` (t) and `preview_length` (t).
+    #[dbus_interface(out_args("clips", "total"))]
+    async fn search(
+        &self,
+        pattern: String,
+        options: HashMap<String, OwnedValue>,
+    ) -> Result<(Vec<Clip>, u64), Error> {
+        let request = proto::SearchRequest::from(search_query(pattern, &options)?);
+        let proto::SearchResponse { metadata, total } =
+            self.service.search(Request::new(request)).await?.into_inner();
+        let clips =
+            metadata.into_iter().map(|metadata| ClipEntryMetadata::from(metadata).into()).collect();
+        Ok((clips, total))
+    }
+
+    async fn get(&self, id: u64) -> Result<ClipData, Error> {
+        self.service
+            .get(Request::new(proto::GetRequest { id }))
+            .await?
+            .into_inner()
+            .data
+            .map(ClipData::from)
+            .ok_or_else(|| Error::NotFound(format!("Clip {id:016x} is not found")))
+    }
+
+    async fn get_current_clip(&self, kind: &str) -> Result<ClipData, Error> {
+        let kind = parse_kind(kind)?.unwrap_or_default();
+        let request =
+            proto::GetCurrentClipRequest { kind: proto::ClipboardKind::from(kind).into() };
+        self.service
+            .get_current_clip(Request::new(request))
+            .await?
+            .into_inner()
+            .data
+            .map(ClipData::from)
+            .ok_or_else(|| Error::NotFound(format!("There is no clip in {kind}")))
+    }
+
+    /// Inserts a clip and marks it as the current one of `kind`, `mime` is
+    /// plaintext if it is empty. The clip expires after `ttl_ms`
+    /// milliseconds, it never expires if `ttl_ms` is 0.
+    async fn insert(
+        &self,
+        kind: &str,
+        mime: &str,
+        data: Vec<u8>,
+        ttl_ms: u64,
+    ) -> Result<u64, Error> {
+        let kind = parse_kind(kind)?.unwrap_or_default();
+        let mime =
+            if mime.is_empty() { mime::TEXT_PLAIN_UTF_8.to_string() } else { mime.to_string() };
+        let request = proto::InsertRequest {
+            kind: proto::ClipboardKind::from(kind).into(),
+            data,
+            mime,
+            ttl_ms,
+        };
+        Ok(self.service.insert(Request::new(request)).await?.into_inner().id)
+    }
+
+    /// Replaces the content of clip, the ID of the updated clip is returned.
+    async fn update(&self, id: u64, data: Vec<u8>, mime: &str) -> Result<u64, Error> {
+        let request = proto::UpdateRequest { id, data, mime: mime.to_string() };
+        let proto::UpdateResponse { ok, new_id } =
+            self.service.update(Request::new(request)).await?.into_inner();
+        if ok {
+            Ok(new_id)
+        } else {
+            Err(Error::NotFound(format!("Clip {id:016x} is not found")))
+        }
+    }
+
+    async fn mark(&self, id: u64, kind: &str) -> Result<bool, Error> {
+        let kind = parse_kind(kind)?.unwrap_or_default();
+        let request = proto::MarkRequest { id, kind: proto::ClipboardKind::from(kind).into() };
+        Ok(self.service.mark(Request::new(request)).await?.into_inner().ok)
+    }
+
+    async fn pin(&self, id: u64) -> Result<bool, Error> {
+        Ok(self.service.pin(Request::new(proto::PinRequest { id })).await?.into_inner().ok)
+    }
+
+    async fn unpin(&self, id: u64) -> Result<bool, Error> {
+        Ok(self.service.unpin(Request::new(proto::UnpinRequest { id })).await?.into_inner().ok)
+    }
+
+    async fn remove(&self, id: u64) -> Result<bool, Error> {
+        Ok(self.service.remove(Request::new(proto::RemoveRequest { id })).await?.into_inner().ok)
+    }
+
+    /// Removes clips, IDs of the removed ones are returned.
+    async fn batch_remove(&self, ids: Vec<u64>) -> Result<Vec<u64>, Error> {
+        let request = proto::BatchRemoveRequest { ids };
+        Ok(self.service.batch_remove(Request::new(request)).await?.into_inner().ids)
+    }
+
+    async fn clear(&self) -> Result<(), Error> {
+        let _unused = self.service.clear(Request::new(())).await?;
+        Ok(())
+    }
+
+    async fn length(&self) -> Result<u64, Error> {
+        Ok(self.service.length(Request::new(())).await?.into_inner().length)
+    }
+
+    #[dbus_interface(signal)]
+    pub async fn clip_inserted(context: &SignalContext<'_>, clip: Clip) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub async fn clip_removed(context: &SignalContext<'_>, id: u64) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub async fn clip_marked(context: &SignalContext<'_>, clip: Clip) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub async fn clip_pinned(context: &SignalContext<'_>, clip: Clip) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub async fn clip_unpinned(context: &SignalContext<'_>, clip: Clip) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub async fn clips_cleared(context: &SignalContext<'_>) -> zbus::Result<()>;
+}
+
+pub async fn emit_clip_event<Notification>(
+    context: &SignalContext<'_>,
+    event: ClipEvent,
+) -> zbus::Result<()>
+where
+    Notification: notification::Notification + 'static,
+{
+    match event {
+        ClipEvent::Inserted(metadata) => {
+            Manager::<Notification>::clip_inserted(context, metadata.into()).await
+        }
+        ClipEvent::Removed { id } => Manager::<Notification>::clip_removed(context, id).await,
+        ClipEvent::Marked(metadata) => {
+            Manager::<Notification>::clip_marked(context, metadata.into()).await
+        }
+        ClipEvent::Pinned(metadata) => {
+            Manager::<Notification>::clip_pinned(context, metadata.into()).await
+        }
+        ClipEvent::Unpinned(metadata) => {
+            Manager::<Notification>::clip_unpinned(context, metadata.into()).await
+        }
+        ClipEvent::Cleared => Manager::<Notification>::clips_cleared(context).await,
+    }
+}
+
+fn search_query(
+    pattern: String,
+    options: &HashMap<String, OwnedValue>,
+) -> Result<SearchQuery, Error> {
+    let mimes = match option::<&str>(options, "mime")? {
+        Some(mime) => vec![mime::Mime::from_str(mime)
+            .map_err(|_err| Error::InvalidArgument(format!("Invalid MIME type `{mime}`")))?],
+        None => Vec::new(),
+    };
+    let timestamp = |key| {
+        option::<i64>(options, key)?
+            .map(|timestamp| {
+                OffsetDateTime::from_unix_timestamp(timestamp)
+                    .map_err(|_err| Error::InvalidArgument(format!("Invalid timestamp `{key}`")))
+            })
+            .transpose()
+    };
+    let to_usize = |n: u64| usize::try_from(n).unwrap_or(usize::MAX);
+
+    Ok(SearchQuery {
+        pattern,
+        mode: if option(options, "regex")?.unwrap_or(false) {
+            SearchMode::Regex
+        } else {
+            SearchMode::Substring
+        },
+        case_sensitive: option(options, "case_sensitive")?.unwrap_or(false),
+        kinds: parse_kind(option(options, "kind")?.unwrap_or_default())?.into_iter().collect(),
+        mimes,
+        since: timestamp("since")?,
+        until: timestamp("until")?,
+        offset: option(options, "offset")?.map_or(0, to_usize),
+        limit: option(options, "limit")?.map(to_usize),
+        preview_length: option(options, "preview_length")?.map_or(DEFAULT_PREVIEW_LENGTH, to_usize),
+    })
+}
+
+fn option<'a, T>(options: &'a HashMap<String, OwnedValue>, key: &str) -> Result<Option<T>, Error>
+where
+    T: TryFrom<&'a OwnedValue>,
+{
+    options
+        .get(key)
+        .map(|value| {
+            T::try_from(value).map_err(|_err| {
+                Error::InvalidArgument(format!("Option `{key}` is of an invalid type"))
+            })
+        })
+        .transpose()
+}
@@ -0,0 +1,332 @@
+mod manager;
+mod watcher;
+
+use std::{future::Future, str::FromStr, sync::Arc};
+
+use clipcat_base::{ClipEntry, ClipEntryMetadata, ClipboardKind};
+use clipcat_proto as proto;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast::error::RecvError, watch, Mutex};
+use tonic::{Code, Status};
+use zbus::{zvariant::Type, ConnectionBuilder, DBusError, SignalContext};
+
+use self::{manager::Manager, watcher::Watcher};
+use crate::{
+    grpc::{ManagerService, WatcherService},
+    history::HistoryManager,
+    notification, ClipboardManager, ClipboardWatcherToggle,
+};
+
+// length of previews which are carried by signals
+const SIGNAL_PREVIEW_LENGTH: usize = 100;
+
+/// Serves the `Manager` and `Watcher` interfaces on the connection built by
+/// `connection_builder`, every method calls the corresponding RPC like the
+/// JSON API does. Signals are emitted until `shutdown` is resolved.
+#[allow(clippy::redundant_pub_crate)]
+pub async fn serve<Notification, Shutdown>(
+    connection_builder: ConnectionBuilder<'_>,
+    clipboard_watcher_toggle: ClipboardWatcherToggle<Notification>,
+    clipboard_manager: Arc<Mutex<ClipboardManager<Notification>>>,
+    history_manager: Arc<Mutex<HistoryManager>>,
+    shutdown: Shutdown,
+) -> zbus::Result<()>
+where
+    Notification: notification::Notification + 'static,
+    Shutdown: Future<Output = ()> + Send,
+{
+    let mut event_receiver = clipboard_manager.lock().await.subscribe();
+    let mut state_receiver = clipboard_watcher_toggle.subscribe();
+    // `Subscribe` is not exported, the receiver is never notified
+    let (_shutdown_sender, shutdown_receiver) = watch::channel(());
+
+    let connection = connection_builder
+        .serve_at(
+            clipcat_base::DBUS_OBJECT_PATH,
+            Manager::new(ManagerService::new(
+                clipboard_manager,
+                history_manager,
+                shutdown_receiver,
+            )),
+        )?
+        .serve_at(
+            clipcat_base::DBUS_OBJECT_PATH,
+            Watcher::new(WatcherService::new(clipboard_watcher_toggle)),
+        )?
+        .build()
+        .await?;
+    let context = SignalContext::new(&connection, clipcat_base::DBUS_OBJECT_PATH)?;
+
+    tokio::pin!(shutdown);
+    loop {
+        let result = tokio::select! {
+            event = event_receiver.recv() => match event {
+                Ok(event) => {
+                    let event = event.to_clip_event(SIGNAL_PREVIEW_LENGTH);
+                    manager::emit_clip_event::<Notification>(&context, event).await
+                }
+                Err(RecvError::Lagged(n)) => {
+                    tracing::warn!("D-Bus server lagged too far behind, {n} event(s) are dropped");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            Ok(()) = state_receiver.changed() => {
+                let state = *state_receiver.borrow_and_update();
+                Watcher::<Notification>::watcher_state_changed(&context, state.as_str()).await
+            }
+            () = &mut shutdown => break,
+        };
+        if let Err(err) = result {
+            tracing::warn!("Could not emit D-Bus signal, error: {err}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Metadata of clip, `timestamp` is the number of seconds since the Unix
+/// epoch.
+#[derive(Clone, Debug, Deserialize, Serialize, Type)]
+pub struct Clip {
+    id: u64,
+
+    kind: String,
+
+    timestamp: i64,
+
+    mime: String,
+
+    preview: String,
+
+    pinned: bool,
+}
+
+impl From<ClipEntryMetadata> for Clip {
+    fn from(
+        ClipEntryMetadata { id, kind, timestamp, mime, preview, pinned, .. }: ClipEntryMetadata,
+    ) -> Self {
+        Self {
+            id,
+            kind: kind.as_str().to_string(),
+            timestamp: timestamp.unix_timestamp(),
+            mime: mime.to_string(),
+            preview,
+            pinned,
+        }
+    }
+}
+
+/// Clip with its content.
+#[derive(Clone, Debug, Deserialize, Serialize, Type)]
+pub struct ClipData {
+    id: u64,
+
+    kind: String,
+
+    timestamp: i64,
+
+    mime: String,
+
+    pinned: bool,
+
+    data: Vec<u8>,
+}
+
+impl From<proto::ClipEntry> for ClipData {
+    fn from(clip: proto::ClipEntry) -> Self {
+        let clip = ClipEntry::from(clip);
+        Self {
+            id: clip.id(),
+            kind: clip.kind().as_str().to_string(),
+            timestamp: clip.timestamp().unix_timestamp(),
+            mime: clip.mime().to_string(),
+            pinned: clip.is_pinned(),
+            data: clip.as_bytes().to_vec(),
+        }
+    }
+}
+
+// an empty string stands for the default kind, or all kinds if it filters clips
+fn parse_kind(kind: &str) -> Result<Option<ClipboardKind>, Error> {
+    if kind.is_empty() {
+        return Ok(None);
+    }
+    ClipboardKind::from_str(kind)
+        .map(Some)
+        .map_err(|_err| Error::InvalidArgument(format!("Unknown clipboard kind `{kind}`")))
+}
+
+#[derive(Debug, DBusError)]
+#[dbus_error(prefix = "org.clipcat.Daemon.Error")]
+pub enum Error {
+    #[dbus_error(zbus_error)]
+    ZBus(zbus::Error),
+
+    NotFound(String),
+
+    InvalidArgument(String),
+
+    Failed(String),
+}
+
+impl From<Status> for Error {
+    fn from(status: Status) -> Self {
+        let message = status.message().to_string();
+        match status.code() {
+            Code::InvalidArgument => Self::InvalidArgument(message),
+            Code::NotFound => Self::NotFound(message),
+            _ => Self::Failed(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc, time::Duration};
+
+    use futures::{StreamExt, TryStreamExt};
+    use serde::Serialize;
+    use tokio::{
+        net::UnixStream,
+        sync::{oneshot, Mutex},
+    };
+    use zbus::{
+        zvariant::{DynamicType, Value},
+        Connection, ConnectionBuilder, Guid, Message, MessageStream, MessageType,
+    };
+
+    use crate::{
+        backend::MockClipboardBackend,
+        config::HistoryBackend,
+        dbus::{serve, Clip, ClipData},
+        history::HistoryManager,
+        notification::MockNotification,
+        ClipboardManager, ClipboardWatcher, ClipboardWatcherOptions,
+    };
+
+    const MANAGER: &str = "org.clipcat.Daemon.Manager";
+    const WATCHER: &str = "org.clipcat.Daemon.Watcher";
+
+    async fn call<B>(
+        connection: &Connection,
+        interface: &str,
+        method: &str,
+        body: B,
+    ) -> zbus::Result<Arc<Message>>
+    where
+        B: Serialize + DynamicType + Send + Sync,
+    {
+        connection
+            .call_method(
+                None::<&str>,
+                clipcat_base::DBUS_OBJECT_PATH,
+                Some(interface),
+                method,
+                &body,
+            )
+            .await
+    }
+
+    async fn next_signal(messages: &mut MessageStream, member: &str) -> Arc<Message> {
+        let next = messages.try_filter(|message| {
+            let is_signal = message.message_type() == MessageType::Signal
+                && message.member().is_some_and(|name| name.as_str() == member);
+            futures::future::ready(is_signal)
+        });
+        tokio::time::timeout(Duration::from_secs(5), Box::pin(next).next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_dbus() {
+        let file_path =
+            std::env::temp_dir().join(format!("clipcat-dbus-test-{pid}", pid = std::process::id()));
+        let history_manager =
+            HistoryManager::new(&file_path, HistoryBackend::FileSystem, None).await.unwrap();
+        let backend = Arc::new(MockClipboardBackend::new());
+        let watcher = ClipboardWatcher::new(
+            backend.clone(),
+            &ClipboardWatcherOptions::default(),
+            MockNotification::default(),
+        )
+        .unwrap();
+        let manager = ClipboardManager::new(backend, MockNotification::default());
+
+        let (server_stream, client_stream) = UnixStream::pair().unwrap();
+        let guid = Guid::generate();
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+        let server = serve(
+            ConnectionBuilder::unix_stream(server_stream).server(&guid).p2p(),
+            watcher.get_toggle(),
+            Arc::new(Mutex::new(manager)),
+            Arc::new(Mutex::new(history_manager)),
+            async move {
+                let _unused = shutdown_receiver.await;
+            },
+        );
+
+        let client = async move {
+            let connection =
+                ConnectionBuilder::unix_stream(client_stream).p2p().build().await.unwrap();
+            let mut messages = MessageStream::from(&connection);
+            let reply =
+                call(&connection, MANAGER, "Insert", ("primary", "", b"Alpha".to_vec(), 0_u64))
+                    .await
+                    .unwrap();
+            let id: u64 = reply.body().unwrap();
+            let clip: Clip = next_signal(&mut messages, "ClipInserted").await.body().unwrap();
+            assert_eq!(
+                (clip.id, clip.kind.as_str(), clip.preview.as_str()),
+                (id, "Primary", "Alpha")
+            );
+
+            let reply = call(&connection, MANAGER, "Get", (id,)).await.unwrap();
+            let clip: ClipData = reply.body().unwrap();
+            assert_eq!(
+                (clip.mime.as_str(), clip.data.as_slice()),
+                ("text/plain; charset=utf-8", &b"Alpha"[..])
+            );
+
+            let reply = call(&connection, MANAGER, "List", ("clipboard", 100_u64)).await.unwrap();
+            assert!(reply.body::<Vec<Clip>>().unwrap().is_empty());
+
+            let options = HashMap::from([("kind", Value::from("primary"))]);
+            let reply = call(&connection, MANAGER, "Search", ("ALP", options)).await.unwrap();
+            let (clips, total): (Vec<Clip>, u64) = reply.body().unwrap();
+            assert_eq!((clips[0].id, total), (id, 1));
+            let options = HashMap::from([("case_sensitive", Value::from(true))]);
+            let reply = call(&connection, MANAGER, "Search", ("ALP", options)).await.unwrap();
+            assert_eq!(reply.body::<(Vec<Clip>, u64)>().unwrap().1, 0);
+
+            let error_name = |result: zbus::Result<Arc<Message>>| match result {
+                Err(zbus::Error::MethodError(name, ..)) => name.to_string(),
+                result => panic!("Unexpected result: {result:?}"),
+            };
+            let result = call(&connection, MANAGER, "Get", (0_u64,)).await;
+            assert_eq!(error_name(result), "org.clipcat.Daemon.Error.NotFound");
+            let result = call(&connection, MANAGER, "List", ("unknown", 100_u64)).await;
+            assert_eq!(error_name(result), "org.clipcat.Daemon.Error.InvalidArgument");
+
+            let reply = call(&connection, WATCHER, "ToggleWatcher", ()).await.unwrap();
+            assert_eq!(reply.body::<String>().unwrap(), "Disabled");
+            let signal = next_signal(&mut messages, "WatcherStateChanged").await;
+            assert_eq!(signal.body::<String>().unwrap(), "Disabled");
+
+            let reply = call(&connection, MANAGER, "Remove", (id,)).await.unwrap();
+            assert!(reply.body::<bool>().unwrap());
+            let signal = next_signal(&mut messages, "ClipRemoved").await;
+            assert_eq!(signal.body::<u64>().unwrap(), id);
+
+            drop(shutdown_sender);
+        };
+
+        let (result, ()) = tokio::join!(server, client);
+        result.unwrap();
+
+        drop(std::fs::remove_dir_all(file_path));
+    }
+}
@@ -0,0 +1,51 @@
+use clipcat_base::ClipboardWatcherState;
+use clipcat_proto::{self as proto, Watcher as _};
+use tonic::{Request, Response, Status};
+use zbus::{dbus_interface, SignalContext};
+
+use crate::{dbus::Error, grpc::WatcherService, notification};
+
+pub struct Watcher<Notification> {
+    service: WatcherService<Notification>,
+}
+
+impl<Notification> Watcher<Notification> {
+    pub const fn new(service: WatcherService<Notification>) -> Self { Self { service } }
+}
+
+fn reply(reply: Result<Response<proto::WatcherStateReply>, Status>) -> Result<String, Error> {
+    let state = ClipboardWatcherState::from(reply?.into_inner().state);
+    Ok(state.as_str().to_string())
+}
+
+// the implementation of `Interface` generated by the macro names the type
+#[allow(clippy::use_self)]
+#[dbus_interface(name = "org.clipcat.Daemon.Watcher")]
+impl<Notification> Watcher<Notification>
+where
+    Notification: notification::Notification + 'static,
+{
+    async fn enable_watcher(&self) -> Result<String, Error> {
+        reply(self.service.enable_watcher(Request::new(())).await)
+    }
+
+    async fn disable_watcher(&self) -> Result<String, Error> {
+        reply(self.service.disable_watcher(Request::new(())).await)
+    }
+
+    async fn toggle_watcher(&self) -> Result<String, Error> {
+        reply(self.service.toggle_watcher(Request::new(())).await)
+    }
+
+    async fn get_watcher_state(&self) -> Result<String, Error> {
+        reply(self.service.get_watcher_state(Request::new(())).await)
+    }
+
+    /// Emitted whenever the watcher is enabled or disabled, no matter which
+    /// API changed it.
+    #[dbus_interface(signal)]
+    pub async fn watcher_state_changed(
+        context: &SignalContext<'_>,
+        state: &str,
+    ) -> zbus::Result<()>;
+}
@@ -10,6 +10,21 @@ pub enum Error {
     #[snafu(display("Error occurs while starting tonic server, error: {source}"))]
     StartTonicServer { source: tonic::transport::Error, backtrace: Backtrace },
 
+    #[snafu(display("Could not read TLS file `{}`, error: {source}", file_path.display()))]
+    ReadTlsFile { file_path: PathBuf, source: std::io::Error, backtrace: Backtrace },
+
+    #[snafu(display("Could not configure TLS of gRPC server, error: {source}"))]
+    ConfigureTls { source: tonic::transport::Error, backtrace: Backtrace },
+
+    #[snafu(display("{server} listens on TCP, an access token is required"))]
+    MissingAccessToken { server: String, backtrace: Backtrace },
+
+    #[snafu(display("Error occurs while starting HTTP server on {endpoint}, error: {source}"))]
+    StartHttpServer { endpoint: String, source: hyper::Error, backtrace: Backtrace },
+
+    #[snafu(display("Error occurs while serving D-Bus interfaces as `{name}`, error: {source}"))]
+    StartDBusServer { name: String, source: zbus::Error, backtrace: Backtrace },
+
     #[snafu(display("Error occurs while creating Unix domain socket listener on `{}`, error: {source}", socket_path.display()))]
     CreateUnixListener { socket_path: PathBuf, source: std::io::Error, backtrace: Backtrace },
 
@@ -22,6 +37,9 @@ pub enum Error {
     #[snafu(display("Could not load HistoryManager, error: {source}"))]
     LoadHistoryManager { source: crate::history::Error },
 
+    #[snafu(display("Could not save HistoryManager, error: {source}"))]
+    SaveHistoryManager { source: crate::history::Error },
+
     #[snafu(display("Could not clear HistoryManager, error: {source}"))]
     ClearHistoryManager { source: crate::history::Error },
 
@@ -0,0 +1,73 @@
+use tonic::{metadata::MetadataValue, service::Interceptor, Request, Status};
+
+use crate::config::AccessToken;
+
+/// Rejects requests which do not present the access token as a bearer token,
+/// every request is accepted if there is no access token. It is also used by
+/// HTTP servers which serve the JSON API over TCP.
+#[derive(Clone, Debug, Default)]
+pub struct Authenticator {
+    // expected value of `authorization` metadata
+    authorization: Option<Vec<u8>>,
+}
+
+impl Authenticator {
+    pub fn new(access_token: Option<&AccessToken>) -> Self {
+        let authorization = access_token
+            .map(|token| format!("Bearer {token}", token = token.as_str()).into_bytes());
+        Self { authorization }
+    }
+
+    /// Checks the value of `authorization` metadata or header.
+    pub fn is_authorized(&self, authorization: Option<&[u8]>) -> bool {
+        self.authorization.as_ref().map_or(true, |expected| {
+            authorization.is_some_and(|value| constant_time_eq(value, expected))
+        })
+    }
+}
+
+impl Interceptor for Authenticator {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let authorization = request.metadata().get("authorization").map(MetadataValue::as_bytes);
+        if self.is_authorized(authorization) {
+            Ok(request)
+        } else {
+            Err(Status::unauthenticated("Access token is missing or invalid"))
+        }
+    }
+}
+
+// do not leak how many leading bytes are matched through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::{service::Interceptor, Code, Request};
+
+    use crate::{config::AccessToken, grpc::auth::Authenticator};
+
+    fn request(authorization: Option<&str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(authorization) = authorization {
+            let _unused =
+                request.metadata_mut().insert("authorization", authorization.parse().unwrap());
+        }
+        request
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let token = AccessToken::new("secret".to_string());
+        let mut authenticator = Authenticator::new(Some(&token));
+        assert!(authenticator.call(request(Some("Bearer secret"))).is_ok());
+        for authorization in [None, Some("Bearer secreT"), Some("Bearer secret2"), Some("secret")] {
+            let status = authenticator.call(request(authorization)).unwrap_err();
+            assert_eq!(status.code(), Code::Unauthenticated);
+        }
+
+        let mut authenticator = Authenticator::new(None);
+        assert!(authenticator.call(request(None)).is_ok());
+    }
+}
@@ -1,17 +1,31 @@
-use std::{str::FromStr, sync::Arc};
+use std::{pin::Pin, str::FromStr, sync::Arc};
 
 use clipcat_proto as proto;
-use tokio::sync::Mutex;
+use futures::{Stream, StreamExt};
+use tokio::sync::{watch, Mutex};
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 use tonic::{Request, Response, Status};
 
-use crate::{notification, ClipboardManager};
+use crate::{history::HistoryManager, manager, notification, ClipboardManager};
 
 pub struct ManagerService<Notification> {
     manager: Arc<Mutex<ClipboardManager<Notification>>>,
+
+    history_manager: Arc<Mutex<HistoryManager>>,
+
+    // subscriptions are terminated once the sender is dropped,
+    // otherwise the server could not be shut down gracefully
+    shutdown_receiver: watch::Receiver<()>,
 }
 
 impl<Notification> ManagerService<Notification> {
-    pub fn new(manager: Arc<Mutex<ClipboardManager<Notification>>>) -> Self { Self { manager } }
+    pub fn new(
+        manager: Arc<Mutex<ClipboardManager<Notification>>>,
+        history_manager: Arc<Mutex<HistoryManager>>,
+        shutdown_receiver: watch::Receiver<()>,
+    ) -> Self {
+        Self { manager, history_manager, shutdown_receiver }
+    }
 }
 
 #[tonic::async_trait]
@@ -19,18 +33,30 @@ impl<Notification> proto::Manager for ManagerService<Notification>
 where
     Notification: notification::Notification + 'static,
 {
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<proto::ClipEvent, Status>> + Send>>;
+
     async fn insert(
         &self,
         request: Request<proto::InsertRequest>,
     ) -> Result<Response<proto::InsertResponse>, Status> {
-        let proto::InsertRequest { data, mime, kind } = request.into_inner();
+        let proto::InsertRequest { data, mime, kind, ttl_ms } = request.into_inner();
         let id = {
             let mime = mime::Mime::from_str(&mime).unwrap_or(mime::APPLICATION_OCTET_STREAM);
+            let mut clip =
+                clipcat_base::ClipEntry::new(&data, &mime, kind.into(), None).unwrap_or_default();
+            if ttl_ms > 0 {
+                clip.set_expires_at(clip.timestamp().checked_add(time::Duration::milliseconds(
+                    i64::try_from(ttl_ms).unwrap_or(i64::MAX),
+                )));
+            }
             let mut manager = self.manager.lock().await;
-            let id = manager.insert(
-                clipcat_base::ClipEntry::new(&data, &mime, kind.into(), None).unwrap_or_default(),
-            );
+            let id = manager.insert(clip);
             let _unused = manager.mark(id, kind.into()).await;
+            if let Some(clip) = manager.get(id) {
+                if let Err(err) = self.history_manager.lock().await.put(&clip).await {
+                    tracing::error!("{err}");
+                }
+            }
             drop(manager);
             id
         };
@@ -44,7 +70,15 @@ where
         let id = request.into_inner().id;
         let ok = {
             let mut manager = self.manager.lock().await;
-            manager.remove(id)
+            // history stores clips with their current identifiers
+            let removed_id = manager.remove(id);
+            if let Some(id) = removed_id {
+                if let Err(err) = self.history_manager.lock().await.remove(&[id]).await {
+                    tracing::error!("{err}");
+                }
+            }
+            drop(manager);
+            removed_id.is_some()
         };
         Ok(Response::new(proto::RemoveResponse { ok }))
     }
@@ -56,7 +90,18 @@ where
         let ids = request.into_inner().ids;
         let ids = {
             let mut manager = self.manager.lock().await;
-            ids.into_iter().filter(|id| manager.remove(*id)).collect()
+            // history stores clips with their current identifiers
+            let (ids, removed_ids): (Vec<_>, Vec<_>) = ids
+                .into_iter()
+                .filter_map(|id| manager.remove(id).map(|removed_id| (id, removed_id)))
+                .unzip();
+            if !removed_ids.is_empty() {
+                if let Err(err) = self.history_manager.lock().await.remove(&removed_ids).await {
+                    tracing::error!("{err}");
+                }
+            }
+            drop(manager);
+            ids
         };
         Ok(Response::new(proto::BatchRemoveResponse { ids }))
     }
@@ -65,6 +110,19 @@ where
         {
             let mut manager = self.manager.lock().await;
             manager.clear();
+            // pinned clips are kept by the manager, they must not be lost from history
+            let retained = manager.export(false);
+            let mut history_manager = self.history_manager.lock().await;
+            if let Err(err) = history_manager.clear().await {
+                tracing::error!("{err}");
+            }
+            for clip in &retained {
+                if let Err(err) = history_manager.put(clip).await {
+                    tracing::error!("{err}");
+                }
+            }
+            drop(history_manager);
+            drop(manager);
         }
         Ok(Response::new(()))
     }
@@ -97,11 +155,13 @@ where
         &self,
         request: Request<proto::ListRequest>,
     ) -> Result<Response<proto::ListResponse>, Status> {
-        let proto::ListRequest { preview_length } = request.into_inner();
+        let request = request.into_inner();
+        let kinds = request.kinds().map(clipcat_base::ClipboardKind::from).collect::<Vec<_>>();
+        let preview_length = usize::try_from(request.preview_length).unwrap_or(30);
         let metadata = {
             let manager = self.manager.lock().await;
             manager
-                .list(usize::try_from(preview_length).unwrap_or(30))
+                .list(preview_length, &kinds)
                 .into_iter()
                 .map(proto::ClipEntryMetadata::from)
                 .collect()
@@ -109,6 +169,22 @@ where
         Ok(Response::new(proto::ListResponse { metadata }))
     }
 
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchResponse>, Status> {
+        let query = clipcat_base::SearchQuery::from(request.into_inner());
+        let (metadata, total) = {
+            let manager = self.manager.lock().await;
+            manager.search(&query).map_err(|err| Status::invalid_argument(err.to_string()))?
+        };
+        let metadata = metadata.into_iter().map(proto::ClipEntryMetadata::from).collect();
+        Ok(Response::new(proto::SearchResponse {
+            metadata,
+            total: u64::try_from(total).unwrap_or(u64::MAX),
+        }))
+    }
+
     async fn update(
         &self,
         request: Request<proto::UpdateRequest>,
@@ -117,7 +193,16 @@ where
         let (ok, new_id) = {
             let mime = mime::Mime::from_str(&mime).unwrap_or(mime::APPLICATION_OCTET_STREAM);
             let mut manager = self.manager.lock().await;
-            manager.replace(id, &data, &mime)
+            let replaced = manager.replace(id, &data, &mime);
+            if let Some((old_id, new_id)) = replaced {
+                // snippets are retained after being replaced, but never stored in history
+                if !manager.is_snippet(old_id) {
+                    persist(&self.history_manager, old_id, manager.get(old_id)).await;
+                }
+                persist(&self.history_manager, new_id, manager.get(new_id)).await;
+            }
+            drop(manager);
+            replaced.map_or((false, id), |(_, new_id)| (true, new_id))
         };
         Ok(Response::new(proto::UpdateResponse { ok, new_id }))
     }
@@ -127,11 +212,56 @@ where
         request: Request<proto::MarkRequest>,
     ) -> Result<Response<proto::MarkResponse>, Status> {
         let proto::MarkRequest { id, kind } = request.into_inner();
+        let result = {
+            let mut manager = self.manager.lock().await;
+            manager.mark(id, kind.into()).await
+        };
+        match result {
+            Ok(()) => Ok(Response::new(proto::MarkResponse { ok: true })),
+            Err(err @ manager::Error::ClipNotFound { .. }) => {
+                Err(Status::not_found(err.to_string()))
+            }
+            Err(err) => {
+                tracing::error!("{err}");
+                Ok(Response::new(proto::MarkResponse { ok: false }))
+            }
+        }
+    }
+
+    async fn pin(
+        &self,
+        request: Request<proto::PinRequest>,
+    ) -> Result<Response<proto::PinResponse>, Status> {
+        let proto::PinRequest { id } = request.into_inner();
+        let ok = {
+            let mut manager = self.manager.lock().await;
+            let pinned_id = manager.pin(id);
+            // snippets are loaded from configuration, they are never stored in history
+            if let Some(id) = pinned_id.filter(|&id| !manager.is_snippet(id)) {
+                persist(&self.history_manager, id, manager.get(id)).await;
+            }
+            drop(manager);
+            pinned_id.is_some()
+        };
+        Ok(Response::new(proto::PinResponse { ok }))
+    }
+
+    async fn unpin(
+        &self,
+        request: Request<proto::UnpinRequest>,
+    ) -> Result<Response<proto::UnpinResponse>, Status> {
+        let proto::UnpinRequest { id } = request.into_inner();
         let ok = {
             let mut manager = self.manager.lock().await;
-            manager.mark(id, kind.into()).await.is_ok()
+            let unpinned_id = manager.unpin(id);
+            if let Some(id) = unpinned_id {
+                // the clip might be evicted right after it is unpinned
+                persist(&self.history_manager, id, manager.get(id)).await;
+            }
+            drop(manager);
+            unpinned_id.is_some()
         };
-        Ok(Response::new(proto::MarkResponse { ok }))
+        Ok(Response::new(proto::UnpinResponse { ok }))
     }
 
     async fn length(
@@ -144,4 +274,151 @@ where
         };
         Ok(Response::new(proto::LengthResponse { length }))
     }
+
+    async fn subscribe(
+        &self,
+        request: Request<proto::SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let proto::SubscribeRequest { preview_length } = request.into_inner();
+        let preview_length = usize::try_from(preview_length).unwrap_or(30);
+        let event_receiver = self.manager.lock().await.subscribe();
+        let mut shutdown_receiver = self.shutdown_receiver.clone();
+
+        let stream = BroadcastStream::new(event_receiver)
+            .filter_map(move |event| async move {
+                match event {
+                    Ok(event) => Some(Ok(event.to_clip_event(preview_length).into())),
+                    Err(BroadcastStreamRecvError::Lagged(n)) => {
+                        tracing::warn!(
+                            "Subscriber lagged too far behind, {n} event(s) are dropped"
+                        );
+                        None
+                    }
+                }
+            })
+            .take_until(async move {
+                let _unused = shutdown_receiver.changed().await;
+            });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+// replace the stored clip with `id` in history, the clip is removed if it is
+// `None`
+async fn persist(
+    history_manager: &Mutex<HistoryManager>,
+    id: u64,
+    clip: Option<clipcat_base::ClipEntry>,
+) {
+    let mut history_manager = history_manager.lock().await;
+    if let Err(err) = history_manager.remove(&[id]).await {
+        tracing::error!("{err}");
+    }
+    if let Some(clip) = clip {
+        if let Err(err) = history_manager.put(&clip).await {
+            tracing::error!("{err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use clipcat_base::ClipEntry;
+    use clipcat_proto::{self as proto, Manager as _};
+    use tokio::sync::{watch, Mutex};
+    use tonic::Request;
+
+    use crate::{
+        backend::MockClipboardBackend, config::HistoryBackend, grpc::ManagerService,
+        history::HistoryManager, notification::MockNotification, ClipboardManager,
+    };
+
+    #[tokio::test]
+    async fn test_clear_keeps_pinned_clips_in_history() {
+        let file_path = std::env::temp_dir()
+            .join(format!("clipcat-grpc-clear-test-{pid}", pid = std::process::id()));
+        let history_manager =
+            HistoryManager::new(&file_path, HistoryBackend::FileSystem, None).await.unwrap();
+        let manager = ClipboardManager::new(
+            Arc::new(MockClipboardBackend::new()),
+            MockNotification::default(),
+        );
+        let (_shutdown_sender, shutdown_receiver) = watch::channel(());
+        let service = ManagerService::new(
+            Arc::new(Mutex::new(manager)),
+            Arc::new(Mutex::new(history_manager)),
+            shutdown_receiver,
+        );
+
+        let mut ids = Vec::new();
+        for data in ["Alpha", "Beta"] {
+            let request = proto::InsertRequest {
+                kind: proto::ClipboardKind::Clipboard.into(),
+                data: data.as_bytes().to_vec(),
+                mime: mime::TEXT_PLAIN_UTF_8.to_string(),
+                ttl_ms: 0,
+            };
+            ids.push(service.insert(Request::new(request)).await.unwrap().into_inner().id);
+        }
+        let request = Request::new(proto::PinRequest { id: ids[0] });
+        assert!(service.pin(request).await.unwrap().into_inner().ok);
+        let _unused = service.clear(Request::new(())).await.unwrap();
+        drop(service);
+
+        let mut history_manager =
+            HistoryManager::new(&file_path, HistoryBackend::FileSystem, None).await.unwrap();
+        let clips = history_manager.load().await.unwrap();
+        assert_eq!(clips.len(), 1);
+        assert_eq!(clips[0].id(), ids[0]);
+        assert!(clips[0].is_pinned());
+
+        drop(std::fs::remove_dir_all(file_path));
+    }
+
+    #[tokio::test]
+    async fn test_remove_by_legacy_id_updates_history() {
+        let file_path = std::env::temp_dir()
+            .join(format!("clipcat-grpc-legacy-id-test-{pid}", pid = std::process::id()));
+        let history_manager =
+            HistoryManager::new(&file_path, HistoryBackend::FileSystem, None).await.unwrap();
+        let manager = Arc::new(Mutex::new(ClipboardManager::new(
+            Arc::new(MockClipboardBackend::new()),
+            MockNotification::default(),
+        )));
+        let (_shutdown_sender, shutdown_receiver) = watch::channel(());
+        let service = ManagerService::new(
+            manager.clone(),
+            Arc::new(Mutex::new(history_manager)),
+            shutdown_receiver,
+        );
+
+        let request = proto::InsertRequest {
+            kind: proto::ClipboardKind::Clipboard.into(),
+            data: b"Alpha".to_vec(),
+            mime: mime::TEXT_PLAIN_UTF_8.to_string(),
+            ttl_ms: 0,
+        };
+        let id = service.insert(Request::new(request)).await.unwrap().into_inner().id;
+        let legacy_id = {
+            let mut manager = manager.lock().await;
+            let clip = manager.get(id).unwrap();
+            let legacy_id = ClipEntry::compute_legacy_id(clip.as_ref());
+            manager.set_id_aliases([(legacy_id, id)].into());
+            legacy_id
+        };
+        assert_ne!(legacy_id, id);
+
+        let request = Request::new(proto::RemoveRequest { id: legacy_id });
+        assert!(service.remove(request).await.unwrap().into_inner().ok);
+        drop(service);
+
+        let mut history_manager =
+            HistoryManager::new(&file_path, HistoryBackend::FileSystem, None).await.unwrap();
+        assert!(history_manager.load().await.unwrap().is_empty());
+
+        drop(std::fs::remove_dir_all(file_path));
+    }
 }
@@ -1,5 +1,8 @@
+mod auth;
 mod manager;
 mod system;
 mod watcher;
 
-pub use self::{manager::ManagerService, system::SystemService, watcher::WatcherService};
+pub use self::{
+    auth::Authenticator, manager::ManagerService, system::SystemService, watcher::WatcherService,
+};
@@ -0,0 +1,97 @@
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+
+use crate::{config::EncryptionKey, history::Error};
+
+// every encrypted record starts with this tag, so that plaintext records
+// written before encryption was enabled could still be read
+const MAGIC: &[u8] = b"clipcat-xchacha20poly1305:";
+
+const NONCE_LENGTH: usize = 24;
+
+pub const SALT_LENGTH: usize = 16;
+
+#[derive(Clone, Default)]
+pub struct Cipher {
+    cipher: Option<XChaCha20Poly1305>,
+}
+
+impl Cipher {
+    /// Derive the key from `key` and `salt` with Argon2id, it is slow by
+    /// design, call it on a blocking thread.
+    pub fn new(key: &EncryptionKey, salt: &[u8]) -> Result<Self, Error> {
+        let mut derived_key = [0_u8; 32];
+        Argon2::default()
+            .hash_password_into(key.as_bytes(), salt, &mut derived_key)
+            .map_err(|_| Error::DeriveEncryptionKey)?;
+        Ok(Self { cipher: Some(XChaCha20Poly1305::new(&derived_key.into())) })
+    }
+
+    pub fn generate_salt() -> [u8; SALT_LENGTH] {
+        let mut salt = [0_u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        salt
+    }
+
+    /// Encrypt `data` if the key is provided, otherwise return `data`
+    /// untouched.
+    pub fn seal(&self, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(data);
+        };
+
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher.encrypt(&nonce, data.as_slice()).map_err(|_| Error::EncryptClip)?;
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LENGTH + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Decrypt `data` if it was encrypted, otherwise return `data` untouched.
+    pub fn open(&self, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let Some(payload) = data.strip_prefix(MAGIC) else {
+            return Ok(data);
+        };
+        let Some(cipher) = &self.cipher else {
+            return Err(Error::MissingEncryptionKey);
+        };
+        if payload.len() < NONCE_LENGTH {
+            return Err(Error::DecryptClip);
+        }
+
+        let (nonce, ciphertext) = payload.split_at(NONCE_LENGTH);
+        cipher.decrypt(XNonce::from_slice(nonce), ciphertext).map_err(|_| Error::DecryptClip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::EncryptionKey, history::cipher::Cipher};
+
+    #[test]
+    fn test_seal_and_open() {
+        let key = EncryptionKey::new(b"secret".to_vec());
+        let salt = Cipher::generate_salt();
+        let cipher = Cipher::new(&key, &salt).unwrap();
+        let sealed = cipher.seal(b"password".to_vec()).unwrap();
+        assert_ne!(sealed, b"password");
+        assert_eq!(cipher.open(sealed.clone()).unwrap(), b"password");
+        assert_eq!(Cipher::new(&key, &salt).unwrap().open(sealed.clone()).unwrap(), b"password");
+
+        // plaintext is readable with or without key
+        assert_eq!(cipher.open(b"plaintext".to_vec()).unwrap(), b"plaintext");
+        assert_eq!(Cipher::default().open(b"plaintext".to_vec()).unwrap(), b"plaintext");
+
+        assert!(Cipher::default().open(sealed.clone()).is_err());
+        let other_key = EncryptionKey::new(b"other".to_vec());
+        assert!(Cipher::new(&other_key, &salt).unwrap().open(sealed.clone()).is_err());
+        // the same key material with another salt derives another key
+        let other_salt = Cipher::generate_salt();
+        assert!(Cipher::new(&key, &other_salt).unwrap().open(sealed).is_err());
+    }
+}
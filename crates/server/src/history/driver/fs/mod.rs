@@ -1,6 +1,7 @@
 mod model;
 
 use std::{
+    collections::HashSet,
     fs::{File, OpenOptions},
     io::{Seek, SeekFrom, Write},
     path::{Path, PathBuf},
@@ -8,24 +9,27 @@ use std::{
 };
 
 use async_trait::async_trait;
-use clipcat_base::ClipEntry;
+use clipcat_base::{ClipEntry, ClipboardKind};
 use parking_lot::Mutex;
 use snafu::ResultExt;
 use time::{format_description::well_known::Rfc3339, OffsetDateTime, UtcOffset};
 
-use crate::history::{driver::Driver, error, Error};
+use crate::{
+    config::HistoryCapacity,
+    history::{cipher::Cipher, driver::Driver, error, Error},
+};
 
 pub struct FileSystemDriver {
     inner: Arc<Mutex<Inner>>,
 }
 
 impl FileSystemDriver {
-    pub async fn new<P>(file_path: P) -> Result<Self, Error>
+    pub async fn new<P>(file_path: P, cipher: Cipher) -> Result<Self, Error>
     where
         P: AsRef<Path> + Send,
     {
         let file_path = file_path.as_ref().to_path_buf();
-        let inner = tokio::task::spawn_blocking(move || Inner::new(file_path))
+        let inner = tokio::task::spawn_blocking(move || Inner::new(file_path, cipher))
             .await
             .context(error::JoinTaskSnafu)??;
         Ok(Self { inner: Arc::new(Mutex::new(inner)) })
@@ -51,7 +55,7 @@ impl Driver for FileSystemDriver {
         let inner = self.inner.clone();
         tokio::task::spawn_blocking(move || {
             let mut driver = inner.lock();
-            Ok(driver.load())
+            driver.load()
         })
         .await
         .context(error::JoinTaskSnafu)?
@@ -82,11 +86,22 @@ impl Driver for FileSystemDriver {
         .context(error::JoinTaskSnafu)?
     }
 
-    async fn shrink_to(&mut self, min_capacity: usize) -> Result<(), Error> {
+    async fn remove(&mut self, ids: &[u64]) -> Result<(), Error> {
+        let inner = self.inner.clone();
+        let ids = ids.iter().copied().collect::<HashSet<_>>();
+        tokio::task::spawn_blocking(move || {
+            let mut driver = inner.lock();
+            driver.remove(&ids)
+        })
+        .await
+        .context(error::JoinTaskSnafu)?
+    }
+
+    async fn shrink_to(&mut self, capacity: HistoryCapacity) -> Result<(), Error> {
         let inner = self.inner.clone();
         tokio::task::spawn_blocking(move || {
             let mut driver = inner.lock();
-            driver.shrink_to(min_capacity)
+            driver.shrink_to(capacity)
         })
         .await
         .context(error::JoinTaskSnafu)?
@@ -97,10 +112,11 @@ pub struct Inner {
     file_path: PathBuf,
     clips_file: File,
     header_file: File,
+    cipher: Cipher,
 }
 
 impl Inner {
-    pub fn new<P: AsRef<Path>>(file_path: P) -> Result<Self, Error> {
+    pub fn new<P: AsRef<Path>>(file_path: P, cipher: Cipher) -> Result<Self, Error> {
         let file_path = file_path.as_ref().to_path_buf();
         std::fs::create_dir_all(&file_path)
             .context(error::CreateDirectorySnafu { file_path: file_path.clone() })?;
@@ -114,9 +130,11 @@ impl Inner {
             .open(&header_file_path)
             .context(error::OpenFileSnafu { file_path: header_file_path.clone() })?;
 
-        if let Ok(model::v1::FileHeader { schema, last_update }) =
-            serde_json::from_reader::<_, model::v1::FileHeader>(&header_file)
+        let mut schema_version = model::v2::FileHeader::SCHEMA_VERSION;
+        if let Ok(model::v2::FileHeader { schema, last_update }) =
+            serde_json::from_reader::<_, model::v2::FileHeader>(&header_file)
         {
+            schema_version = schema;
             tracing::info!(
                 "Open `{}`, schema: {schema}, last update: {last_update}",
                 header_file_path.display(),
@@ -136,19 +154,47 @@ impl Inner {
             .open(&clips_file_path)
             .context(error::OpenFileSnafu { file_path: clips_file_path })?;
 
-        Ok(Self { file_path, clips_file, header_file })
+        let mut inner = Self { file_path, clips_file, header_file, cipher };
+        if schema_version == model::v1::FileHeader::SCHEMA_VERSION {
+            inner.migrate_from_v1()?;
+        }
+        Ok(inner)
+    }
+
+    fn migrate_from_v1(&mut self) -> Result<(), Error> {
+        tracing::info!(
+            "Migrate `{}` from schema {} to schema {}",
+            self.clips_file_path().display(),
+            model::v1::FileHeader::SCHEMA_VERSION,
+            model::v2::FileHeader::SCHEMA_VERSION
+        );
+
+        drop(self.clips_file.seek(SeekFrom::Start(0)));
+        let mut clips = Vec::new();
+        while let Ok(clip) =
+            bincode::deserialize_from::<_, model::v1::ClipboardValue>(&self.clips_file)
+        {
+            clips.push(ClipEntry::from(clip));
+        }
+        self.save(clips)
     }
 
     fn save<I>(&mut self, clips: I) -> Result<(), Error>
     where
         I: IntoIterator<Item = ClipEntry>,
     {
+        let values =
+            clips.into_iter().map(|clip| self.encode(clip)).collect::<Result<Vec<_>, _>>()?;
+        self.write_values(values)
+    }
+
+    fn write_values(&mut self, values: Vec<model::v2::ClipboardValue>) -> Result<(), Error> {
         self.clips_file
             .set_len(0)
             .context(error::TruncateFileSnafu { file_path: self.clips_file_path() })?;
 
-        for clip in clips {
-            bincode::serialize_into(&mut self.clips_file, &model::v1::ClipboardValue::from(clip))
+        for value in values {
+            bincode::serialize_into(&mut self.clips_file, &value)
                 .context(error::SeriailizeClipSnafu)?;
         }
 
@@ -157,15 +203,15 @@ impl Inner {
         self.update_header()
     }
 
-    fn load(&mut self) -> Vec<ClipEntry> {
+    fn load(&mut self) -> Result<Vec<ClipEntry>, Error> {
         drop(self.clips_file.seek(SeekFrom::Start(0)));
         let mut clips = Vec::new();
-        while let Ok(clip) =
-            bincode::deserialize_from::<_, model::v1::ClipboardValue>(&self.clips_file)
+        while let Ok(value) =
+            bincode::deserialize_from::<_, model::v2::ClipboardValue>(&self.clips_file)
         {
-            clips.push(ClipEntry::from(clip));
+            clips.push(self.decode(value)?);
         }
-        clips
+        Ok(clips)
     }
 
     fn clear(&mut self) -> Result<(), Error> {
@@ -179,22 +225,47 @@ impl Inner {
     fn put(&mut self, data: &ClipEntry) -> Result<(), Error> {
         self.update_header()?;
 
+        let value = self.encode(data.clone())?;
         drop(self.clips_file.seek(SeekFrom::End(0)));
-        bincode::serialize_into(
-            &mut self.clips_file,
-            &model::v1::ClipboardValue::from(data.clone()),
-        )
-        .context(error::SeriailizeClipSnafu)
+        bincode::serialize_into(&mut self.clips_file, &value).context(error::SeriailizeClipSnafu)
     }
 
-    fn shrink_to(&mut self, min_capacity: usize) -> Result<(), Error> {
-        let mut saved = self.load();
+    fn remove(&mut self, ids: &HashSet<u64>) -> Result<(), Error> {
+        let clips =
+            self.load()?.into_iter().filter(|clip| !ids.contains(&clip.id())).collect::<Vec<_>>();
+        self.save(clips)
+    }
+
+    fn shrink_to(&mut self, capacity: HistoryCapacity) -> Result<(), Error> {
+        // pinned clips are not counted in capacity
+        let (mut saved, unpinned): (Vec<_>, Vec<_>) =
+            self.load()?.into_iter().partition(ClipEntry::is_pinned);
 
-        saved.sort_unstable();
-        saved.truncate(min_capacity);
+        for kind in ClipboardKind::all_kinds() {
+            let mut clips = unpinned.iter().filter(|clip| clip.kind() == kind).collect::<Vec<_>>();
+            clips.sort_unstable();
+            saved.extend(clips.into_iter().take(capacity.get(kind)).cloned());
+        }
         self.save(saved)
     }
 
+    fn encode(&self, clip: ClipEntry) -> Result<model::v2::ClipboardValue, Error> {
+        let mut value = model::v2::ClipboardValue::from(clip);
+        value.data = self.cipher.seal(value.data)?;
+        for (_, data) in &mut value.representations {
+            *data = self.cipher.seal(std::mem::take(data))?;
+        }
+        Ok(value)
+    }
+
+    fn decode(&self, mut value: model::v2::ClipboardValue) -> Result<ClipEntry, Error> {
+        value.data = self.cipher.open(value.data)?;
+        for (_, data) in &mut value.representations {
+            *data = self.cipher.open(std::mem::take(data))?;
+        }
+        Ok(ClipEntry::from(value))
+    }
+
     fn update_header(&mut self) -> Result<(), Error> {
         self.header_file
             .set_len(0)
@@ -203,8 +274,8 @@ impl Inner {
 
         serde_json::to_writer(
             &mut self.header_file,
-            &model::v1::FileHeader {
-                schema: model::v1::FileHeader::SCHEMA_VERSION,
+            &model::v2::FileHeader {
+                schema: model::v2::FileHeader::SCHEMA_VERSION,
                 last_update: OffsetDateTime::now_utc(),
             },
         )
@@ -223,9 +294,173 @@ where
     [file_path.as_ref(), &Path::new("header.json")].into_iter().collect()
 }
 
-fn clips_file_path<P>(file_path: P) -> PathBuf
+pub fn clips_file_path<P>(file_path: P) -> PathBuf
 where
     P: AsRef<Path>,
 {
     [file_path.as_ref(), &Path::new("clips")].into_iter().collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use clipcat_base::{ClipEntry, ClipSource, ClipboardKind};
+
+    use crate::{
+        config::{EncryptionKey, HistoryCapacity},
+        history::{
+            cipher::Cipher,
+            driver::{Driver, FileSystemDriver},
+        },
+    };
+
+    #[tokio::test]
+    async fn test_remove_and_clear() {
+        let file_path = std::env::temp_dir()
+            .join(format!("clipcat-history-test-{pid}", pid = std::process::id()));
+        let mut driver = FileSystemDriver::new(&file_path, Cipher::default()).await.unwrap();
+        driver.clear().await.unwrap();
+
+        let clips = ["Alpha", "Beta", "Gamma"]
+            .into_iter()
+            .map(|text| ClipEntry::from_string(text, ClipboardKind::Clipboard))
+            .collect::<Vec<_>>();
+        for clip in &clips {
+            driver.put(clip).await.unwrap();
+        }
+        assert_eq!(driver.load().await.unwrap().len(), clips.len());
+
+        driver.remove(&[clips[1].id()]).await.unwrap();
+        let mut loaded =
+            driver.load().await.unwrap().into_iter().map(|clip| clip.id()).collect::<Vec<_>>();
+        loaded.sort_unstable();
+        let mut expected = vec![clips[0].id(), clips[2].id()];
+        expected.sort_unstable();
+        assert_eq!(loaded, expected);
+
+        driver.clear().await.unwrap();
+        assert!(driver.load().await.unwrap().is_empty());
+
+        drop(driver);
+        drop(std::fs::remove_dir_all(file_path));
+    }
+
+    #[tokio::test]
+    async fn test_pinned_clips_survive_shrinking() {
+        let file_path = std::env::temp_dir()
+            .join(format!("clipcat-history-pinned-test-{pid}", pid = std::process::id()));
+        let mut driver = FileSystemDriver::new(&file_path, Cipher::default()).await.unwrap();
+        driver.clear().await.unwrap();
+
+        let mut pinned = ClipEntry::from_string("Pinned", ClipboardKind::Clipboard);
+        pinned.set_pinned(true);
+        driver.put(&pinned).await.unwrap();
+        for text in ["Alpha", "Beta", "Gamma"] {
+            driver.put(&ClipEntry::from_string(text, ClipboardKind::Clipboard)).await.unwrap();
+        }
+        let primary = ClipEntry::from_string("Delta", ClipboardKind::Primary);
+        driver.put(&primary).await.unwrap();
+
+        // every kind of clipboard is shrunk separately
+        driver.shrink_to(HistoryCapacity::from(1)).await.unwrap();
+        let loaded = driver.load().await.unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert!(loaded.iter().any(|clip| clip.id() == pinned.id() && clip.is_pinned()));
+        assert!(loaded
+            .iter()
+            .any(|clip| clip.id() == primary.id() && clip.kind() == ClipboardKind::Primary));
+
+        drop(driver);
+        drop(std::fs::remove_dir_all(file_path));
+    }
+
+    #[tokio::test]
+    async fn test_encryption() {
+        let file_path = std::env::temp_dir()
+            .join(format!("clipcat-history-encryption-test-{pid}", pid = std::process::id()));
+        let key = EncryptionKey::new(b"secret".to_vec());
+        let cipher = Cipher::new(&key, &Cipher::generate_salt()).unwrap();
+        let clip = ClipEntry::from_string("my-password", ClipboardKind::Clipboard);
+        {
+            let mut driver = FileSystemDriver::new(&file_path, cipher.clone()).await.unwrap();
+            driver.clear().await.unwrap();
+            driver.put(&clip).await.unwrap();
+        }
+
+        let raw = std::fs::read(file_path.join("clips")).unwrap();
+        assert!(!raw.windows(b"my-password".len()).any(|window| window == b"my-password"));
+
+        let mut driver = FileSystemDriver::new(&file_path, Cipher::default()).await.unwrap();
+        assert!(driver.load().await.is_err());
+
+        let mut driver = FileSystemDriver::new(&file_path, cipher).await.unwrap();
+        assert_eq!(driver.load().await.unwrap(), vec![clip]);
+
+        drop(driver);
+        drop(std::fs::remove_dir_all(file_path));
+    }
+
+    #[tokio::test]
+    async fn test_rich_content() {
+        let file_path = std::env::temp_dir()
+            .join(format!("clipcat-history-rich-test-{pid}", pid = std::process::id()));
+        let key = EncryptionKey::new(b"secret".to_vec());
+        let cipher = Cipher::new(&key, &Cipher::generate_salt()).unwrap();
+        let clip = ClipEntry::from_representations(
+            [
+                ("text/html".to_string(), "<a href=\"https://example.com\">link</a>".into()),
+                ("text/plain".to_string(), "link".into()),
+                ("application/x-clipcat-test".to_string(), vec![0_u8, 1, 2, 3].into()),
+            ]
+            .into(),
+            ClipboardKind::Clipboard,
+            None,
+        )
+        .unwrap();
+        assert_eq!(clip.as_utf8_string(), "link");
+        {
+            let mut driver = FileSystemDriver::new(&file_path, cipher.clone()).await.unwrap();
+            driver.clear().await.unwrap();
+            driver.put(&clip).await.unwrap();
+        }
+
+        let raw = std::fs::read(file_path.join("clips")).unwrap();
+        assert!(!raw.windows(b"example.com".len()).any(|window| window == b"example.com"));
+
+        let mut driver = FileSystemDriver::new(&file_path, cipher).await.unwrap();
+        let loaded = driver.load().await.unwrap();
+        assert_eq!(loaded, vec![clip.clone()]);
+        assert_eq!(loaded[0].representations(), clip.representations());
+
+        drop(driver);
+        drop(std::fs::remove_dir_all(file_path));
+    }
+
+    #[tokio::test]
+    async fn test_source() {
+        let file_path = std::env::temp_dir()
+            .join(format!("clipcat-history-source-test-{pid}", pid = std::process::id()));
+        let source = ClipSource {
+            class: Some("KeePassXC".to_string()),
+            pid: Some(4242),
+            process_name: Some("keepassxc".to_string()),
+        };
+        let mut clip = ClipEntry::from_string("Alpha", ClipboardKind::Clipboard);
+        clip.set_source(Some(source.clone()));
+        let unknown = ClipEntry::from_string("Beta", ClipboardKind::Clipboard);
+        {
+            let mut driver = FileSystemDriver::new(&file_path, Cipher::default()).await.unwrap();
+            driver.clear().await.unwrap();
+            driver.put(&clip).await.unwrap();
+            driver.put(&unknown).await.unwrap();
+        }
+
+        let mut driver = FileSystemDriver::new(&file_path, Cipher::default()).await.unwrap();
+        let loaded = driver.load().await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].source(), Some(&source));
+        assert_eq!(loaded[1].source(), None);
+
+        drop(driver);
+        drop(std::fs::remove_dir_all(file_path));
+    }
+}
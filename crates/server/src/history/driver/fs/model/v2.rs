@@ -0,0 +1,104 @@
+use clipcat_base::{ClipEntry, ClipSource, ClipboardKind};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FileHeader {
+    pub schema: u64,
+
+    #[serde(with = "time::serde::iso8601")]
+    pub last_update: OffsetDateTime,
+}
+
+impl FileHeader {
+    pub const SCHEMA_VERSION: u64 = 2;
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClipboardValue {
+    pub timestamp: OffsetDateTime,
+
+    #[serde(with = "clipcat_base::serde::mime")]
+    pub mime: mime::Mime,
+
+    // empty if the clip is rich content, which is stored in `representations`
+    pub data: Vec<u8>,
+
+    pub pinned: bool,
+
+    pub expires_at: Option<OffsetDateTime>,
+
+    // MIME type and data of every representation of rich content
+    pub representations: Vec<(String, Vec<u8>)>,
+
+    // the application which owned the selection
+    pub source: Option<Source>,
+
+    #[serde(with = "clipcat_base::serde::clipboard_kind")]
+    pub kind: ClipboardKind,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Source {
+    pub class: Option<String>,
+
+    pub pid: Option<u32>,
+
+    pub process_name: Option<String>,
+}
+
+impl From<Source> for ClipSource {
+    fn from(Source { class, pid, process_name }: Source) -> Self {
+        Self { class, pid, process_name }
+    }
+}
+
+impl From<ClipSource> for Source {
+    fn from(ClipSource { class, pid, process_name }: ClipSource) -> Self {
+        Self { class, pid, process_name }
+    }
+}
+
+impl From<ClipboardValue> for ClipEntry {
+    fn from(
+        ClipboardValue { timestamp, mime, data, pinned, expires_at, representations, source, kind }: ClipboardValue,
+    ) -> Self {
+        let mut entry = if representations.is_empty() {
+            Self::new(&data, &mime, kind, Some(timestamp))
+        } else {
+            let representations =
+                representations.into_iter().map(|(mime, data)| (mime, data.into())).collect();
+            Self::from_representations(representations, kind, Some(timestamp))
+        }
+        .unwrap_or_default();
+        entry.set_pinned(pinned);
+        entry.set_expires_at(expires_at);
+        entry.set_source(source.map(ClipSource::from));
+        entry
+    }
+}
+
+impl From<ClipEntry> for ClipboardValue {
+    fn from(entry: ClipEntry) -> Self {
+        let representations = entry
+            .representations()
+            .map(|representations| {
+                representations.iter().map(|(mime, data)| (mime.clone(), data.to_vec())).collect()
+            })
+            .unwrap_or_default();
+        Self {
+            data: if entry.representations().is_some() {
+                Vec::new()
+            } else {
+                entry.encoded().unwrap_or_default()
+            },
+            mime: entry.mime(),
+            timestamp: entry.timestamp(),
+            pinned: entry.is_pinned(),
+            expires_at: entry.expires_at(),
+            representations,
+            source: entry.source().cloned().map(Source::from),
+            kind: entry.kind(),
+        }
+    }
+}
@@ -1,10 +1,11 @@
 mod fs;
+mod sqlite;
 
 use async_trait::async_trait;
 use clipcat_base::ClipEntry;
 
-pub use self::fs::FileSystemDriver;
-use crate::history::Error;
+pub use self::{fs::FileSystemDriver, sqlite::SqliteDriver};
+use crate::{config::HistoryCapacity, history::Error};
 
 #[async_trait]
 pub trait Driver: Send + Sync {
@@ -16,14 +17,16 @@ pub trait Driver: Send + Sync {
 
     async fn put(&mut self, data: &ClipEntry) -> Result<(), Error>;
 
-    async fn shrink_to(&mut self, min_capacity: usize) -> Result<(), Error>;
+    async fn remove(&mut self, ids: &[u64]) -> Result<(), Error>;
+
+    async fn shrink_to(&mut self, capacity: HistoryCapacity) -> Result<(), Error>;
 
     async fn save_and_shrink_to(
         &mut self,
         data: &[ClipEntry],
-        min_capacity: usize,
+        capacity: HistoryCapacity,
     ) -> Result<(), Error> {
         self.save(data).await?;
-        self.shrink_to(min_capacity).await
+        self.shrink_to(capacity).await
     }
 }
@@ -0,0 +1,495 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use clipcat_base::{ClipEntry, ClipSource, ClipboardKind};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection};
+use snafu::ResultExt;
+use time::OffsetDateTime;
+
+use crate::{
+    config::HistoryCapacity,
+    history::{
+        cipher::Cipher,
+        driver::{fs, Driver, FileSystemDriver},
+        error, Error,
+    },
+};
+
+const SCHEMA_VERSION: i64 = 2;
+
+const DATABASE_FILE_NAME: &str = "clips.sqlite";
+
+pub struct SqliteDriver {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl SqliteDriver {
+    pub async fn new<P>(file_path: P, cipher: Cipher) -> Result<Self, Error>
+    where
+        P: AsRef<Path> + Send,
+    {
+        let file_path = file_path.as_ref().to_path_buf();
+        let (inner, schema_version) = {
+            let (file_path, cipher) = (file_path.clone(), cipher.clone());
+            tokio::task::spawn_blocking(move || Inner::open(&file_path, cipher))
+                .await
+                .context(error::JoinTaskSnafu)??
+        };
+
+        let inner = Arc::new(Mutex::new(inner));
+        if schema_version == 0 {
+            // migrate clips stored by `FileSystemDriver` for the first time
+            let legacy_clips = if fs::clips_file_path(&file_path).exists() {
+                FileSystemDriver::new(&file_path, cipher).await?.load().await?
+            } else {
+                Vec::new()
+            };
+
+            let inner = inner.clone();
+            tokio::task::spawn_blocking(move || inner.lock().initialize(&legacy_clips))
+                .await
+                .context(error::JoinTaskSnafu)??;
+        } else if schema_version < SCHEMA_VERSION {
+            let inner = inner.clone();
+            tokio::task::spawn_blocking(move || inner.lock().migrate(schema_version))
+                .await
+                .context(error::JoinTaskSnafu)??;
+        }
+
+        Ok(Self { inner })
+    }
+
+    async fn execute<F, T>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Inner) -> Result<T, Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut driver = inner.lock();
+            f(&mut driver)
+        })
+        .await
+        .context(error::JoinTaskSnafu)?
+    }
+}
+
+#[async_trait]
+impl Driver for SqliteDriver {
+    async fn load(&mut self) -> Result<Vec<ClipEntry>, Error> { self.execute(Inner::load).await }
+
+    async fn save(&mut self, clips: &[ClipEntry]) -> Result<(), Error> {
+        let clips = clips.to_vec();
+        self.execute(move |driver| driver.save(&clips)).await
+    }
+
+    async fn clear(&mut self) -> Result<(), Error> { self.execute(Inner::clear).await }
+
+    async fn put(&mut self, clip: &ClipEntry) -> Result<(), Error> {
+        let clip = clip.clone();
+        self.execute(move |driver| driver.put(&clip)).await
+    }
+
+    async fn remove(&mut self, ids: &[u64]) -> Result<(), Error> {
+        let ids = ids.to_vec();
+        self.execute(move |driver| driver.remove(&ids)).await
+    }
+
+    async fn shrink_to(&mut self, capacity: HistoryCapacity) -> Result<(), Error> {
+        self.execute(move |driver| driver.shrink_to(capacity)).await
+    }
+}
+
+struct Inner {
+    connection: Connection,
+    cipher: Cipher,
+}
+
+impl Inner {
+    // returns the schema version of database, `0` if it is not initialized yet
+    fn open(file_path: &Path, cipher: Cipher) -> Result<(Self, i64), Error> {
+        std::fs::create_dir_all(file_path)
+            .context(error::CreateDirectorySnafu { file_path: file_path.to_path_buf() })?;
+        let database_file_path = database_file_path(file_path);
+        let connection = Connection::open(&database_file_path)
+            .context(error::OpenDatabaseSnafu { file_path: database_file_path.clone() })?;
+
+        let schema_version = connection
+            .query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+            .context(error::OpenDatabaseSnafu { file_path: database_file_path.clone() })?;
+        tracing::info!("Open `{}`, schema: {schema_version}", database_file_path.display());
+
+        Ok((Self { connection, cipher }, schema_version))
+    }
+
+    fn initialize(&mut self, legacy_clips: &[ClipEntry]) -> Result<(), Error> {
+        let transaction = self.connection.transaction().context(error::AccessDatabaseSnafu)?;
+        transaction
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS clips (
+                    id        INTEGER PRIMARY KEY,
+                    timestamp INTEGER NOT NULL,
+                    mime      TEXT    NOT NULL,
+                    data      BLOB    NOT NULL,
+                    pinned    INTEGER NOT NULL DEFAULT 0,
+                    expires_at INTEGER,
+                    representations BLOB,
+                    source_class TEXT,
+                    source_pid INTEGER,
+                    source_process_name TEXT,
+                    kind INTEGER NOT NULL DEFAULT 0
+                );
+                CREATE INDEX IF NOT EXISTS clips_timestamp ON clips (timestamp);",
+            )
+            .context(error::AccessDatabaseSnafu)?;
+
+        if !legacy_clips.is_empty() {
+            tracing::info!("Migrate {} clip(s) from file system history", legacy_clips.len());
+        }
+        for clip in legacy_clips {
+            insert_clip(&transaction, &self.cipher, clip)?;
+        }
+
+        transaction
+            .pragma_update(None, "user_version", SCHEMA_VERSION)
+            .context(error::AccessDatabaseSnafu)?;
+        transaction.commit().context(error::AccessDatabaseSnafu)
+    }
+
+    fn migrate(&mut self, schema_version: i64) -> Result<(), Error> {
+        tracing::info!("Migrate database from schema {schema_version} to schema {SCHEMA_VERSION}");
+        let transaction = self.connection.transaction().context(error::AccessDatabaseSnafu)?;
+        // schema 1 does not store expiration, rich content, source and kind of clips
+        transaction
+            .execute_batch(
+                "ALTER TABLE clips ADD COLUMN expires_at INTEGER;
+                 ALTER TABLE clips ADD COLUMN representations BLOB;
+                 ALTER TABLE clips ADD COLUMN source_class TEXT;
+                 ALTER TABLE clips ADD COLUMN source_pid INTEGER;
+                 ALTER TABLE clips ADD COLUMN source_process_name TEXT;
+                 ALTER TABLE clips ADD COLUMN kind INTEGER NOT NULL DEFAULT 0;",
+            )
+            .context(error::AccessDatabaseSnafu)?;
+        // identifiers are computed with a stable hash since schema 2, re-insert clips
+        // with their new identifiers
+        let clips = load_clips(&transaction, &self.cipher)?;
+        let _count =
+            transaction.execute("DELETE FROM clips", []).context(error::AccessDatabaseSnafu)?;
+        for clip in &clips {
+            insert_clip(&transaction, &self.cipher, clip)?;
+        }
+        transaction
+            .pragma_update(None, "user_version", SCHEMA_VERSION)
+            .context(error::AccessDatabaseSnafu)?;
+        transaction.commit().context(error::AccessDatabaseSnafu)
+    }
+
+    fn load(&mut self) -> Result<Vec<ClipEntry>, Error> {
+        load_clips(&self.connection, &self.cipher)
+    }
+
+    fn save(&mut self, clips: &[ClipEntry]) -> Result<(), Error> {
+        let transaction = self.connection.transaction().context(error::AccessDatabaseSnafu)?;
+        let _count =
+            transaction.execute("DELETE FROM clips", []).context(error::AccessDatabaseSnafu)?;
+        for clip in clips {
+            insert_clip(&transaction, &self.cipher, clip)?;
+        }
+        transaction.commit().context(error::AccessDatabaseSnafu)
+    }
+
+    fn clear(&mut self) -> Result<(), Error> {
+        let _count =
+            self.connection.execute("DELETE FROM clips", []).context(error::AccessDatabaseSnafu)?;
+        Ok(())
+    }
+
+    fn put(&mut self, clip: &ClipEntry) -> Result<(), Error> {
+        insert_clip(&self.connection, &self.cipher, clip)
+    }
+
+    fn remove(&mut self, ids: &[u64]) -> Result<(), Error> {
+        let transaction = self.connection.transaction().context(error::AccessDatabaseSnafu)?;
+        {
+            let mut statement = transaction
+                .prepare("DELETE FROM clips WHERE id = ?1")
+                .context(error::AccessDatabaseSnafu)?;
+            for &id in ids {
+                let _count = statement
+                    .execute(params![to_row_id(id)])
+                    .context(error::AccessDatabaseSnafu)?;
+            }
+        }
+        transaction.commit().context(error::AccessDatabaseSnafu)
+    }
+
+    fn shrink_to(&mut self, capacity: HistoryCapacity) -> Result<(), Error> {
+        // pinned clips are not counted in capacity, every kind has its own capacity
+        let transaction = self.connection.transaction().context(error::AccessDatabaseSnafu)?;
+        for kind in ClipboardKind::all_kinds() {
+            let _count = transaction
+                .execute(
+                    "DELETE FROM clips WHERE pinned = 0 AND kind = ?1 AND id NOT IN (
+                        SELECT id FROM clips WHERE pinned = 0 AND kind = ?1
+                        ORDER BY timestamp DESC LIMIT ?2
+                    )",
+                    params![i32::from(kind), i64::try_from(capacity.get(kind)).unwrap_or(i64::MAX)],
+                )
+                .context(error::AccessDatabaseSnafu)?;
+        }
+        transaction.commit().context(error::AccessDatabaseSnafu)
+    }
+}
+
+fn load_clips(connection: &Connection, cipher: &Cipher) -> Result<Vec<ClipEntry>, Error> {
+    let mut statement = connection
+        .prepare(
+            "SELECT id, timestamp, mime, data, pinned, expires_at, representations, source_class, \
+             source_pid, source_process_name, kind FROM clips ORDER BY timestamp",
+        )
+        .context(error::AccessDatabaseSnafu)?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Vec<u8>>(3)?,
+                row.get::<_, bool>(4)?,
+                row.get::<_, Option<i64>>(5)?,
+                row.get::<_, Option<Vec<u8>>>(6)?,
+                ClipSource {
+                    class: row.get::<_, Option<String>>(7)?,
+                    pid: row.get::<_, Option<u32>>(8)?,
+                    process_name: row.get::<_, Option<String>>(9)?,
+                },
+                ClipboardKind::from(row.get::<_, i32>(10)?),
+            ))
+        })
+        .context(error::AccessDatabaseSnafu)?;
+
+    let mut clips = Vec::new();
+    for row in rows {
+        // skip the broken row instead of discarding the rest of history
+        let (id, timestamp, mime, data, pinned, expires_at, representations, source, kind) =
+            match row {
+                Ok(row) => row,
+                Err(err) => {
+                    tracing::warn!("Failed to read clip from database, error: {err}");
+                    continue;
+                }
+            };
+        // a clip which could not be decrypted must not be dropped silently
+        let data = cipher.open(data)?;
+        let representations =
+            representations.map(|representations| cipher.open(representations)).transpose()?;
+        let timestamp = from_row_timestamp(timestamp);
+        let Some(mut clip) = decode_clip(&data, &mime, representations.as_deref(), kind, timestamp)
+        else {
+            tracing::warn!("Failed to decode clip (id: {:016x})", from_row_id(id));
+            continue;
+        };
+        clip.set_pinned(pinned);
+        clip.set_expires_at(expires_at.and_then(from_row_timestamp));
+        clip.set_source(Some(source));
+        clips.push(clip);
+    }
+    Ok(clips)
+}
+
+fn decode_clip(
+    data: &[u8],
+    mime: &str,
+    representations: Option<&[u8]>,
+    kind: ClipboardKind,
+    timestamp: Option<OffsetDateTime>,
+) -> Option<ClipEntry> {
+    if let Some(representations) = representations {
+        let representations = bincode::deserialize::<Vec<(String, Vec<u8>)>>(representations)
+            .ok()?
+            .into_iter()
+            .map(|(mime, data)| (mime, data.into()))
+            .collect();
+        ClipEntry::from_representations(representations, kind, timestamp).ok()
+    } else {
+        let mime = mime.parse::<mime::Mime>().ok()?;
+        ClipEntry::new(data, &mime, kind, timestamp).ok()
+    }
+}
+
+fn insert_clip(connection: &Connection, cipher: &Cipher, clip: &ClipEntry) -> Result<(), Error> {
+    // every representation of rich content is stored in `representations`
+    let (data, representations) = if let Some(representations) = clip.representations() {
+        let representations = representations
+            .iter()
+            .map(|(mime, data)| (mime.as_str(), data.as_ref()))
+            .collect::<Vec<(&str, &[u8])>>();
+        let representations =
+            bincode::serialize(&representations).context(error::SeriailizeClipSnafu)?;
+        (Vec::new(), Some(cipher.seal(representations)?))
+    } else {
+        (clip.encoded().unwrap_or_default(), None)
+    };
+    let data = cipher.seal(data)?;
+    let source = clip.source().cloned().unwrap_or_default();
+    let _count = connection
+        .execute(
+            "INSERT OR REPLACE INTO clips (id, timestamp, mime, data, pinned, expires_at, \
+             representations, source_class, source_pid, source_process_name, kind)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                to_row_id(clip.id()),
+                to_row_timestamp(clip.timestamp()),
+                clip.mime().essence_str(),
+                data,
+                clip.is_pinned(),
+                clip.expires_at().map(to_row_timestamp),
+                representations,
+                source.class,
+                source.pid,
+                source.process_name,
+                i32::from(clip.kind()),
+            ],
+        )
+        .context(error::AccessDatabaseSnafu)?;
+    Ok(())
+}
+
+// SQLite does not support unsigned 64-bit integer, store the bits of ID as
+// `i64`
+#[inline]
+const fn to_row_id(id: u64) -> i64 { i64::from_ne_bytes(id.to_ne_bytes()) }
+
+#[inline]
+const fn from_row_id(id: i64) -> u64 { u64::from_ne_bytes(id.to_ne_bytes()) }
+
+// timestamps are stored as nanoseconds since Unix epoch
+#[inline]
+fn to_row_timestamp(timestamp: OffsetDateTime) -> i64 {
+    i64::try_from(timestamp.unix_timestamp_nanos()).unwrap_or(i64::MAX)
+}
+
+#[inline]
+fn from_row_timestamp(timestamp: i64) -> Option<OffsetDateTime> {
+    OffsetDateTime::from_unix_timestamp_nanos(i128::from(timestamp)).ok()
+}
+
+fn database_file_path<P>(file_path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    [file_path.as_ref(), Path::new(DATABASE_FILE_NAME)].into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use clipcat_base::{ClipEntry, ClipSource, ClipboardKind};
+
+    use crate::{
+        config::HistoryCapacity,
+        history::{
+            cipher::Cipher,
+            driver::{Driver, FileSystemDriver, SqliteDriver},
+        },
+    };
+
+    #[tokio::test]
+    async fn test_migrate_and_shrink() {
+        let file_path = std::env::temp_dir()
+            .join(format!("clipcat-history-sqlite-test-{pid}", pid = std::process::id()));
+        drop(std::fs::remove_dir_all(&file_path));
+
+        let mut pinned = ClipEntry::from_string("Pinned", ClipboardKind::Clipboard);
+        pinned.set_pinned(true);
+        let clips = ["Alpha", "Beta", "Gamma"]
+            .into_iter()
+            .map(|text| ClipEntry::from_string(text, ClipboardKind::Clipboard))
+            .collect::<Vec<_>>();
+        {
+            let mut driver = FileSystemDriver::new(&file_path, Cipher::default()).await.unwrap();
+            driver.put(&pinned).await.unwrap();
+            for clip in &clips {
+                driver.put(clip).await.unwrap();
+            }
+        }
+
+        let mut driver = SqliteDriver::new(&file_path, Cipher::default()).await.unwrap();
+        let loaded = driver.load().await.unwrap();
+        assert_eq!(loaded.len(), clips.len() + 1);
+        assert!(loaded.iter().any(|clip| clip.id() == pinned.id() && clip.is_pinned()));
+
+        driver.remove(&[clips[0].id()]).await.unwrap();
+        assert_eq!(driver.load().await.unwrap().len(), clips.len());
+
+        driver.shrink_to(HistoryCapacity::from(1)).await.unwrap();
+        let loaded = driver.load().await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert!(loaded.iter().any(|clip| clip.id() == pinned.id()));
+
+        // every kind of clipboard is shrunk separately
+        let primary = ClipEntry::from_string("Primary", ClipboardKind::Primary);
+        driver.put(&primary).await.unwrap();
+        driver.shrink_to(HistoryCapacity::from(1)).await.unwrap();
+        let loaded = driver.load().await.unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert!(loaded
+            .iter()
+            .any(|clip| clip.id() == primary.id() && clip.kind() == ClipboardKind::Primary));
+        driver.remove(&[primary.id()]).await.unwrap();
+
+        // migration happens only once
+        drop(driver);
+        let mut driver = SqliteDriver::new(&file_path, Cipher::default()).await.unwrap();
+        assert_eq!(driver.load().await.unwrap().len(), 2);
+
+        let mut expiring = ClipEntry::from_string("Expiring", ClipboardKind::Clipboard);
+        let expires_at = expiring.timestamp() + Duration::from_secs(30);
+        expiring.set_expires_at(Some(expires_at));
+        driver.put(&expiring).await.unwrap();
+        assert!(driver
+            .load()
+            .await
+            .unwrap()
+            .iter()
+            .any(|clip| clip.id() == expiring.id() && clip.expires_at() == Some(expires_at)));
+
+        let rich = ClipEntry::from_representations(
+            [
+                ("text/html".to_string(), "<b>Rich</b>".into()),
+                ("text/plain".to_string(), "Rich".into()),
+            ]
+            .into(),
+            ClipboardKind::Clipboard,
+            None,
+        )
+        .unwrap();
+        driver.put(&rich).await.unwrap();
+        assert!(driver.load().await.unwrap().contains(&rich));
+
+        let mut sourced = ClipEntry::from_string("Sourced", ClipboardKind::Clipboard);
+        sourced.set_source(Some(ClipSource {
+            class: Some("firefox".to_string()),
+            pid: Some(4242),
+            process_name: None,
+        }));
+        driver.put(&sourced).await.unwrap();
+        assert!(driver
+            .load()
+            .await
+            .unwrap()
+            .iter()
+            .any(|clip| clip.id() == sourced.id() && clip.source() == sourced.source()));
+
+        driver.clear().await.unwrap();
+        assert!(driver.load().await.unwrap().is_empty());
+
+        drop(driver);
+        drop(std::fs::remove_dir_all(file_path));
+    }
+}
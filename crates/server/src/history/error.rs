@@ -28,4 +28,43 @@ pub enum Error {
 
     #[snafu(display("Failed to deserialize history header, error: {source}"))]
     DeseriailizeHistoryHeader { source: serde_json::Error },
+
+    #[snafu(display("Failed to open database {}, error: {source}", file_path.display()))]
+    OpenDatabase { source: rusqlite::Error, file_path: PathBuf },
+
+    #[snafu(display("Failed to access database, error: {source}"))]
+    AccessDatabase { source: rusqlite::Error },
+
+    #[snafu(display("Failed to read encryption salt {}, error: {source}", file_path.display()))]
+    ReadEncryptionSalt { source: std::io::Error, file_path: PathBuf },
+
+    #[snafu(display("Failed to write encryption salt {}, error: {source}", file_path.display()))]
+    WriteEncryptionSalt { source: std::io::Error, file_path: PathBuf },
+
+    #[snafu(display("Encryption salt {} is corrupted", file_path.display()))]
+    InvalidEncryptionSalt { file_path: PathBuf },
+
+    #[snafu(display("Failed to derive encryption key"))]
+    DeriveEncryptionKey,
+
+    #[snafu(display("Failed to encrypt clip"))]
+    EncryptClip,
+
+    #[snafu(display("Failed to decrypt clip, the encryption key might be incorrect"))]
+    DecryptClip,
+
+    #[snafu(display("History is encrypted, but no encryption key is provided"))]
+    MissingEncryptionKey,
+
+    #[snafu(display("Failed to read identifier aliases {}, error: {source}", file_path.display()))]
+    ReadIdAliases { source: std::io::Error, file_path: PathBuf },
+
+    #[snafu(display("Failed to write identifier aliases {}, error: {source}", file_path.display()))]
+    WriteIdAliases { source: std::io::Error, file_path: PathBuf },
+
+    #[snafu(display("Failed to serialize identifier aliases, error: {source}"))]
+    SerializeIdAliases { source: serde_json::Error },
+
+    #[snafu(display("Failed to deserialize identifier aliases, error: {source}"))]
+    DeserializeIdAliases { source: serde_json::Error },
 }
@@ -1,11 +1,29 @@
+mod cipher;
 mod driver;
 mod error;
 
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
 
 use clipcat_base::ClipEntry;
+use snafu::ResultExt;
+use tokio::io::AsyncWriteExt;
 
+use self::cipher::Cipher;
 pub use self::error::Error;
+use crate::config::{EncryptionKey, HistoryBackend, HistoryCapacity};
+
+const ID_ALIASES_FILE_NAME: &str = "id-aliases.json";
+
+const ENCRYPTION_SALT_FILE_NAME: &str = "encryption-salt";
+
+// encrypted history is only accessible to the owner
+const ENCRYPTED_DIRECTORY_MODE: u32 = 0o700;
+
+const ENCRYPTION_SALT_FILE_MODE: u32 = 0o600;
 
 pub struct HistoryManager {
     file_path: PathBuf,
@@ -15,25 +33,82 @@ pub struct HistoryManager {
 impl HistoryManager {
     /// # Errors
     #[inline]
-    pub async fn new<P>(file_path: P) -> Result<Self, Error>
+    pub async fn new<P>(
+        file_path: P,
+        backend: HistoryBackend,
+        encryption_key: Option<&EncryptionKey>,
+    ) -> Result<Self, Error>
     where
         P: AsRef<Path> + Send,
     {
         let file_path = file_path.as_ref().to_owned();
-        let driver = driver::FileSystemDriver::new(&file_path).await?;
-        Ok(Self { file_path, driver: Box::new(driver) })
+        let cipher = match encryption_key {
+            Some(key) => {
+                let salt = load_or_create_encryption_salt(&file_path).await?;
+                let key = key.clone();
+                tokio::task::spawn_blocking(move || Cipher::new(&key, &salt))
+                    .await
+                    .context(error::JoinTaskSnafu)??
+            }
+            None => Cipher::default(),
+        };
+        let driver: Box<dyn driver::Driver> = match backend {
+            HistoryBackend::FileSystem => {
+                Box::new(driver::FileSystemDriver::new(&file_path, cipher).await?)
+            }
+            HistoryBackend::Sqlite => {
+                Box::new(driver::SqliteDriver::new(&file_path, cipher).await?)
+            }
+        };
+        Ok(Self { file_path, driver })
     }
 
     #[inline]
     pub fn path(&self) -> &Path { &self.file_path }
 
+    /// Loads the aliases which map identifiers computed by releases before
+    /// identifiers became stable to the current identifiers of `clips`.
+    ///
+    /// The aliases are created when the history saved by those releases is
+    /// loaded for the first time.
+    ///
+    /// # Errors
+    pub async fn load_id_aliases(&self, clips: &[ClipEntry]) -> Result<HashMap<u64, u64>, Error> {
+        let file_path = self.file_path.join(ID_ALIASES_FILE_NAME);
+        match tokio::fs::read(&file_path).await {
+            Ok(data) => {
+                return serde_json::from_slice(&data).context(error::DeserializeIdAliasesSnafu);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(source) => return Err(Error::ReadIdAliases { source, file_path }),
+        }
+
+        let aliases = clips
+            .iter()
+            .map(|clip| (ClipEntry::compute_legacy_id(clip.as_ref()), clip.id()))
+            .filter(|(legacy_id, id)| legacy_id != id)
+            .collect::<HashMap<_, _>>();
+        if !aliases.is_empty() {
+            tracing::info!("Keep legacy identifiers of {} clip(s) resolvable", aliases.len());
+        }
+        let data = serde_json::to_vec(&aliases).context(error::SerializeIdAliasesSnafu)?;
+        tokio::fs::write(&file_path, data)
+            .await
+            .context(error::WriteIdAliasesSnafu { file_path: file_path.clone() })?;
+        Ok(aliases)
+    }
+
     #[inline]
     pub async fn put(&mut self, data: &ClipEntry) -> Result<(), Error> {
         self.driver.put(data).await
     }
 
     #[inline]
-    #[allow(dead_code)]
+    pub async fn remove(&mut self, ids: &[u64]) -> Result<(), Error> {
+        self.driver.remove(ids).await
+    }
+
+    #[inline]
     pub async fn clear(&mut self) -> Result<(), Error> { self.driver.clear().await }
 
     #[inline]
@@ -44,17 +119,102 @@ impl HistoryManager {
         self.driver.save(data).await
     }
 
-    #[inline]
-    pub async fn shrink_to(&mut self, min_capacity: usize) -> Result<(), Error> {
-        self.driver.shrink_to(min_capacity).await
-    }
-
     #[inline]
     pub async fn save_and_shrink_to(
         &mut self,
         data: &[ClipEntry],
-        min_capacity: usize,
+        capacity: HistoryCapacity,
     ) -> Result<(), Error> {
-        self.driver.save_and_shrink_to(data, min_capacity).await
+        self.driver.save_and_shrink_to(data, capacity).await
+    }
+}
+
+// the salt is generated once for each history, clips encrypted with another
+// salt could not be decrypted, so it is never replaced
+async fn load_or_create_encryption_salt(
+    file_path: &Path,
+) -> Result<[u8; cipher::SALT_LENGTH], Error> {
+    let salt_file_path = file_path.join(ENCRYPTION_SALT_FILE_NAME);
+    match tokio::fs::read(&salt_file_path).await {
+        Ok(salt) => {
+            return salt
+                .try_into()
+                .map_err(|_| Error::InvalidEncryptionSalt { file_path: salt_file_path });
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(source) => return Err(Error::ReadEncryptionSalt { source, file_path: salt_file_path }),
+    }
+
+    // the directory might have been created before the history is encrypted
+    async {
+        tokio::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(ENCRYPTED_DIRECTORY_MODE)
+            .create(file_path)
+            .await?;
+        let permissions = std::fs::Permissions::from_mode(ENCRYPTED_DIRECTORY_MODE);
+        tokio::fs::set_permissions(file_path, permissions).await
+    }
+    .await
+    .context(error::CreateDirectorySnafu { file_path: file_path.to_path_buf() })?;
+
+    // write a temporary file and rename it, so that a partially written salt is
+    // never left behind
+    let salt = Cipher::generate_salt();
+    let temp_file_path = salt_file_path.with_extension("tmp");
+    async {
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(ENCRYPTION_SALT_FILE_MODE)
+            .open(&temp_file_path)
+            .await?;
+        file.write_all(&salt).await?;
+        file.sync_all().await
+    }
+    .await
+    .context(error::WriteEncryptionSaltSnafu { file_path: temp_file_path.clone() })?;
+    tokio::fs::rename(&temp_file_path, &salt_file_path)
+        .await
+        .context(error::WriteEncryptionSaltSnafu { file_path: salt_file_path })?;
+    Ok(salt)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::fs::PermissionsExt, path::Path};
+
+    use clipcat_base::{ClipEntry, ClipboardKind};
+
+    use crate::{
+        config::{EncryptionKey, HistoryBackend},
+        history::{HistoryManager, ENCRYPTION_SALT_FILE_NAME},
+    };
+
+    #[tokio::test]
+    async fn test_encryption_salt_is_kept() {
+        let file_path = std::env::temp_dir()
+            .join(format!("clipcat-history-salt-test-{pid}", pid = std::process::id()));
+        let key = EncryptionKey::new(b"secret".to_vec());
+        let clip = ClipEntry::from_string("my-password", ClipboardKind::Clipboard);
+        HistoryManager::new(&file_path, HistoryBackend::FileSystem, Some(&key))
+            .await
+            .unwrap()
+            .save(&[clip.clone()])
+            .await
+            .unwrap();
+        let salt = std::fs::read(file_path.join(ENCRYPTION_SALT_FILE_NAME)).unwrap();
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&file_path), 0o700);
+        assert_eq!(mode(&file_path.join(ENCRYPTION_SALT_FILE_NAME)), 0o600);
+
+        let mut history_manager =
+            HistoryManager::new(&file_path, HistoryBackend::FileSystem, Some(&key)).await.unwrap();
+        assert_eq!(history_manager.load().await.unwrap(), vec![clip]);
+        assert_eq!(std::fs::read(file_path.join(ENCRYPTION_SALT_FILE_NAME)).unwrap(), salt);
+
+        drop(history_manager);
+        drop(std::fs::remove_dir_all(file_path));
     }
 }
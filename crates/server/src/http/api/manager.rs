@@ -0,0 +1,603 @@
+use std::{collections::BTreeMap, convert::Infallible, str::FromStr};
+
+use axum::{
+    body::{Bytes, StreamBody},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use clipcat_base::{
+    ClipEntry, ClipEntryMetadata, ClipEvent, ClipSource, ClipboardKind, SearchMode, SearchQuery,
+};
+use clipcat_proto::{self as proto, Manager as _};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tonic::Request;
+
+use crate::{
+    http::api::{parse_kind, Clip, ClipId, Error, ServicesState},
+    notification,
+};
+
+const DEFAULT_PREVIEW_LENGTH: usize = 100;
+
+/// Clip with its content, binary data is encoded with base64.
+#[derive(Debug, Serialize)]
+pub struct ClipData {
+    id: ClipId,
+
+    #[serde(with = "clipcat_base::serde::clipboard_kind")]
+    kind: ClipboardKind,
+
+    #[serde(with = "time::serde::rfc3339")]
+    timestamp: OffsetDateTime,
+
+    #[serde(with = "clipcat_base::serde::mime")]
+    mime: mime::Mime,
+
+    pinned: bool,
+
+    source: Option<ClipSource>,
+
+    // the content is also provided as `text` if it is plaintext
+    text: Option<String>,
+
+    data: String,
+
+    // every representation of rich content keyed by MIME type
+    representations: BTreeMap<String, String>,
+}
+
+impl From<proto::ClipEntry> for ClipData {
+    fn from(clip: proto::ClipEntry) -> Self {
+        let clip = ClipEntry::from(clip);
+        let representations = clip
+            .representations()
+            .map(|representations| {
+                representations
+                    .iter()
+                    .map(|(mime, data)| (mime.clone(), BASE64.encode(data)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            id: ClipId(clip.id()),
+            kind: clip.kind(),
+            timestamp: clip.timestamp(),
+            mime: clip.mime(),
+            pinned: clip.is_pinned(),
+            source: clip.source().cloned(),
+            text: clip.is_utf8_string().then(|| clip.as_utf8_string()),
+            data: BASE64.encode(clip.as_bytes()),
+            representations,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListParams {
+    kind: Option<String>,
+
+    preview_length: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    pattern: String,
+
+    #[serde(default)]
+    regex: bool,
+
+    #[serde(default)]
+    case_sensitive: bool,
+
+    kind: Option<String>,
+
+    // `image/*` matches all images
+    mime: Option<String>,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    since: Option<OffsetDateTime>,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    until: Option<OffsetDateTime>,
+
+    #[serde(default)]
+    offset: usize,
+
+    limit: Option<usize>,
+
+    preview_length: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    clips: Vec<Clip>,
+
+    total: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KindParams {
+    kind: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InsertRequest {
+    #[serde(default, with = "clipcat_base::serde::clipboard_kind")]
+    kind: ClipboardKind,
+
+    #[serde(default = "default_mime", with = "clipcat_base::serde::mime")]
+    mime: mime::Mime,
+
+    text: Option<String>,
+
+    // base64
+    data: Option<String>,
+
+    // the clip expires after this duration, it never expires if it is 0
+    #[serde(default)]
+    ttl_ms: u64,
+}
+
+const fn default_mime() -> mime::Mime { mime::TEXT_PLAIN_UTF_8 }
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MarkRequest {
+    #[serde(default, with = "clipcat_base::serde::clipboard_kind")]
+    kind: ClipboardKind,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Ids {
+    ids: Vec<ClipId>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IdResponse {
+    id: ClipId,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LengthResponse {
+    length: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubscribeParams {
+    preview_length: Option<usize>,
+}
+
+/// Event of clips, it is the counterpart of `ClipEvent` with IDs formatted
+/// like the other endpoints.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event {
+    Inserted(Clip),
+
+    Removed { id: ClipId },
+
+    Marked(Clip),
+
+    Pinned(Clip),
+
+    Unpinned(Clip),
+
+    Cleared,
+}
+
+impl From<ClipEvent> for Event {
+    fn from(event: ClipEvent) -> Self {
+        match event {
+            ClipEvent::Inserted(metadata) => Self::Inserted(metadata.into()),
+            ClipEvent::Removed { id } => Self::Removed { id: ClipId(id) },
+            ClipEvent::Marked(metadata) => Self::Marked(metadata.into()),
+            ClipEvent::Pinned(metadata) => Self::Pinned(metadata.into()),
+            ClipEvent::Unpinned(metadata) => Self::Unpinned(metadata.into()),
+            ClipEvent::Cleared => Self::Cleared,
+        }
+    }
+}
+
+fn preview_length(preview_length: Option<usize>) -> u64 {
+    u64::try_from(preview_length.unwrap_or(DEFAULT_PREVIEW_LENGTH)).unwrap_or(u64::MAX)
+}
+
+pub async fn list<Notification>(
+    State(services): ServicesState<Notification>,
+    Query(ListParams { kind, preview_length: length }): Query<ListParams>,
+) -> Result<Json<Vec<Clip>>, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let kinds = parse_kind(kind.as_deref())?
+        .into_iter()
+        .map(|kind| proto::ClipboardKind::from(kind).into())
+        .collect();
+    let request = proto::ListRequest { preview_length: preview_length(length), kinds };
+    let mut metadata: Vec<_> = services
+        .manager
+        .list(Request::new(request))
+        .await?
+        .into_inner()
+        .metadata
+        .into_iter()
+        .map(ClipEntryMetadata::from)
+        .collect();
+    // the newest clip comes first
+    metadata.sort_unstable();
+    Ok(Json(metadata.into_iter().map(Clip::from).collect()))
+}
+
+pub async fn search<Notification>(
+    State(services): ServicesState<Notification>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<SearchResponse>, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let SearchParams {
+        pattern,
+        regex,
+        case_sensitive,
+        kind,
+        mime,
+        since,
+        until,
+        offset,
+        limit,
+        preview_length: length,
+    } = params;
+    let mimes = match mime {
+        Some(mime) => {
+            vec![mime::Mime::from_str(&mime).map_err(|_err| Error::InvalidMime { mime })?]
+        }
+        None => Vec::new(),
+    };
+    let query = SearchQuery {
+        pattern,
+        mode: if regex { SearchMode::Regex } else { SearchMode::Substring },
+        case_sensitive,
+        kinds: parse_kind(kind.as_deref())?.into_iter().collect(),
+        mimes,
+        since,
+        until,
+        offset,
+        limit,
+        preview_length: length.unwrap_or(DEFAULT_PREVIEW_LENGTH),
+    };
+    let request = proto::SearchRequest::from(query);
+    let proto::SearchResponse { metadata, total } =
+        services.manager.search(Request::new(request)).await?.into_inner();
+    let clips =
+        metadata.into_iter().map(|metadata| ClipEntryMetadata::from(metadata).into()).collect();
+    Ok(Json(SearchResponse { clips, total }))
+}
+
+pub async fn get<Notification>(
+    State(services): ServicesState<Notification>,
+    Path(ClipId(id)): Path<ClipId>,
+) -> Result<Json<ClipData>, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let clip = services
+        .manager
+        .get(Request::new(proto::GetRequest { id }))
+        .await?
+        .into_inner()
+        .data
+        .ok_or(Error::NotFound { id })?;
+    Ok(Json(clip.into()))
+}
+
+pub async fn get_current_clip<Notification>(
+    State(services): ServicesState<Notification>,
+    Query(KindParams { kind }): Query<KindParams>,
+) -> Result<Response, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let kind = parse_kind(kind.as_deref())?.unwrap_or_default();
+    let request = proto::GetCurrentClipRequest { kind: proto::ClipboardKind::from(kind).into() };
+    let clip = services.manager.get_current_clip(Request::new(request)).await?.into_inner().data;
+    // there might be no clip in clipboard
+    Ok(clip.map_or_else(
+        || StatusCode::NO_CONTENT.into_response(),
+        |clip| Json(ClipData::from(clip)).into_response(),
+    ))
+}
+
+pub async fn content<Notification>(
+    State(services): ServicesState<Notification>,
+    Path(ClipId(id)): Path<ClipId>,
+) -> Result<Response, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let proto::ClipEntry { data, mime, .. } = services
+        .manager
+        .get(Request::new(proto::GetRequest { id }))
+        .await?
+        .into_inner()
+        .data
+        .ok_or(Error::NotFound { id })?;
+    let content_type = HeaderValue::from_str(&mime)
+        .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+    let headers = [
+        (header::CONTENT_TYPE, content_type),
+        (header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+        // content is untrusted, e.g. HTML or SVG with scripts
+        (header::CONTENT_SECURITY_POLICY, HeaderValue::from_static("sandbox")),
+    ];
+    Ok((headers, data).into_response())
+}
+
+pub async fn insert<Notification>(
+    State(services): ServicesState<Notification>,
+    Json(InsertRequest { kind, mime, text, data, ttl_ms }): Json<InsertRequest>,
+) -> Result<(StatusCode, Json<IdResponse>), Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let data = match (text, data) {
+        (Some(text), None) => text.into_bytes(),
+        (None, Some(data)) => BASE64.decode(data).map_err(|_err| Error::InvalidData)?,
+        _ => return Err(Error::InvalidData),
+    };
+    let request = proto::InsertRequest {
+        kind: proto::ClipboardKind::from(kind).into(),
+        data,
+        mime: mime.to_string(),
+        ttl_ms,
+    };
+    let proto::InsertResponse { id } =
+        services.manager.insert(Request::new(request)).await?.into_inner();
+    Ok((StatusCode::CREATED, Json(IdResponse { id: ClipId(id) })))
+}
+
+pub async fn update<Notification>(
+    State(services): ServicesState<Notification>,
+    Path(ClipId(id)): Path<ClipId>,
+    headers: HeaderMap,
+    data: Bytes,
+) -> Result<Json<IdResponse>, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let mime = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|mime| mime.to_str().ok())
+        .map_or_else(|| mime::TEXT_PLAIN_UTF_8.to_string(), ToString::to_string);
+    let proto::UpdateResponse { ok, new_id } = services
+        .manager
+        .update(Request::new(proto::UpdateRequest { id, data: data.to_vec(), mime }))
+        .await?
+        .into_inner();
+    if ok {
+        Ok(Json(IdResponse { id: ClipId(new_id) }))
+    } else {
+        Err(Error::NotFound { id })
+    }
+}
+
+pub async fn mark<Notification>(
+    State(services): ServicesState<Notification>,
+    Path(ClipId(id)): Path<ClipId>,
+    request: Option<Json<MarkRequest>>,
+) -> Result<StatusCode, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let Json(MarkRequest { kind }) = request.unwrap_or_default();
+    let request = proto::MarkRequest { id, kind: proto::ClipboardKind::from(kind).into() };
+    if services.manager.mark(Request::new(request)).await?.into_inner().ok {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound { id })
+    }
+}
+
+pub async fn pin<Notification>(
+    State(services): ServicesState<Notification>,
+    Path(ClipId(id)): Path<ClipId>,
+) -> Result<StatusCode, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    if services.manager.pin(Request::new(proto::PinRequest { id })).await?.into_inner().ok {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound { id })
+    }
+}
+
+pub async fn unpin<Notification>(
+    State(services): ServicesState<Notification>,
+    Path(ClipId(id)): Path<ClipId>,
+) -> Result<StatusCode, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    if services.manager.unpin(Request::new(proto::UnpinRequest { id })).await?.into_inner().ok {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound { id })
+    }
+}
+
+pub async fn remove<Notification>(
+    State(services): ServicesState<Notification>,
+    Path(ClipId(id)): Path<ClipId>,
+) -> Result<StatusCode, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    if services.manager.remove(Request::new(proto::RemoveRequest { id })).await?.into_inner().ok {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound { id })
+    }
+}
+
+pub async fn batch_remove<Notification>(
+    State(services): ServicesState<Notification>,
+    Json(Ids { ids }): Json<Ids>,
+) -> Result<Json<Ids>, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let request = proto::BatchRemoveRequest { ids: ids.into_iter().map(|ClipId(id)| id).collect() };
+    let proto::BatchRemoveResponse { ids } =
+        services.manager.batch_remove(Request::new(request)).await?.into_inner();
+    Ok(Json(Ids { ids: ids.into_iter().map(ClipId).collect() }))
+}
+
+pub async fn clear<Notification>(
+    State(services): ServicesState<Notification>,
+) -> Result<StatusCode, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let _unused = services.manager.clear(Request::new(())).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn length<Notification>(
+    State(services): ServicesState<Notification>,
+) -> Result<Json<LengthResponse>, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let proto::LengthResponse { length } =
+        services.manager.length(Request::new(())).await?.into_inner();
+    Ok(Json(LengthResponse { length }))
+}
+
+/// Streams events of clips as newline-delimited JSON.
+pub async fn subscribe<Notification>(
+    State(services): ServicesState<Notification>,
+    Query(SubscribeParams { preview_length: length }): Query<SubscribeParams>,
+) -> Result<Response, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let request = proto::SubscribeRequest { preview_length: preview_length(length) };
+    let events = services.manager.subscribe(Request::new(request)).await?.into_inner();
+    let lines = events
+        .filter_map(|event| async move {
+            let event = Event::from(ClipEvent::from(event.ok()?.event?));
+            let mut line = serde_json::to_vec(&event).ok()?;
+            line.push(b'\n');
+            Some(Ok::<_, Infallible>(Bytes::from(line)))
+        })
+        .boxed();
+    let headers = [(header::CONTENT_TYPE, HeaderValue::from_static("application/x-ndjson"))];
+    Ok((headers, StreamBody::new(lines)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        Router,
+    };
+    use clipcat_base::{ClipEntry, ClipboardKind};
+    use tokio::sync::{watch, Mutex};
+    use tower::ServiceExt as _;
+
+    use crate::{
+        backend::MockClipboardBackend,
+        config::HistoryBackend,
+        history::HistoryManager,
+        http::api::{router, Services},
+        notification::MockNotification,
+        ClipboardManager, ClipboardWatcher, ClipboardWatcherOptions,
+    };
+
+    async fn send(router: Router, method: Method, uri: &str, body: &str) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_api() {
+        let file_path = std::env::temp_dir()
+            .join(format!("clipcat-webui-api-test-{pid}", pid = std::process::id()));
+        let history_manager =
+            HistoryManager::new(&file_path, HistoryBackend::FileSystem, None).await.unwrap();
+        let backend = Arc::new(MockClipboardBackend::new());
+        let watcher = ClipboardWatcher::new(
+            backend.clone(),
+            &ClipboardWatcherOptions::default(),
+            MockNotification::default(),
+        )
+        .unwrap();
+        let mut manager = ClipboardManager::new(backend, MockNotification::default());
+        let clip = ClipEntry::from_string("Alpha", ClipboardKind::Clipboard);
+        let id = format!("{:016x}", manager.insert(clip));
+        let _unused = manager.insert(ClipEntry::from_string("Beta", ClipboardKind::Primary));
+        let (_shutdown_sender, shutdown_receiver) = watch::channel(());
+        let router = router(Arc::new(Services::new(
+            watcher.get_toggle(),
+            Arc::new(Mutex::new(manager)),
+            Arc::new(Mutex::new(history_manager)),
+            shutdown_receiver,
+        )));
+
+        let (status, body) = send(router.clone(), Method::GET, "/clips?kind=primary", "").await;
+        assert_eq!(status, StatusCode::OK);
+        let clips: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(clips.as_array().unwrap().len(), 1);
+        assert_eq!(clips[0]["preview"], "Beta");
+        assert_eq!(clips[0]["kind"], "Primary");
+
+        let (status, _) = send(router.clone(), Method::GET, "/clips?kind=unknown", "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) =
+            send(router.clone(), Method::GET, "/clips/search?pattern=alp", "").await;
+        assert_eq!(status, StatusCode::OK);
+        let response: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(response["total"], 1);
+        assert_eq!(response["clips"][0]["id"], id.as_str());
+
+        let (status, body) =
+            send(router.clone(), Method::GET, &format!("/clips/{id}/content"), "").await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "Alpha"));
+
+        let (status, body) =
+            send(router.clone(), Method::PUT, &format!("/clips/{id}/content"), "Gamma").await;
+        assert_eq!(status, StatusCode::OK);
+        let response: serde_json::Value = serde_json::from_str(&body).unwrap();
+        let new_id = response["id"].as_str().unwrap().to_string();
+        let (_, body) =
+            send(router.clone(), Method::GET, &format!("/clips/{new_id}/content"), "").await;
+        assert_eq!(body, "Gamma");
+
+        let (status, _) =
+            send(router.clone(), Method::DELETE, &format!("/clips/{new_id}"), "").await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) =
+            send(router.clone(), Method::GET, &format!("/clips/{new_id}/content"), "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(router.clone(), Method::GET, "/clips/not-an-id/content", "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        drop(std::fs::remove_dir_all(file_path));
+    }
+}
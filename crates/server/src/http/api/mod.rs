@@ -0,0 +1,190 @@
+mod manager;
+mod system;
+mod watcher;
+
+use std::{str::FromStr, sync::Arc};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use clipcat_base::{ClipEntryMetadata, ClipSource, ClipboardKind};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use time::OffsetDateTime;
+use tokio::sync::{watch, Mutex};
+use tonic::{Code, Status};
+
+use crate::{
+    grpc::{ManagerService, SystemService, WatcherService},
+    history::HistoryManager,
+    notification, ClipboardManager, ClipboardWatcherToggle,
+};
+
+/// The gRPC services which are exposed as a JSON API, every endpoint calls
+/// the corresponding RPC, so both of them behave the same.
+pub struct Services<Notification> {
+    pub manager: ManagerService<Notification>,
+
+    pub watcher: WatcherService<Notification>,
+
+    pub system: SystemService,
+}
+
+impl<Notification> Services<Notification> {
+    pub fn new(
+        clipboard_watcher_toggle: ClipboardWatcherToggle<Notification>,
+        clipboard_manager: Arc<Mutex<ClipboardManager<Notification>>>,
+        history_manager: Arc<Mutex<HistoryManager>>,
+        shutdown_receiver: watch::Receiver<()>,
+    ) -> Self {
+        Self {
+            manager: ManagerService::new(clipboard_manager, history_manager, shutdown_receiver),
+            watcher: WatcherService::new(clipboard_watcher_toggle),
+            system: SystemService::new(),
+        }
+    }
+}
+
+type ServicesState<Notification> = State<Arc<Services<Notification>>>;
+
+pub fn router<Notification>(services: Arc<Services<Notification>>) -> Router
+where
+    Notification: notification::Notification + 'static,
+{
+    Router::new()
+        .route("/clips", get(manager::list::<Notification>).post(manager::insert::<Notification>))
+        .route("/clips/search", get(manager::search::<Notification>))
+        .route("/clips/current", get(manager::get_current_clip::<Notification>))
+        .route("/clips/length", get(manager::length::<Notification>))
+        .route("/clips/batch-remove", post(manager::batch_remove::<Notification>))
+        .route("/clips/clear", post(manager::clear::<Notification>))
+        .route(
+            "/clips/:id",
+            get(manager::get::<Notification>).delete(manager::remove::<Notification>),
+        )
+        .route(
+            "/clips/:id/content",
+            get(manager::content::<Notification>).put(manager::update::<Notification>),
+        )
+        .route("/clips/:id/mark", post(manager::mark::<Notification>))
+        .route(
+            "/clips/:id/pin",
+            post(manager::pin::<Notification>).delete(manager::unpin::<Notification>),
+        )
+        .route("/events", get(manager::subscribe::<Notification>))
+        .route("/watcher", get(watcher::get_state::<Notification>))
+        .route("/watcher/enable", post(watcher::enable::<Notification>))
+        .route("/watcher/disable", post(watcher::disable::<Notification>))
+        .route("/watcher/toggle", post(watcher::toggle::<Notification>))
+        .route("/version", get(system::get_version::<Notification>))
+        .with_state(services)
+}
+
+/// ID of clip, it is formatted as 16 hexadecimal digits like `clipcatctl`
+/// does, JavaScript could not hold 64-bit integers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct ClipId(u64);
+
+impl Serialize for ClipId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{:016x}", self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for ClipId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let id = String::deserialize(deserializer)?;
+        u64::from_str_radix(&id, 16).map(Self).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Metadata of clip, the content is truncated to a preview.
+#[derive(Debug, Serialize)]
+struct Clip {
+    id: ClipId,
+
+    #[serde(with = "clipcat_base::serde::clipboard_kind")]
+    kind: ClipboardKind,
+
+    #[serde(with = "time::serde::rfc3339")]
+    timestamp: OffsetDateTime,
+
+    #[serde(with = "clipcat_base::serde::mime")]
+    mime: mime::Mime,
+
+    preview: String,
+
+    pinned: bool,
+
+    source: Option<ClipSource>,
+}
+
+impl From<ClipEntryMetadata> for Clip {
+    fn from(
+        ClipEntryMetadata { id, kind, timestamp, mime, preview, pinned, source }: ClipEntryMetadata,
+    ) -> Self {
+        Self { id: ClipId(id), kind, timestamp, mime, preview, pinned, source }
+    }
+}
+
+fn parse_kind(kind: Option<&str>) -> Result<Option<ClipboardKind>, Error> {
+    kind.map(|kind| {
+        ClipboardKind::from_str(kind).map_err(|_err| Error::InvalidKind { kind: kind.to_string() })
+    })
+    .transpose()
+}
+
+#[derive(Debug)]
+enum Error {
+    NotFound { id: u64 },
+
+    InvalidKind { kind: String },
+
+    InvalidMime { mime: String },
+
+    InvalidData,
+
+    Status { status: Status },
+}
+
+impl From<Status> for Error {
+    fn from(status: Status) -> Self { Self::Status { status } }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let (code, message) = match self {
+            Self::NotFound { id } => {
+                (StatusCode::NOT_FOUND, format!("Clip {id:016x} is not found"))
+            }
+            Self::InvalidKind { kind } => {
+                (StatusCode::BAD_REQUEST, format!("Unknown clipboard kind `{kind}`"))
+            }
+            Self::InvalidMime { mime } => {
+                (StatusCode::BAD_REQUEST, format!("Invalid MIME type `{mime}`"))
+            }
+            Self::InvalidData => (
+                StatusCode::BAD_REQUEST,
+                "Exactly one of `text` and `data` (base64) is required".to_string(),
+            ),
+            Self::Status { status } => {
+                let code = match status.code() {
+                    Code::InvalidArgument => StatusCode::BAD_REQUEST,
+                    Code::NotFound => StatusCode::NOT_FOUND,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (code, status.message().to_string())
+            }
+        };
+        (code, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
@@ -0,0 +1,29 @@
+use axum::{extract::State, Json};
+use clipcat_proto::{self as proto, System as _};
+use serde::Serialize;
+use tonic::Request;
+
+use crate::{
+    http::api::{Error, ServicesState},
+    notification,
+};
+
+#[derive(Debug, Serialize)]
+pub struct Version {
+    major: u64,
+
+    minor: u64,
+
+    patch: u64,
+}
+
+pub async fn get_version<Notification>(
+    State(services): ServicesState<Notification>,
+) -> Result<Json<Version>, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    let proto::GetSystemVersionResponse { major, minor, patch } =
+        services.system.get_version(Request::new(())).await?.into_inner();
+    Ok(Json(Version { major, minor, patch }))
+}
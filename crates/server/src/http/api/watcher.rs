@@ -0,0 +1,63 @@
+use axum::{extract::State, Json};
+use clipcat_base::ClipboardWatcherState;
+use clipcat_proto::{self as proto, Watcher as _};
+use serde::Serialize;
+use tonic::{Request, Response, Status};
+
+use crate::{
+    http::api::{Error, ServicesState},
+    notification,
+};
+
+#[derive(Debug, Serialize)]
+pub struct WatcherState {
+    state: ClipboardWatcherState,
+}
+
+impl From<Response<proto::WatcherStateReply>> for WatcherState {
+    fn from(reply: Response<proto::WatcherStateReply>) -> Self {
+        Self { state: ClipboardWatcherState::from(reply.into_inner().state) }
+    }
+}
+
+fn reply(
+    reply: Result<Response<proto::WatcherStateReply>, Status>,
+) -> Result<Json<WatcherState>, Error> {
+    Ok(Json(reply?.into()))
+}
+
+pub async fn get_state<Notification>(
+    State(services): ServicesState<Notification>,
+) -> Result<Json<WatcherState>, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    reply(services.watcher.get_watcher_state(Request::new(())).await)
+}
+
+pub async fn enable<Notification>(
+    State(services): ServicesState<Notification>,
+) -> Result<Json<WatcherState>, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    reply(services.watcher.enable_watcher(Request::new(())).await)
+}
+
+pub async fn disable<Notification>(
+    State(services): ServicesState<Notification>,
+) -> Result<Json<WatcherState>, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    reply(services.watcher.disable_watcher(Request::new(())).await)
+}
+
+pub async fn toggle<Notification>(
+    State(services): ServicesState<Notification>,
+) -> Result<Json<WatcherState>, Error>
+where
+    Notification: notification::Notification + 'static,
+{
+    reply(services.watcher.toggle_watcher(Request::new(())).await)
+}
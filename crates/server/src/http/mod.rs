@@ -0,0 +1,238 @@
+mod api;
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderValue, Request, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+pub use self::api::Services;
+use crate::{config::AccessToken, grpc::Authenticator, notification};
+
+const INDEX_HTML: &str = include_str!("index.html");
+
+/// Creates the routes of web UI, the page is backed by the JSON API which is
+/// served under `/api`. The page itself is public, the JSON API requires
+/// `access_token`.
+pub fn webui_router<Notification>(
+    services: Arc<Services<Notification>>,
+    access_token: &AccessToken,
+) -> Router
+where
+    Notification: notification::Notification + 'static,
+{
+    Router::new()
+        .route("/", get(index))
+        .nest("/api", require_access_token(api::router(services), access_token))
+}
+
+/// Creates the routes of the JSON API alone, they are served under `/api`.
+pub fn api_router<Notification>(services: Arc<Services<Notification>>) -> Router
+where
+    Notification: notification::Notification + 'static,
+{
+    Router::new().nest("/api", api::router(services))
+}
+
+/// Rejects requests which do not present `access_token` as a bearer token,
+/// it is applied to the JSON API which is served over TCP, any local user or
+/// process could connect to it otherwise.
+pub fn require_access_token(router: Router, access_token: &AccessToken) -> Router {
+    let authenticator = Authenticator::new(Some(access_token));
+    router.layer(middleware::from_fn_with_state(authenticator, check_access_token))
+}
+
+/// Rejects requests for hosts other than the local one and requests which
+/// change state from other origins, it is applied to routers which are served
+/// over TCP on `listen_address`.
+pub fn restrict_to_local_host(router: Router, listen_address: SocketAddr) -> Router {
+    router
+        .layer(middleware::from_fn(check_origin))
+        .layer(middleware::from_fn_with_state(listen_address, check_host))
+}
+
+async fn index() -> Html<&'static str> { Html(INDEX_HTML) }
+
+async fn check_access_token(
+    State(authenticator): State<Authenticator>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let authorization = request.headers().get(header::AUTHORIZATION).map(HeaderValue::as_bytes);
+    if authenticator.is_authorized(authorization) {
+        next.run(request).await
+    } else {
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "Access token is missing or invalid",
+        )
+            .into_response()
+    }
+}
+
+// reject requests for other hosts, so that web pages could not read clips
+// through DNS rebinding
+async fn check_host(
+    State(listen_address): State<SocketAddr>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let host = request.headers().get(header::HOST).and_then(|host| host.to_str().ok());
+    if host.map_or(true, |host| is_local_host(host, listen_address.ip())) {
+        next.run(request).await
+    } else {
+        (StatusCode::FORBIDDEN, "Host is not allowed").into_response()
+    }
+}
+
+// reject state-changing requests sent by other sites, a form or a `no-cors`
+// fetch on any web page could reach the local host without being preflighted,
+// clients other than browsers send neither of these headers
+async fn check_origin(request: Request<Body>, next: Next<Body>) -> Response {
+    if request.method().is_safe() {
+        return next.run(request).await;
+    }
+
+    let headers = request.headers();
+    let is_cross_site =
+        headers.get("sec-fetch-site").is_some_and(|site| site != "same-origin" && site != "none");
+    let is_cross_origin = headers.get(header::ORIGIN).is_some_and(|origin| {
+        let host = headers.get(header::HOST).and_then(|host| host.to_str().ok());
+        let origin = origin.to_str().ok().and_then(|origin| origin.split_once("://"));
+        match (origin, host) {
+            (Some((_scheme, origin)), Some(host)) => !origin.eq_ignore_ascii_case(host),
+            _ => true,
+        }
+    });
+    if is_cross_site || is_cross_origin {
+        (StatusCode::FORBIDDEN, "Cross-site request is not allowed").into_response()
+    } else {
+        next.run(request).await
+    }
+}
+
+// only loopback addresses and the address which the server listens on are
+// accepted, the latter is not accepted if it is unspecified, e.g. `0.0.0.0`
+fn is_local_host(host: &str, listen_address: IpAddr) -> bool {
+    let hostname = host
+        .strip_prefix('[')
+        .and_then(|host| host.split_once(']'))
+        .map_or_else(|| host.rsplit_once(':').map_or(host, |(hostname, _)| hostname), |(ip, _)| ip);
+    hostname.eq_ignore_ascii_case("localhost")
+        || hostname.parse::<IpAddr>().is_ok_and(|ip| {
+            ip.is_loopback() || (ip == listen_address && !listen_address.is_unspecified())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use tower::ServiceExt as _;
+
+    use crate::{
+        config::AccessToken,
+        http::{is_local_host, require_access_token, restrict_to_local_host},
+    };
+
+    async fn send(method: Method, headers: &[(&str, &str)]) -> StatusCode {
+        let router = restrict_to_local_host(
+            Router::new().route("/api/clips/clear", post(|| async { StatusCode::NO_CONTENT })),
+            ([127, 0, 0, 1], 45046).into(),
+        );
+        let request = headers
+            .iter()
+            .fold(Request::builder().method(method).uri("/api/clips/clear"), |request, header| {
+                request.header(header.0, header.1)
+            })
+            .header(header::HOST, "127.0.0.1:45046")
+            .body(Body::empty())
+            .unwrap();
+        router.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_reject_cross_site_requests() {
+        // a form on a foreign page is posted to the local host
+        let status = send(Method::POST, &[("origin", "https://example.com")]).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let status = send(Method::POST, &[("origin", "null")]).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let status = send(Method::POST, &[("sec-fetch-site", "cross-site")]).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let status = send(Method::POST, &[("sec-fetch-site", "same-site")]).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // the web UI and clients other than browsers
+        let headers = [("origin", "http://127.0.0.1:45046"), ("sec-fetch-site", "same-origin")];
+        assert_eq!(send(Method::POST, &headers).await, StatusCode::NO_CONTENT);
+        assert_eq!(send(Method::POST, &[]).await, StatusCode::NO_CONTENT);
+        // requests which do not change state are left to the same-origin policy
+        let status = send(Method::GET, &[("origin", "https://example.com")]).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[tokio::test]
+    async fn test_require_access_token() {
+        let router = require_access_token(
+            Router::new().route("/api/clips/clear", post(|| async { StatusCode::NO_CONTENT })),
+            &AccessToken::new("secret".to_string()),
+        );
+        for (authorization, expected) in [
+            (Some("Bearer secret"), StatusCode::NO_CONTENT),
+            (Some("Bearer secreT"), StatusCode::UNAUTHORIZED),
+            (Some("secret"), StatusCode::UNAUTHORIZED),
+            (None, StatusCode::UNAUTHORIZED),
+        ] {
+            let request = authorization
+                .into_iter()
+                .fold(Request::builder().method(Method::POST), |request, authorization| {
+                    request.header(header::AUTHORIZATION, authorization)
+                })
+                .uri("/api/clips/clear")
+                .body(Body::empty())
+                .unwrap();
+            let status = router.clone().oneshot(request).await.unwrap().status();
+            assert_eq!(status, expected, "{authorization:?}");
+        }
+    }
+
+    #[test]
+    fn test_is_local_host() {
+        let listen_address = [192, 168, 1, 2].into();
+        for host in [
+            "localhost",
+            "LocalHost:45046",
+            "127.0.0.1",
+            "127.0.0.1:45046",
+            "[::1]:45046",
+            "192.168.1.2:45046",
+        ] {
+            assert!(is_local_host(host, listen_address), "{host}");
+        }
+        for host in [
+            "example.com",
+            "example.com:45046",
+            "127.0.0.1.example.com",
+            "192.168.1.3:45046",
+            "[2001:db8::1]:45046",
+        ] {
+            assert!(!is_local_host(host, listen_address), "{host}");
+        }
+        assert!(!is_local_host("0.0.0.0:45046", [0, 0, 0, 0].into()));
+    }
+}
@@ -1,101 +1,149 @@
 pub mod backend;
 pub mod config;
+mod dbus;
 mod error;
 mod grpc;
 mod history;
+mod http;
+mod local_socket;
 mod manager;
 mod notification;
 mod watcher;
 
-use std::{future::Future, net::SocketAddr, path::PathBuf, pin::Pin, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
 
-use clipcat_base::ClipEntry;
+use clipcat_base::{ClipEntry, ClipboardKind};
 use clipcat_proto::{ManagerServer, SystemServer, WatcherServer};
 use futures::{FutureExt, StreamExt};
 use notification::Notification;
 use sigfinn::{ExitStatus, Handle, LifecycleManager, Shutdown};
-use snafu::ResultExt;
-use tokio::{
-    net::UnixListener,
-    sync::{broadcast::error::RecvError, Mutex},
-};
-use tokio_stream::wrappers::UnixListenerStream;
+use snafu::{ensure, ResultExt};
+use time::OffsetDateTime;
+use tokio::sync::{broadcast::error::RecvError, watch, Mutex};
+use tonic::transport::{Identity, ServerTlsConfig};
 
 pub use self::{
     config::Config,
     error::{Error, Result},
-    watcher::{ClipboardWatcherOptions, Toggle as ClipboardWatcherToggle},
+    watcher::{
+        ClipboardWatcherOptions, FilterOptions as ClipboardWatcherFilterOptions,
+        Toggle as ClipboardWatcherToggle,
+    },
+};
+use self::{
+    history::HistoryManager,
+    manager::ClipboardManager,
+    watcher::{CapturedClip, ClipboardWatcher},
 };
-use self::{history::HistoryManager, manager::ClipboardManager, watcher::ClipboardWatcher};
 
+const EXPIRATION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// # Errors
+///
+/// This function will return an error if the server fails to start.
+pub async fn serve_with_shutdown(config: Config, snippets: &[ClipEntry]) -> Result<()> {
+    let sensitive_mime_types = config.watcher.filters.sensitive_mime_types.clone();
+    serve(config, snippets, |desktop_notification| {
+        backend::new_shared(&[Arc::new(desktop_notification.clone())], &sensitive_mime_types)
+            .context(error::CreateClipboardBackendSnafu)
+    })
+    .await
+}
+
+/// Serves with `clipboard_backend` instead of the clipboard of the desktop,
+/// e.g. `MockClipboardBackend` in tests.
+///
 /// # Errors
 ///
 /// This function will return an error if the server fails to start.
-pub async fn serve_with_shutdown(
+pub async fn serve_with_backend(
+    config: Config,
+    snippets: &[ClipEntry],
+    clipboard_backend: Arc<dyn backend::ClipboardBackend>,
+) -> Result<()> {
+    serve(config, snippets, |_| Ok(clipboard_backend)).await
+}
+
+#[allow(clippy::too_many_lines)]
+async fn serve<F>(
     Config {
         grpc_listen_address,
         grpc_local_socket,
+        local_socket_access,
+        grpc_tls,
+        grpc_access_token,
+        webui_listen_address,
+        webui_access_token,
+        rest_listen_address,
+        rest_access_token,
+        rest_local_socket,
+        dbus_name,
         max_history,
+        max_age,
         history_file_path,
+        history_backend,
+        history_encryption_key,
         watcher: watcher_opts,
         desktop_notification: desktop_notification_config,
     }: Config,
     snippets: &[ClipEntry],
-) -> Result<()> {
+    create_clipboard_backend: F,
+) -> Result<()>
+where
+    F: FnOnce(&notification::DesktopNotification) -> Result<Arc<dyn backend::ClipboardBackend>>
+        + Send,
+{
+    ensure!(
+        webui_listen_address.is_none() || webui_access_token.is_some(),
+        error::MissingAccessTokenSnafu { server: "Web UI server" }
+    );
+    ensure!(
+        rest_listen_address.is_none() || rest_access_token.is_some(),
+        error::MissingAccessTokenSnafu { server: "JSON API HTTP server" }
+    );
+
     let (desktop_notification, desktop_notification_worker) =
         notification::DesktopNotification::new(
             desktop_notification_config.icon,
             desktop_notification_config.timeout,
         );
 
-    let clipboard_backend = backend::new_shared(&[Arc::new(desktop_notification.clone())])
-        .context(error::CreateClipboardBackendSnafu)?;
+    let clipboard_backend = create_clipboard_backend(&desktop_notification)?;
 
     let (clipboard_manager, history_manager) = {
         tracing::info!("History file path: `{path}`", path = history_file_path.display());
-        let mut history_manager = HistoryManager::new(&history_file_path)
-            .await
-            .context(error::CreateHistoryManagerSnafu)?;
-
-        tracing::info!("Load history from `{path}`", path = history_manager.path().display());
-        let history_clips = history_manager
-            .load()
-            .await
-            .map_err(|err| {
-                tracing::error!(
-                    "Could not load history, data might be corrupted, please remove `{path}`, \
-                     error: {err}",
-                    path = history_manager.path().display()
-                );
-            })
-            .unwrap_or_default();
-        let clip_count = history_clips.len();
-        if clip_count > 0 {
-            tracing::info!("{clip_count} clip(s) loaded");
-        }
-        let snippet_count = snippets.len();
-        if snippet_count > 0 {
-            tracing::info!("{snippet_count} snippet(s) loaded");
-        }
-
-        tracing::info!("Initialize ClipboardManager with capacity {max_history}");
-        let mut clipboard_manager = ClipboardManager::with_capacity(
+        let mut history_manager = HistoryManager::new(
+            &history_file_path,
+            history_backend,
+            history_encryption_key.as_ref(),
+        )
+        .await
+        .context(error::CreateHistoryManagerSnafu)?;
+
+        let clipboard_manager = create_clipboard_manager(
             clipboard_backend.clone(),
-            max_history,
             desktop_notification.clone(),
-        );
-
-        tracing::info!("Import {clip_count} clip(s) into ClipboardManager");
-        clipboard_manager.import(&history_clips);
-
-        tracing::info!("Import {snippet_count} snippet(s) into ClipboardManager");
-        clipboard_manager.insert_snippets(snippets);
+            max_history,
+            max_age,
+            &mut history_manager,
+            snippets,
+        )
+        .await?;
 
-        (Arc::new(Mutex::new(clipboard_manager)), history_manager)
+        (Arc::new(Mutex::new(clipboard_manager)), Arc::new(Mutex::new(history_manager)))
     };
 
     let clipboard_watcher =
-        ClipboardWatcher::new(clipboard_backend, watcher_opts, desktop_notification.clone())
+        ClipboardWatcher::new(clipboard_backend, &watcher_opts, desktop_notification.clone())
             .context(error::CreateClipboardWatcherSnafu)?;
 
     let lifecycle_manager = LifecycleManager::<Error>::new();
@@ -112,8 +160,11 @@ pub async fn serve_with_shutdown(
             "gRPC HTTP server",
             create_grpc_http_server_future(
                 grpc_listen_address,
+                grpc_tls,
+                grpc_access_token,
                 clipboard_watcher.get_toggle(),
                 clipboard_manager.clone(),
+                history_manager.clone(),
             ),
         );
     }
@@ -123,12 +174,42 @@ pub async fn serve_with_shutdown(
             "gRPC local socket server",
             create_grpc_local_socket_server_future(
                 grpc_local_socket,
+                local_socket_access.clone(),
+                clipboard_watcher.get_toggle(),
+                clipboard_manager.clone(),
+                history_manager.clone(),
+            ),
+        );
+    }
+
+    spawn_http_servers(
+        &lifecycle_manager,
+        webui_listen_address.zip(webui_access_token),
+        rest_listen_address.zip(rest_access_token),
+        rest_local_socket
+            .map(|path| HttpEndpoint::LocalSocket { path, access: local_socket_access }),
+        &clipboard_watcher,
+        &clipboard_manager,
+        &history_manager,
+    );
+
+    if let Some(dbus_name) = dbus_name {
+        let _handle = lifecycle_manager.spawn(
+            "D-Bus server",
+            create_dbus_server_future(
+                dbus_name,
                 clipboard_watcher.get_toggle(),
                 clipboard_manager.clone(),
+                history_manager.clone(),
             ),
         );
     }
 
+    let _handle = lifecycle_manager.spawn(
+        "Clipboard expiration worker",
+        create_expiration_worker_future(clipboard_manager.clone(), history_manager.clone()),
+    );
+
     let handle = lifecycle_manager.handle();
     let _handle = lifecycle_manager.spawn(
         "Clipboard worker",
@@ -150,37 +231,73 @@ pub async fn serve_with_shutdown(
     }
 }
 
+async fn create_clipboard_manager(
+    clipboard_backend: Arc<dyn backend::ClipboardBackend>,
+    desktop_notification: notification::DesktopNotification,
+    max_history: config::HistoryCapacity,
+    max_age: Option<Duration>,
+    history_manager: &mut HistoryManager,
+    snippets: &[ClipEntry],
+) -> Result<ClipboardManager<notification::DesktopNotification>> {
+    let history_clips = load_history(history_manager).await?;
+    let clip_count = history_clips.len();
+    if clip_count > 0 {
+        tracing::info!("{clip_count} clip(s) loaded");
+    }
+    let snippet_count = snippets.len();
+    if snippet_count > 0 {
+        tracing::info!("{snippet_count} snippet(s) loaded");
+    }
+
+    tracing::info!("Initialize ClipboardManager with capacity {max_history}");
+    let mut clipboard_manager =
+        ClipboardManager::with_capacity(clipboard_backend, max_history, desktop_notification);
+
+    if let Some(max_age) = max_age {
+        tracing::info!("Clips older than {max_age:?} will be removed");
+    }
+    clipboard_manager.set_max_age(max_age);
+
+    tracing::info!("Import {clip_count} clip(s) into ClipboardManager");
+    clipboard_manager.import(&history_clips);
+    clipboard_manager.set_id_aliases(load_id_aliases(history_manager, &history_clips).await);
+
+    tracing::info!("Import {snippet_count} snippet(s) into ClipboardManager");
+    clipboard_manager.insert_snippets(snippets);
+
+    Ok(clipboard_manager)
+}
+
 fn create_grpc_local_socket_server_future(
     local_socket: PathBuf,
+    access: config::LocalSocketAccess,
     clipboard_watcher_toggle: ClipboardWatcherToggle<notification::DesktopNotification>,
     clipboard_manager: Arc<Mutex<ClipboardManager<notification::DesktopNotification>>>,
+    history_manager: Arc<Mutex<HistoryManager>>,
 ) -> impl FnOnce(Shutdown) -> Pin<Box<dyn Future<Output = ExitStatus<Error>> + Send>> {
     move |signal| {
         async move {
             tracing::info!("Listen Clipcat gRPC endpoint on {}", local_socket.display());
-            if let Some(local_socket_parent) = local_socket.parent() {
-                if let Err(err) = tokio::fs::create_dir_all(&local_socket_parent)
-                    .await
-                    .context(error::CreateUnixListenerSnafu { socket_path: local_socket.clone() })
-                {
-                    return ExitStatus::Failure(err);
-                }
-            }
-
-            let uds_stream = match UnixListener::bind(&local_socket)
-                .context(error::CreateUnixListenerSnafu { socket_path: local_socket.clone() })
-            {
-                Ok(uds) => UnixListenerStream::new(uds),
+            let incoming = match local_socket::bind(&local_socket, access).await {
+                Ok(incoming) => incoming,
                 Err(err) => return ExitStatus::Failure(err),
             };
 
+            let (shutdown_sender, shutdown_receiver) = watch::channel(());
             let result = tonic::transport::Server::builder()
                 .add_service(SystemServer::new(grpc::SystemService::new()))
                 .add_service(WatcherServer::new(grpc::WatcherService::new(
                     clipboard_watcher_toggle,
                 )))
-                .add_service(ManagerServer::new(grpc::ManagerService::new(clipboard_manager)))
-                .serve_with_incoming_shutdown(uds_stream, signal)
+                .add_service(ManagerServer::new(grpc::ManagerService::new(
+                    clipboard_manager,
+                    history_manager,
+                    shutdown_receiver,
+                )))
+                .serve_with_incoming_shutdown(incoming, async move {
+                    signal.await;
+                    drop(shutdown_sender);
+                })
                 .await
                 .context(error::StartTonicServerSnafu);
 
@@ -217,20 +334,52 @@ fn create_desktop_notification_worker_future(
 
 fn create_grpc_http_server_future(
     listen_address: SocketAddr,
+    tls: Option<config::GrpcTlsConfig>,
+    access_token: Option<config::AccessToken>,
     clipboard_watcher_toggle: ClipboardWatcherToggle<notification::DesktopNotification>,
     clipboard_manager: Arc<Mutex<ClipboardManager<notification::DesktopNotification>>>,
+    history_manager: Arc<Mutex<HistoryManager>>,
 ) -> impl FnOnce(Shutdown) -> Pin<Box<dyn Future<Output = ExitStatus<Error>> + Send>> {
     move |signal| {
         async move {
             tracing::info!("Listen Clipcat gRPC endpoint on {listen_address}");
+            if access_token.is_some() && tls.is_none() {
+                tracing::warn!(
+                    "Access token of gRPC HTTP server is sent in plaintext, please enable TLS"
+                );
+            }
 
-            let result = tonic::transport::Server::builder()
-                .add_service(SystemServer::new(grpc::SystemService::new()))
-                .add_service(WatcherServer::new(grpc::WatcherService::new(
-                    clipboard_watcher_toggle,
-                )))
-                .add_service(ManagerServer::new(grpc::ManagerService::new(clipboard_manager)))
-                .serve_with_shutdown(listen_address, signal)
+            let mut builder = tonic::transport::Server::builder();
+            if let Some(tls) = tls {
+                builder = match configure_grpc_tls(builder, &tls).await {
+                    Ok(builder) => builder,
+                    Err(err) => return ExitStatus::Failure(err),
+                };
+            }
+
+            let authenticator = grpc::Authenticator::new(access_token.as_ref());
+            let (shutdown_sender, shutdown_receiver) = watch::channel(());
+            let result = builder
+                .add_service(SystemServer::with_interceptor(
+                    grpc::SystemService::new(),
+                    authenticator.clone(),
+                ))
+                .add_service(WatcherServer::with_interceptor(
+                    grpc::WatcherService::new(clipboard_watcher_toggle),
+                    authenticator.clone(),
+                ))
+                .add_service(ManagerServer::with_interceptor(
+                    grpc::ManagerService::new(
+                        clipboard_manager,
+                        history_manager,
+                        shutdown_receiver,
+                    ),
+                    authenticator,
+                ))
+                .serve_with_shutdown(listen_address, async move {
+                    signal.await;
+                    drop(shutdown_sender);
+                })
                 .await
                 .context(error::StartTonicServerSnafu);
 
@@ -246,10 +395,203 @@ fn create_grpc_http_server_future(
     }
 }
 
+async fn configure_grpc_tls(
+    builder: tonic::transport::Server,
+    config::GrpcTlsConfig { cert_file, key_file }: &config::GrpcTlsConfig,
+) -> Result<tonic::transport::Server> {
+    let cert = tokio::fs::read(cert_file)
+        .await
+        .context(error::ReadTlsFileSnafu { file_path: cert_file.clone() })?;
+    let key = tokio::fs::read(key_file)
+        .await
+        .context(error::ReadTlsFileSnafu { file_path: key_file.clone() })?;
+    builder
+        .tls_config(ServerTlsConfig::new().identity(Identity::from_pem(cert, key)))
+        .context(error::ConfigureTlsSnafu)
+}
+
+#[derive(Clone, Debug)]
+enum HttpEndpoint {
+    Tcp(SocketAddr),
+
+    LocalSocket { path: PathBuf, access: config::LocalSocketAccess },
+}
+
+impl fmt::Display for HttpEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(listen_address) => write!(f, "http://{listen_address}"),
+            Self::LocalSocket { path, .. } => write!(f, "`{}`", path.display()),
+        }
+    }
+}
+
+fn spawn_http_servers(
+    lifecycle_manager: &LifecycleManager<Error>,
+    webui: Option<(SocketAddr, config::AccessToken)>,
+    rest: Option<(SocketAddr, config::AccessToken)>,
+    rest_local_socket: Option<HttpEndpoint>,
+    clipboard_watcher: &ClipboardWatcher<notification::DesktopNotification>,
+    clipboard_manager: &Arc<Mutex<ClipboardManager<notification::DesktopNotification>>>,
+    history_manager: &Arc<Mutex<HistoryManager>>,
+) {
+    // every server has its own services, so that their subscriptions are
+    // terminated once the server is shutting down
+    let new_services = || {
+        let (shutdown_sender, shutdown_receiver) = watch::channel(());
+        let services = http::Services::new(
+            clipboard_watcher.get_toggle(),
+            clipboard_manager.clone(),
+            history_manager.clone(),
+            shutdown_receiver,
+        );
+        (Arc::new(services), shutdown_sender)
+    };
+
+    if let Some((listen_address, access_token)) = webui {
+        let (services, shutdown_sender) = new_services();
+        let _handle = lifecycle_manager.spawn(
+            "Web UI server",
+            create_http_server_future(
+                "Web UI server",
+                HttpEndpoint::Tcp(listen_address),
+                http::restrict_to_local_host(
+                    http::webui_router(services, &access_token),
+                    listen_address,
+                ),
+                shutdown_sender,
+            ),
+        );
+    }
+
+    if let Some((listen_address, access_token)) = rest {
+        let (services, shutdown_sender) = new_services();
+        let _handle = lifecycle_manager.spawn(
+            "JSON API HTTP server",
+            create_http_server_future(
+                "JSON API HTTP server",
+                HttpEndpoint::Tcp(listen_address),
+                http::restrict_to_local_host(
+                    http::require_access_token(http::api_router(services), &access_token),
+                    listen_address,
+                ),
+                shutdown_sender,
+            ),
+        );
+    }
+
+    if let Some(endpoint) = rest_local_socket {
+        let (services, shutdown_sender) = new_services();
+        let _handle = lifecycle_manager.spawn(
+            "JSON API local socket server",
+            create_http_server_future(
+                "JSON API local socket server",
+                endpoint,
+                http::api_router(services),
+                shutdown_sender,
+            ),
+        );
+    }
+}
+
+fn create_http_server_future(
+    name: &'static str,
+    endpoint: HttpEndpoint,
+    router: axum::Router,
+    shutdown_sender: watch::Sender<()>,
+) -> impl FnOnce(Shutdown) -> Pin<Box<dyn Future<Output = ExitStatus<Error>> + Send>> {
+    move |signal| {
+        async move {
+            tracing::info!("Serve {name} on {endpoint}");
+
+            let shutdown = async move {
+                signal.await;
+                drop(shutdown_sender);
+            };
+            let result = match &endpoint {
+                HttpEndpoint::Tcp(listen_address) => match axum::Server::try_bind(listen_address) {
+                    Ok(builder) => {
+                        builder
+                            .serve(router.into_make_service())
+                            .with_graceful_shutdown(shutdown)
+                            .await
+                    }
+                    Err(err) => Err(err),
+                },
+                HttpEndpoint::LocalSocket { path: local_socket, access } => {
+                    let incoming = match local_socket::bind(local_socket, access.clone()).await {
+                        Ok(incoming) => incoming,
+                        Err(err) => return ExitStatus::Failure(err),
+                    };
+                    let result =
+                        axum::Server::builder(hyper::server::accept::from_stream(incoming))
+                            .serve(router.into_make_service())
+                            .with_graceful_shutdown(shutdown)
+                            .await;
+                    tracing::info!(
+                        "Remove Unix domain socket `{path}`",
+                        path = local_socket.display()
+                    );
+                    drop(tokio::fs::remove_file(local_socket).await);
+                    result
+                }
+            }
+            .context(error::StartHttpServerSnafu { endpoint: endpoint.to_string() });
+
+            match result {
+                Ok(()) => {
+                    tracing::info!("{name} is shut down gracefully");
+                    ExitStatus::Success
+                }
+                Err(err) => ExitStatus::Failure(err),
+            }
+        }
+        .boxed()
+    }
+}
+
+fn create_dbus_server_future(
+    name: String,
+    clipboard_watcher_toggle: ClipboardWatcherToggle<notification::DesktopNotification>,
+    clipboard_manager: Arc<Mutex<ClipboardManager<notification::DesktopNotification>>>,
+    history_manager: Arc<Mutex<HistoryManager>>,
+) -> impl FnOnce(Shutdown) -> Pin<Box<dyn Future<Output = ExitStatus<Error>> + Send>> {
+    move |signal| {
+        async move {
+            tracing::info!("Serve Clipcat D-Bus interfaces as `{name}` on session bus");
+            let result = match zbus::ConnectionBuilder::session()
+                .and_then(|builder| builder.name(name.as_str()))
+            {
+                Ok(builder) => {
+                    dbus::serve(
+                        builder,
+                        clipboard_watcher_toggle,
+                        clipboard_manager,
+                        history_manager,
+                        signal,
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            }
+            .context(error::StartDBusServerSnafu { name: name.as_str() });
+
+            match result {
+                Ok(()) => {
+                    tracing::info!("D-Bus server is shut down gracefully");
+                    ExitStatus::Success
+                }
+                Err(err) => ExitStatus::Failure(err),
+            }
+        }
+        .boxed()
+    }
+}
+
 fn create_clipboard_worker_future(
     clipboard_watcher: ClipboardWatcher<notification::DesktopNotification>,
     clipboard_manager: Arc<Mutex<ClipboardManager<notification::DesktopNotification>>>,
-    history_manager: HistoryManager,
+    history_manager: Arc<Mutex<HistoryManager>>,
     handle: Handle<Error>,
 ) -> impl FnOnce(Shutdown) -> Pin<Box<dyn Future<Output = ExitStatus<Error>> + Send>> {
     move |shutdown_signal| {
@@ -274,34 +616,203 @@ fn create_clipboard_worker_future(
     }
 }
 
+fn create_expiration_worker_future(
+    clipboard_manager: Arc<Mutex<ClipboardManager<notification::DesktopNotification>>>,
+    history_manager: Arc<Mutex<HistoryManager>>,
+) -> impl FnOnce(Shutdown) -> Pin<Box<dyn Future<Output = ExitStatus<Error>> + Send>> {
+    move |shutdown_signal| {
+        async move {
+            serve_expiration_worker(clipboard_manager, history_manager, shutdown_signal).await;
+            tracing::info!("Clipboard expiration worker is shut down gracefully");
+            ExitStatus::Success
+        }
+        .boxed()
+    }
+}
+
+#[allow(clippy::redundant_pub_crate)]
+async fn serve_expiration_worker(
+    clipboard_manager: Arc<Mutex<ClipboardManager<notification::DesktopNotification>>>,
+    history_manager: Arc<Mutex<HistoryManager>>,
+    shutdown_signal: Shutdown,
+) {
+    let mut shutdown_signal = shutdown_signal.into_stream();
+    let mut interval = tokio::time::interval(EXPIRATION_CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {},
+            _ = shutdown_signal.next() => break,
+        }
+
+        let mut clipboard_manager = clipboard_manager.lock().await;
+        let expired_ids = clipboard_manager.remove_expired(OffsetDateTime::now_utc()).await;
+        if !expired_ids.is_empty() {
+            tracing::info!("{count} expired clip(s) are removed", count = expired_ids.len());
+            if let Err(err) = history_manager.lock().await.remove(&expired_ids).await {
+                tracing::error!("{err}");
+            }
+        }
+        drop(clipboard_manager);
+    }
+}
+
+async fn restore_current_clip(
+    clipboard_manager: &Mutex<ClipboardManager<notification::DesktopNotification>>,
+    kind: ClipboardKind,
+) {
+    let result = clipboard_manager.lock().await.restore_current_clip(kind).await;
+    match result {
+        Ok(true) => tracing::info!("Current clip is restored into {kind}"),
+        Ok(false) => tracing::debug!("There is no current clip of {kind} to restore"),
+        Err(err) => tracing::warn!("Failed to restore current clip into {kind}, error: {err}"),
+    }
+}
+
+async fn load_history(history_manager: &mut HistoryManager) -> Result<Vec<ClipEntry>> {
+    tracing::info!("Load history from `{path}`", path = history_manager.path().display());
+    match history_manager.load().await {
+        Ok(clips) => Ok(clips),
+        // history would be overwritten with nothing if the daemon keeps running
+        Err(err @ (history::Error::MissingEncryptionKey | history::Error::DecryptClip)) => {
+            Err(Error::LoadHistoryManager { source: err })
+        }
+        Err(err) => {
+            tracing::error!(
+                "Could not load history, data might be corrupted, please remove `{path}`, error: \
+                 {err}",
+                path = history_manager.path().display()
+            );
+            Ok(Vec::new())
+        }
+    }
+}
+
+async fn load_id_aliases(
+    history_manager: &HistoryManager,
+    history_clips: &[ClipEntry],
+) -> HashMap<u64, u64> {
+    match history_manager.load_id_aliases(history_clips).await {
+        Ok(id_aliases) => id_aliases,
+        Err(err) => {
+            tracing::warn!("Could not load aliases of clip identifiers, error: {err}");
+            HashMap::new()
+        }
+    }
+}
+
+/// Encrypt the history with `encryption_key` in place, clips which are already
+/// encrypted are re-encrypted. Returns the number of clips.
+///
+/// # Errors
+///
+/// This function will return an error if the history could not be read or
+/// written.
+pub async fn encrypt_history(
+    file_path: &Path,
+    backend: config::HistoryBackend,
+    encryption_key: &config::EncryptionKey,
+) -> Result<usize> {
+    rewrite_history(file_path, backend, encryption_key, Some(encryption_key)).await
+}
+
+/// Decrypt the history with `encryption_key` in place. Returns the number of
+/// clips.
+///
+/// # Errors
+///
+/// This function will return an error if the history could not be read or
+/// written.
+pub async fn decrypt_history(
+    file_path: &Path,
+    backend: config::HistoryBackend,
+    encryption_key: &config::EncryptionKey,
+) -> Result<usize> {
+    rewrite_history(file_path, backend, encryption_key, None).await
+}
+
+async fn rewrite_history(
+    file_path: &Path,
+    backend: config::HistoryBackend,
+    decryption_key: &config::EncryptionKey,
+    encryption_key: Option<&config::EncryptionKey>,
+) -> Result<usize> {
+    let clips = HistoryManager::new(file_path, backend, Some(decryption_key))
+        .await
+        .context(error::CreateHistoryManagerSnafu)?
+        .load()
+        .await
+        .context(error::LoadHistoryManagerSnafu)?;
+
+    HistoryManager::new(file_path, backend, encryption_key)
+        .await
+        .context(error::CreateHistoryManagerSnafu)?
+        .save(&clips)
+        .await
+        .context(error::SaveHistoryManagerSnafu)?;
+
+    Ok(clips.len())
+}
+
 #[allow(clippy::redundant_pub_crate)]
 async fn serve_worker(
     clipboard_watcher: ClipboardWatcher<notification::DesktopNotification>,
     clipboard_manager: Arc<Mutex<ClipboardManager<notification::DesktopNotification>>>,
-    mut history_manager: HistoryManager,
+    history_manager: Arc<Mutex<HistoryManager>>,
     handle: Handle<Error>,
     shutdown_signal: Shutdown,
 ) -> Result<()> {
     let mut shutdown_signal = shutdown_signal.into_stream();
     let mut clip_recv = clipboard_watcher.subscribe();
+    let mut owner_lost_recv = clipboard_watcher.subscribe_owner_lost();
+    // the primary clip added to history by the latest capture, only this clip may
+    // be superseded, an earlier clip with the same content is not part of the
+    // selection
+    let mut added_primary_id = None;
 
     loop {
         let maybe_clip = tokio::select! {
             clip = clip_recv.recv().fuse() => clip,
+            Ok(kind) = owner_lost_recv.recv() => {
+                restore_current_clip(&clipboard_manager, kind).await;
+                continue;
+            }
             _ = shutdown_signal.next() => break,
         };
 
         match maybe_clip {
-            Ok(clip) => {
+            Ok(CapturedClip { clip, supersedes }) => {
                 tracing::debug!(
                     "New clip: {kind} [{basic_info}]",
                     kind = clip.kind(),
                     basic_info = clip.basic_information()
                 );
-                let _unused = clipboard_manager.lock().await.insert(clip.clone());
-                if let Err(err) = history_manager.put(&clip).await {
-                    tracing::error!("{err}");
+                // hold the lock of `ClipboardManager` while writing history, so the history
+                // file will not be modified by other operations in the meantime
+                let mut clipboard_manager = clipboard_manager.lock().await;
+                // the previous primary selection is merged into this one, unless it is pinned
+                if let Some(superseded) = supersedes.filter(|id| added_primary_id == Some(*id)) {
+                    if clipboard_manager.remove(superseded).is_some() {
+                        tracing::debug!("Clip (id: {superseded}) is superseded by the new clip");
+                        if let Err(err) = history_manager.lock().await.remove(&[superseded]).await {
+                            tracing::error!("{err}");
+                        }
+                    }
+                }
+                let (kind, is_new) = (clip.kind(), clipboard_manager.get(clip.id()).is_none());
+                let id = clipboard_manager.insert(clip);
+                if kind == ClipboardKind::Primary {
+                    added_primary_id = is_new.then_some(id);
+                }
+                // store the clip held by `ClipboardManager`, its pinned flag and
+                // time-to-live are preserved
+                if let Some(clip) = clipboard_manager.get(id) {
+                    if let Err(err) = history_manager.lock().await.put(&clip).await {
+                        tracing::error!("{err}");
+                    }
                 }
+                drop(clipboard_manager);
             }
             Err(RecvError::Closed) => {
                 tracing::info!("ClipboardWatcher is closing, no further clip will be received");
@@ -321,6 +832,7 @@ async fn serve_worker(
     };
 
     {
+        let mut history_manager = history_manager.lock().await;
         tracing::info!("Save history and shrink to capacity {history_capacity}");
         if let Err(err) = history_manager.save_and_shrink_to(&clips, history_capacity).await {
             tracing::warn!("Failed to save history, error: {err}");
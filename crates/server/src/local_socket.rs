@@ -0,0 +1,174 @@
+use std::{
+    io,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+};
+
+use futures::{future, Stream, StreamExt};
+use snafu::ResultExt;
+use tokio::net::{UnixListener, UnixStream};
+use tokio_stream::wrappers::UnixListenerStream;
+
+use crate::{config::LocalSocketAccess, error, Result};
+
+const SOCKET_MODE: u32 = 0o600;
+const DIRECTORY_MODE: u32 = 0o700;
+
+/// Connections which are accepted from a Unix domain socket, connections from
+/// peers which are not allowed are dropped.
+pub type Incoming = Pin<Box<dyn Stream<Item = io::Result<UnixStream>> + Send>>;
+
+/// Binds a Unix domain socket which is only accessible to the owner of the
+/// daemon and the users or groups allowed by `access`.
+pub async fn bind(local_socket: &Path, access: LocalSocketAccess) -> Result<Incoming> {
+    let context = || error::CreateUnixListenerSnafu { socket_path: local_socket.to_path_buf() };
+    let parent = local_socket.parent().filter(|parent| !parent.as_os_str().is_empty());
+    if let Some(parent) = parent {
+        tokio::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(DIRECTORY_MODE)
+            .create(parent)
+            .await
+            .with_context(|_| context())?;
+    }
+
+    let listener = bind_privately(local_socket).await.with_context(|_| context())?;
+    // the socket is created by the daemon, so it is owned by the daemon
+    let owner = tokio::fs::metadata(local_socket).await.with_context(|_| context())?.uid();
+
+    if let Some(parent) = parent {
+        restrict_directory(parent, owner).await.with_context(|_| context())?;
+    }
+
+    let local_socket = Arc::new(local_socket.to_path_buf());
+    let incoming = UnixListenerStream::new(listener).filter(move |stream| {
+        // errors of accepting are passed through and handled by the server
+        let allowed = stream
+            .as_ref()
+            .map_or(true, |stream| is_peer_allowed(stream, owner, &access, &local_socket));
+        future::ready(allowed)
+    });
+    Ok(Box::pin(incoming))
+}
+
+// the socket is bound in a private directory and linked into place after its
+// permissions are restricted, so that it is never accessible to others, unlike
+// renaming, linking fails if the socket exists
+async fn bind_privately(local_socket: &Path) -> io::Result<UnixListener> {
+    let file_name = local_socket.file_name().unwrap_or_default().to_string_lossy();
+    let staging_directory =
+        local_socket.with_file_name(format!(".{file_name}.{pid}", pid = std::process::id()));
+    tokio::fs::DirBuilder::new().mode(DIRECTORY_MODE).create(&staging_directory).await?;
+
+    let staging_socket = staging_directory.join("s");
+    let result = async {
+        let listener = UnixListener::bind(&staging_socket)?;
+        tokio::fs::set_permissions(&staging_socket, std::fs::Permissions::from_mode(SOCKET_MODE))
+            .await?;
+        tokio::fs::hard_link(&staging_socket, local_socket).await?;
+        Ok(listener)
+    }
+    .await;
+    drop(tokio::fs::remove_dir_all(&staging_directory).await);
+    result
+}
+
+// the directory may be shared with others, e.g. `/tmp`, it is left untouched
+// if it is not owned by the daemon
+async fn restrict_directory(directory: &Path, owner: u32) -> io::Result<()> {
+    let metadata = tokio::fs::metadata(directory).await?;
+    if metadata.mode() & 0o777 == DIRECTORY_MODE {
+        return Ok(());
+    }
+    if metadata.uid() == owner {
+        tokio::fs::set_permissions(directory, std::fs::Permissions::from_mode(DIRECTORY_MODE)).await
+    } else {
+        tracing::warn!(
+            "Directory `{path}` of Unix domain socket is accessible to other users",
+            path = directory.display()
+        );
+        Ok(())
+    }
+}
+
+fn is_peer_allowed(
+    stream: &UnixStream,
+    owner: u32,
+    access: &LocalSocketAccess,
+    local_socket: &Path,
+) -> bool {
+    match stream.peer_cred() {
+        Ok(cred) if access.is_allowed(owner, cred.uid(), cred.gid()) => true,
+        Ok(cred) => {
+            tracing::warn!(
+                "Reject connection on `{path}` from process {pid} of user {uid} and group {gid}, \
+                 they are not allowed to access Clipcat",
+                path = local_socket.display(),
+                pid = cred.pid().map_or_else(|| "<unknown>".to_string(), |pid| pid.to_string()),
+                uid = cred.uid(),
+                gid = cred.gid(),
+            );
+            false
+        }
+        Err(err) => {
+            tracing::warn!(
+                "Reject connection on `{path}`, could not get credentials of peer, error: {err}",
+                path = local_socket.display()
+            );
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use futures::StreamExt;
+    use tokio::net::UnixStream;
+
+    use crate::{config::LocalSocketAccess, local_socket};
+
+    #[tokio::test]
+    async fn test_bind() {
+        let dir = std::env::temp_dir()
+            .join(format!("clipcat-local-socket-test-{pid}", pid = std::process::id()));
+        let local_socket = dir.join("run").join("test.sock");
+
+        let mut incoming =
+            local_socket::bind(&local_socket, LocalSocketAccess::default()).await.unwrap();
+        let mode = |path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&local_socket), 0o600);
+        assert_eq!(mode(&dir.join("run")), 0o700);
+
+        // the owner of daemon is allowed
+        let _stream = UnixStream::connect(&local_socket).await.unwrap();
+        assert!(incoming.next().await.unwrap().is_ok());
+
+        // the socket is in use
+        assert!(local_socket::bind(&local_socket, LocalSocketAccess::default()).await.is_err());
+        assert_eq!(std::fs::read_dir(dir.join("run")).unwrap().count(), 1);
+
+        drop(std::fs::remove_dir_all(dir));
+    }
+
+    #[test]
+    fn test_is_allowed() {
+        let access = LocalSocketAccess { allowed_uids: vec![1001], allowed_gids: vec![100] };
+        let owner = 1000;
+        // UID and GID of peer, and whether it is allowed
+        let peers = [
+            (1000, 1000, true),
+            (1001, 1001, true),
+            (1002, 100, true),
+            (1002, 1002, false),
+            (0, 0, false),
+        ];
+        for (uid, gid, allowed) in peers {
+            assert_eq!(access.is_allowed(owner, uid, gid), allowed, "UID: {uid}, GID: {gid}");
+        }
+        assert!(!LocalSocketAccess::default().is_allowed(owner, 1001, 100));
+    }
+}
@@ -7,4 +7,10 @@ use crate::backend;
 pub enum Error {
     #[snafu(display("Error occurs while storing clipboard content, error: {source}"))]
     StoreClipboardContent { source: backend::Error },
+
+    #[snafu(display("Could not build search pattern, error: {source}"))]
+    BuildSearchPattern { source: regex::Error },
+
+    #[snafu(display("Clip {id:016x} is not found"))]
+    ClipNotFound { id: u64 },
 }
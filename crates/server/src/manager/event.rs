@@ -0,0 +1,29 @@
+use clipcat_base::{ClipEntry, ClipEvent};
+
+#[derive(Clone, Debug)]
+pub enum Event {
+    Inserted(ClipEntry),
+
+    Removed { id: u64 },
+
+    Marked(ClipEntry),
+
+    Pinned(ClipEntry),
+
+    Unpinned(ClipEntry),
+
+    Cleared,
+}
+
+impl Event {
+    pub fn to_clip_event(&self, preview_length: usize) -> ClipEvent {
+        match self {
+            Self::Inserted(clip) => ClipEvent::Inserted(clip.metadata(Some(preview_length))),
+            Self::Removed { id } => ClipEvent::Removed { id: *id },
+            Self::Marked(clip) => ClipEvent::Marked(clip.metadata(Some(preview_length))),
+            Self::Pinned(clip) => ClipEvent::Pinned(clip.metadata(Some(preview_length))),
+            Self::Unpinned(clip) => ClipEvent::Unpinned(clip.metadata(Some(preview_length))),
+            Self::Cleared => ClipEvent::Cleared,
+        }
+    }
+}
@@ -1,23 +1,34 @@
 mod error;
+mod event;
 
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     sync::Arc,
+    time::Duration,
 };
 
-use clipcat_base::{ClipEntry, ClipEntryMetadata, ClipboardContent, ClipboardKind};
-use snafu::ResultExt;
+use clipcat_base::{
+    ClipEntry, ClipEntryMetadata, ClipboardContent, ClipboardKind, SearchMode, SearchQuery,
+};
+use snafu::{OptionExt, ResultExt};
 use time::OffsetDateTime;
+use tokio::sync::broadcast;
 
-pub use self::error::Error;
-use crate::{backend::ClipboardBackend, notification};
+pub use self::{error::Error, event::Event};
+use crate::{backend::ClipboardBackend, config::HistoryCapacity, notification};
 
 const DEFAULT_CAPACITY: usize = 40;
 
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+
 pub struct ClipboardManager<Notification> {
     backend: Arc<dyn ClipboardBackend>,
 
-    capacity: usize,
+    // capacity of each clipboard kind, pinned clips are not counted
+    capacity: HistoryCapacity,
+
+    // clips older than this are removed, pinned clips are not affected
+    max_age: Option<time::Duration>,
 
     // use id of ClipEntry as the key
     clips: HashMap<u64, ClipEntry>,
@@ -25,11 +36,22 @@ pub struct ClipboardManager<Notification> {
     // store current clip for each clipboard kind
     current_clips: [Option<u64>; ClipboardKind::MAX_LENGTH],
 
-    // use BTreeMap to store timestamps for remove the oldest clip
-    timestamp_to_id: BTreeMap<OffsetDateTime, u64>,
+    // clips ordered by timestamp for removing the oldest clip, the id breaks
+    // ties of clips captured at the same moment, it is only updated along
+    // with `clips` by `put_clip` and `take_clip`
+    timestamp_to_id: BTreeSet<(OffsetDateTime, u64)>,
 
     snippet_ids: HashSet<u64>,
 
+    // clips pinned at runtime, they are retained like snippets but stored in history
+    pinned_ids: HashSet<u64>,
+
+    // identifiers computed by releases before identifiers became stable, they
+    // are mapped to the current identifiers so they remain resolvable
+    id_aliases: HashMap<u64, u64>,
+
+    event_sender: broadcast::Sender<Event>,
+
     notification: Notification,
 }
 
@@ -37,19 +59,33 @@ impl<Notification> ClipboardManager<Notification>
 where
     Notification: notification::Notification,
 {
-    pub fn with_capacity(
+    pub fn with_capacity<C>(
         backend: Arc<dyn ClipboardBackend>,
-        capacity: usize,
+        capacity: C,
         notification: Notification,
-    ) -> Self {
-        let capacity = if capacity == 0 { DEFAULT_CAPACITY } else { capacity };
+    ) -> Self
+    where
+        C: Into<HistoryCapacity>,
+    {
+        let or_default = |capacity| if capacity == 0 { DEFAULT_CAPACITY } else { capacity };
+        let HistoryCapacity { clipboard, primary, secondary } = capacity.into();
+        let capacity = HistoryCapacity {
+            clipboard: or_default(clipboard),
+            primary: or_default(primary),
+            secondary: or_default(secondary),
+        };
+        let (event_sender, _event_receiver) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
         Self {
             backend,
             capacity,
+            max_age: None,
             clips: HashMap::new(),
             current_clips: [None; ClipboardKind::MAX_LENGTH],
-            timestamp_to_id: BTreeMap::new(),
+            timestamp_to_id: BTreeSet::new(),
             snippet_ids: HashSet::new(),
+            pinned_ids: HashSet::new(),
+            id_aliases: HashMap::new(),
+            event_sender,
             notification,
         }
     }
@@ -61,7 +97,31 @@ where
     }
 
     #[inline]
-    pub const fn capacity(&self) -> usize { self.capacity }
+    pub const fn capacity(&self) -> HistoryCapacity { self.capacity }
+
+    #[inline]
+    pub fn set_max_age(&mut self, max_age: Option<Duration>) {
+        self.max_age =
+            max_age.map(|max_age| time::Duration::try_from(max_age).unwrap_or(time::Duration::MAX));
+    }
+
+    #[inline]
+    pub fn set_id_aliases(&mut self, id_aliases: HashMap<u64, u64>) {
+        self.id_aliases = id_aliases;
+    }
+
+    /// Returns the current identifier of clip if `id` is a legacy identifier.
+    #[inline]
+    fn resolve_id(&self, id: u64) -> u64 {
+        if self.clips.contains_key(&id) {
+            id
+        } else {
+            self.id_aliases.get(&id).copied().unwrap_or(id)
+        }
+    }
+
+    #[inline]
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> { self.event_sender.subscribe() }
 
     #[inline]
     pub fn import(&mut self, clips: &[ClipEntry]) { self.import_iter(clips.iter()); }
@@ -70,10 +130,12 @@ where
     pub fn import_iter<'a>(&'a mut self, clips_iter: impl Iterator<Item = &'a ClipEntry>) {
         self.clips.clear();
         self.timestamp_to_id.clear();
+        self.pinned_ids.clear();
         for clip in clips_iter {
-            let (id, timestamp) = (clip.id(), clip.timestamp());
-            let _ = self.timestamp_to_id.insert(timestamp, id);
-            drop(self.clips.insert(id, clip.clone()));
+            if clip.is_pinned() {
+                let _unused = self.pinned_ids.insert(clip.id());
+            }
+            drop(self.put_clip(clip.clone()));
         }
 
         self.remove_oldest();
@@ -81,9 +143,11 @@ where
 
     pub fn insert_snippets(&mut self, snippets: &[ClipEntry]) {
         for clip in snippets {
-            let (id, timestamp) = (clip.id(), clip.timestamp());
-            let _ = self.timestamp_to_id.insert(timestamp, id);
-            drop(self.clips.insert(id, clip.clone()));
+            let id = clip.id();
+            let mut clip = clip.clone();
+            clip.set_pinned(true);
+            drop(self.put_clip(clip));
+            let _unused = self.pinned_ids.remove(&id);
             let _unused = self.snippet_ids.insert(id);
         }
 
@@ -101,16 +165,57 @@ where
             .collect()
     }
 
+    /// Lists clips of the specified kinds, clips of all kinds are listed if
+    /// `kinds` is empty.
     #[inline]
-    pub fn list(&self, preview_length: usize) -> Vec<ClipEntryMetadata> {
-        self.iter().map(|entry| entry.metadata(Some(preview_length))).collect()
+    pub fn list(&self, preview_length: usize, kinds: &[ClipboardKind]) -> Vec<ClipEntryMetadata> {
+        self.iter()
+            .filter(|entry| kinds.is_empty() || kinds.contains(&entry.kind()))
+            .map(|entry| entry.metadata(Some(preview_length)))
+            .collect()
+    }
+
+    pub fn search(&self, query: &SearchQuery) -> Result<(Vec<ClipEntryMetadata>, usize), Error> {
+        let pattern = match query.mode {
+            SearchMode::Substring => regex::escape(&query.pattern),
+            SearchMode::Regex => query.pattern.clone(),
+        };
+        let regex = regex::RegexBuilder::new(&pattern)
+            .case_insensitive(!query.case_sensitive)
+            .build()
+            .context(error::BuildSearchPatternSnafu)?;
+
+        let mut clips = self
+            .iter()
+            .filter(|clip| query.filter_attributes(clip))
+            .filter(|clip| {
+                if clip.is_utf8_string() {
+                    regex.is_match(&clip.as_utf8_string())
+                } else {
+                    // only the attributes of a non-text clip could be matched
+                    query.pattern.is_empty()
+                }
+            })
+            .collect::<Vec<_>>();
+        clips.sort_unstable();
+
+        let total = clips.len();
+        let metadata = clips
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .map(|clip| clip.metadata(Some(query.preview_length)))
+            .collect();
+        Ok((metadata, total))
     }
 
     #[inline]
     pub fn iter(&self) -> impl Iterator<Item = &ClipEntry> { self.clips.values() }
 
     #[inline]
-    pub fn get(&self, id: u64) -> Option<ClipEntry> { self.clips.get(&id).map(Clone::clone) }
+    pub fn get(&self, id: u64) -> Option<ClipEntry> {
+        self.clips.get(&self.resolve_id(id)).map(Clone::clone)
+    }
 
     #[inline]
     pub fn get_current_clip(&self, kind: ClipboardKind) -> Option<&ClipEntry> {
@@ -120,24 +225,30 @@ where
     #[inline]
     pub fn insert(&mut self, data: ClipEntry) -> u64 { self.insert_inner(data) }
 
-    fn insert_inner(&mut self, entry: ClipEntry) -> u64 {
+    fn insert_inner(&mut self, mut entry: ClipEntry) -> u64 {
         // get image information for later use
         let image_info = match entry.as_ref() {
-            ClipboardContent::Image { width, height, bytes } => {
-                Some((bytes.len(), *width, *height))
+            content @ ClipboardContent::Image { bytes, .. } => {
+                Some((bytes.len(), content.image_dimensions()))
             }
-            ClipboardContent::Plaintext(_) => None,
+            ClipboardContent::Plaintext(_) | ClipboardContent::Rich(_) => None,
         };
 
-        let (id, timestamp) = (entry.id(), entry.timestamp());
+        let id = entry.id();
+        entry.set_pinned(self.is_pinned(id));
+        if entry.expires_at().is_none() {
+            // keep the time-to-live when the same content is captured again
+            entry.set_expires_at(self.clips.get(&id).and_then(ClipEntry::expires_at));
+        }
         self.current_clips[usize::from(entry.kind())] = Some(id);
-        drop(self.clips.insert(id, entry));
-        let _unused = self.timestamp_to_id.insert(timestamp, id);
+        self.emit(Event::Inserted(entry.clone()));
+        // a clip captured again is moved to the position of its new timestamp
+        drop(self.put_clip(entry));
         self.remove_oldest();
 
         // emit notification before returning
-        if let Some((size, width, height)) = image_info {
-            self.notification.on_image_fetched(size, width, height);
+        if let Some((size, dimensions)) = image_info {
+            self.notification.on_image_fetched(size, dimensions);
         }
 
         id
@@ -154,30 +265,61 @@ where
             return;
         }
 
-        let snippet_count = self.snippet_ids.len();
-        let now = OffsetDateTime::now_utc();
+        // every kind of clipboard has its own capacity, pinned clips are not counted
+        let mut excess = [0_usize; ClipboardKind::MAX_LENGTH];
+        for clip in self.clips.values().filter(|clip| !self.is_pinned(clip.id())) {
+            excess[usize::from(clip.kind())] += 1;
+        }
+        for kind in ClipboardKind::all_kinds() {
+            let excess = &mut excess[usize::from(kind)];
+            *excess = excess.saturating_sub(self.capacity.get(kind));
+        }
+        if excess.iter().all(|&excess| excess == 0) {
+            return;
+        }
 
-        while self.clips.len() > self.capacity + snippet_count {
-            if let Some((timestamp, id)) = self.timestamp_to_id.pop_first() {
-                if self.snippet_ids.contains(&id) {
-                    tracing::trace!("Retain snippet clip and update its timestamp (id: {id})");
-                    let _ = self.timestamp_to_id.insert(now, id);
-                    let _ = self.clips.get_mut(&id).map(|entry| entry.set_timestamp(now));
-                } else {
-                    tracing::trace!("Remove old clip (id: {id}, timestamp: {timestamp})");
-                    drop(self.clips.remove(&id));
+        let now = OffsetDateTime::now_utc();
+        // walk through a snapshot, retained clips are moved to the front
+        let ordered = self.timestamp_to_id.iter().copied().collect::<Vec<_>>();
+        for (timestamp, id) in ordered {
+            if excess.iter().all(|&excess| excess == 0) {
+                break;
+            }
+            let Some(kind) = self.clips.get(&id).map(ClipEntry::kind) else {
+                continue;
+            };
+            if excess[usize::from(kind)] == 0 {
+                continue;
+            }
+            if self.is_pinned(id) {
+                tracing::trace!("Retain pinned clip and update its timestamp (id: {id})");
+                if let Some(mut clip) = self.take_clip(id) {
+                    clip.set_timestamp(now);
+                    drop(self.put_clip(clip));
+                }
+            } else {
+                tracing::trace!("Remove old clip (id: {id}, timestamp: {timestamp})");
+                if self.take_clip(id).is_some() {
+                    self.emit(Event::Removed { id });
+                    excess[usize::from(kind)] -= 1;
                 }
             }
         }
     }
 
+    /// Removes a clip, returns the current identifier of the removed clip,
+    /// which differs from `id` if `id` is a legacy identifier.
     #[inline]
-    pub fn remove(&mut self, id: u64) -> bool { self.remove_inner(id).is_some() }
+    pub fn remove(&mut self, id: u64) -> Option<u64> {
+        let id = self.resolve_id(id);
+        // pinned clips are never removed
+        (!self.is_pinned(id) && self.remove_inner(id).is_some()).then_some(id)
+    }
 
     #[inline]
     fn remove_inner(&mut self, id: u64) -> Option<ClipEntry> {
-        if let Some(id) = self.snippet_ids.get(&id) {
-            return self.clips.get(id).cloned();
+        if self.is_pinned(id) {
+            return self.clips.get(&id).cloned();
         }
 
         for kind in ClipboardKind::all_kinds().map(usize::from) {
@@ -186,56 +328,178 @@ where
             }
         }
 
-        if let Some(clip) = self.clips.remove(&id) {
-            let _id = self.timestamp_to_id.remove(&clip.timestamp());
-            Some(clip)
-        } else {
-            None
+        let clip = self.take_clip(id)?;
+        self.emit(Event::Removed { id });
+        Some(clip)
+    }
+
+    /// Inserts a clip, or replaces the clip with the same id, and keeps
+    /// `timestamp_to_id` in sync. Returns the replaced clip.
+    fn put_clip(&mut self, clip: ClipEntry) -> Option<ClipEntry> {
+        let (id, timestamp) = (clip.id(), clip.timestamp());
+        let previous = self.clips.insert(id, clip);
+        if let Some(previous) = &previous {
+            let _unused = self.timestamp_to_id.remove(&(previous.timestamp(), id));
         }
+        let _unused = self.timestamp_to_id.insert((timestamp, id));
+        previous
+    }
+
+    /// Removes a clip and its timestamp from `timestamp_to_id`.
+    fn take_clip(&mut self, id: u64) -> Option<ClipEntry> {
+        let clip = self.clips.remove(&id)?;
+        let _unused = self.timestamp_to_id.remove(&(clip.timestamp(), id));
+        Some(clip)
     }
 
     #[inline]
     pub fn clear(&mut self) {
-        self.timestamp_to_id.retain(|_, id| self.snippet_ids.contains(id));
+        self.timestamp_to_id
+            .retain(|(_, id)| self.snippet_ids.contains(id) || self.pinned_ids.contains(id));
         self.current_clips = [None; ClipboardKind::MAX_LENGTH];
-        self.clips.retain(|id, _| self.snippet_ids.contains(id));
+        self.clips.retain(|id, _| self.snippet_ids.contains(id) || self.pinned_ids.contains(id));
+        self.emit(Event::Cleared);
         self.notification.on_history_cleared();
     }
 
-    pub fn replace(&mut self, old_id: u64, data: &[u8], mime: &mime::Mime) -> (bool, u64) {
-        let kind = self.remove_inner(old_id).map_or(ClipboardKind::Primary, |clip| clip.kind());
-        ClipEntry::new(data, mime, kind, None).map_or((false, old_id), |entry| {
-            let new_id = entry.id();
-            let _ = self.insert_inner(entry);
-            (true, new_id)
-        })
+    /// Replaces the content of clip, returns the current identifier of the
+    /// replaced clip and the identifier of the new one. The pin of a pinned
+    /// clip is transferred to the new clip, snippets are kept as they are.
+    pub fn replace(&mut self, old_id: u64, data: &[u8], mime: &mime::Mime) -> Option<(u64, u64)> {
+        let old_id = self.resolve_id(old_id);
+        let kind = self.clips.get(&old_id).map_or(ClipboardKind::Primary, ClipEntry::kind);
+        let entry = ClipEntry::new(data, mime, kind, None).ok()?;
+        let new_id = entry.id();
+        if self.pinned_ids.remove(&old_id) {
+            let _unused = self.pinned_ids.insert(new_id);
+        }
+        let _unused = self.remove_inner(old_id);
+        let _unused = self.insert_inner(entry);
+        Some((old_id, new_id))
     }
 
-    pub async fn mark(&mut self, id: u64, clipboard_kind: ClipboardKind) -> Result<(), Error> {
+    /// Pins a clip, returns the current identifier of the pinned clip.
+    pub fn pin(&mut self, id: u64) -> Option<u64> {
+        let id = self.resolve_id(id);
+        let clip = self.clips.get_mut(&id)?;
+
+        if !self.snippet_ids.contains(&id) && self.pinned_ids.insert(id) {
+            clip.set_pinned(true);
+            let clip = clip.clone();
+            self.emit(Event::Pinned(clip));
+        }
+        Some(id)
+    }
+
+    /// Unpins a clip, returns the current identifier of the unpinned clip.
+    pub fn unpin(&mut self, id: u64) -> Option<u64> {
+        let id = self.resolve_id(id);
+        if !self.pinned_ids.remove(&id) {
+            return None;
+        }
+
         if let Some(clip) = self.clips.get_mut(&id) {
-            clip.mark(clipboard_kind);
-            self.backend
-                .store(clipboard_kind, clip.to_clipboard_content())
-                .await
-                .context(error::StoreClipboardContentSnafu)?;
+            clip.set_pinned(false);
+            let clip = clip.clone();
+            self.emit(Event::Unpinned(clip));
         }
+        self.remove_oldest();
+        Some(id)
+    }
+
+    pub async fn mark(&mut self, id: u64, clipboard_kind: ClipboardKind) -> Result<(), Error> {
+        let id = self.resolve_id(id);
+        let mut clip = self.take_clip(id).context(error::ClipNotFoundSnafu { id })?;
+        // marking a clip updates its timestamp, move it to the front
+        clip.mark(clipboard_kind);
+        drop(self.put_clip(clip.clone()));
+        // the clip may be counted in the capacity of another kind now
+        self.remove_oldest();
+        self.backend
+            .store(clipboard_kind, clip.to_clipboard_content())
+            .await
+            .context(error::StoreClipboardContentSnafu)?;
+        self.emit(Event::Marked(clip));
 
         Ok(())
     }
 
+    /// Store the current clip of `clipboard_kind` into the clipboard again,
+    /// used when the application owning the clipboard exits. Returns `false`
+    /// if there is no current clip.
+    pub async fn restore_current_clip(
+        &mut self,
+        clipboard_kind: ClipboardKind,
+    ) -> Result<bool, Error> {
+        let Some(clip) = self.get_current_clip(clipboard_kind) else {
+            return Ok(false);
+        };
+        self.backend
+            .store(clipboard_kind, clip.to_clipboard_content())
+            .await
+            .context(error::StoreClipboardContentSnafu)?;
+        Ok(true)
+    }
+
+    /// Remove clips which are expired or older than `max_age`, the clipboard is
+    /// cleared if its current clip is removed. Returns the IDs of removed
+    /// clips.
+    pub async fn remove_expired(&mut self, now: OffsetDateTime) -> Vec<u64> {
+        let deadline = self.max_age.and_then(|max_age| now.checked_sub(max_age));
+        let expired_ids = self
+            .clips
+            .values()
+            .filter(|clip| !self.is_pinned(clip.id()))
+            .filter(|clip| {
+                clip.is_expired(now) || deadline.is_some_and(|deadline| clip.timestamp() < deadline)
+            })
+            .map(ClipEntry::id)
+            .collect::<Vec<_>>();
+
+        for &id in &expired_ids {
+            let current_kinds = ClipboardKind::all_kinds()
+                .into_iter()
+                .filter(|&kind| self.current_clips[usize::from(kind)] == Some(id))
+                .collect::<Vec<_>>();
+            tracing::debug!("Remove expired clip (id: {id:016x})");
+            let _unused = self.remove_inner(id);
+            for kind in current_kinds {
+                if let Err(err) = self.backend.clear(kind).await {
+                    tracing::warn!("Failed to clear {kind}, error: {err}");
+                }
+            }
+        }
+
+        expired_ids
+    }
+
     #[inline]
-    fn is_snippet(&self, id: u64) -> bool { self.snippet_ids.contains(&id) }
+    pub fn is_snippet(&self, id: u64) -> bool { self.snippet_ids.contains(&id) }
+
+    #[inline]
+    fn is_pinned(&self, id: u64) -> bool {
+        self.snippet_ids.contains(&id) || self.pinned_ids.contains(&id)
+    }
+
+    #[inline]
+    fn emit(&self, event: Event) {
+        // it is fine that there is no subscriber
+        let _unused = self.event_sender.send(event);
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{collections::HashSet, sync::Arc};
+    use std::{collections::HashSet, sync::Arc, time::Duration};
 
-    use clipcat_base::{ClipEntry, ClipboardKind};
+    use clipcat_base::{ClipEntry, ClipboardKind, SearchMode, SearchQuery};
+    use proptest::prelude::*;
+    use time::OffsetDateTime;
 
     use crate::{
-        backend::MockClipboardBackend,
-        manager::{ClipboardManager, DEFAULT_CAPACITY},
+        backend::{ClipboardBackend as _, MockClipboardBackend},
+        config::HistoryCapacity,
+        manager::{ClipboardManager, Error, Event, DEFAULT_CAPACITY},
         notification::MockNotification,
     };
 
@@ -250,7 +514,7 @@ mod tests {
         let mgr = ClipboardManager::new(backend, notification);
         assert!(mgr.is_empty());
         assert_eq!(mgr.len(), 0);
-        assert_eq!(mgr.capacity(), DEFAULT_CAPACITY);
+        assert_eq!(mgr.capacity(), HistoryCapacity::from(DEFAULT_CAPACITY));
         assert!(mgr.get_current_clip(ClipboardKind::Clipboard).is_none());
         assert!(mgr.get_current_clip(ClipboardKind::Primary).is_none());
 
@@ -259,7 +523,7 @@ mod tests {
         let mgr = ClipboardManager::with_capacity(backend, cap, notification);
         assert!(mgr.is_empty());
         assert_eq!(mgr.len(), 0);
-        assert_eq!(mgr.capacity(), cap);
+        assert_eq!(mgr.capacity(), HistoryCapacity::from(cap));
         assert!(mgr.get_current_clip(ClipboardKind::Clipboard).is_none());
         assert!(mgr.get_current_clip(ClipboardKind::Primary).is_none());
     }
@@ -271,7 +535,7 @@ mod tests {
         let cap = 10;
         let mut mgr = ClipboardManager::with_capacity(backend, cap, notification);
         assert_eq!(mgr.len(), 0);
-        assert_eq!(mgr.capacity(), cap);
+        assert_eq!(mgr.capacity(), HistoryCapacity::from(cap));
 
         let n = 20;
         let clips = create_clips(n);
@@ -280,18 +544,18 @@ mod tests {
         }
 
         assert_eq!(mgr.len(), cap);
-        assert_eq!(mgr.capacity(), cap);
+        assert_eq!(mgr.capacity(), HistoryCapacity::from(cap));
 
         let n = 25;
         let clips = create_clips(n);
         mgr.import(&clips);
 
         assert_eq!(mgr.len(), cap);
-        assert_eq!(mgr.capacity(), cap);
+        assert_eq!(mgr.capacity(), HistoryCapacity::from(cap));
 
         let mut exported = mgr.export(false);
         exported.sort_unstable();
-        let mut clips = clips[(n - mgr.capacity())..].to_vec();
+        let mut clips = clips[(n - cap)..].to_vec();
         clips.sort_unstable();
         assert_eq!(exported, clips);
     }
@@ -353,8 +617,8 @@ mod tests {
         let old_id = mgr.insert(clip);
         assert_eq!(mgr.len(), 1);
 
-        let (ok, new_id) = mgr.replace(old_id, data2.as_bytes(), &MIME);
-        assert!(ok);
+        let (replaced_id, new_id) = mgr.replace(old_id, data2.as_bytes(), &MIME).unwrap();
+        assert_eq!(replaced_id, old_id);
         assert_ne!(old_id, new_id);
         assert_eq!(mgr.len(), 1);
 
@@ -363,13 +627,29 @@ mod tests {
         assert_eq!(clip.kind(), ClipboardKind::Clipboard);
     }
 
+    #[test]
+    fn test_replace_pinned() {
+        let backend = Arc::new(MockClipboardBackend::new());
+        let notification = MockNotification::default();
+        let mut mgr = ClipboardManager::new(backend, notification);
+        let old_id = mgr.insert(ClipEntry::from_string("old", ClipboardKind::Clipboard));
+        assert_eq!(mgr.pin(old_id), Some(old_id));
+
+        let (_, new_id) = mgr.replace(old_id, b"new", &mime::TEXT_PLAIN_UTF_8).unwrap();
+        assert_eq!(mgr.len(), 1);
+        assert!(mgr.get(old_id).is_none());
+        assert!(mgr.get(new_id).unwrap().is_pinned());
+        assert_eq!(mgr.remove(new_id), None);
+        assert_eq!(mgr.unpin(new_id), Some(new_id));
+    }
+
     #[test]
     fn test_remove() {
         let backend = Arc::new(MockClipboardBackend::new());
         let notification = MockNotification::default();
         let mut mgr = ClipboardManager::new(backend, notification);
         assert_eq!(mgr.len(), 0);
-        assert!(!mgr.remove(43));
+        assert_eq!(mgr.remove(43), None);
 
         let clip = ClipEntry::from_string("АБВГДЕ", ClipboardKind::Primary);
         let id = mgr.insert(clip);
@@ -377,14 +657,12 @@ mod tests {
         assert!(mgr.get_current_clip(ClipboardKind::Clipboard).is_none());
         assert!(mgr.get_current_clip(ClipboardKind::Primary).is_some());
 
-        let ok = mgr.remove(id);
-        assert!(ok);
+        assert_eq!(mgr.remove(id), Some(id));
         assert_eq!(mgr.len(), 0);
         assert!(mgr.get_current_clip(ClipboardKind::Clipboard).is_none());
         assert!(mgr.get_current_clip(ClipboardKind::Primary).is_none());
 
-        let ok = mgr.remove(id);
-        assert!(!ok);
+        assert_eq!(mgr.remove(id), None);
     }
 
     #[test]
@@ -403,4 +681,348 @@ mod tests {
         assert!(mgr.is_empty());
         assert_eq!(mgr.len(), 0);
     }
+
+    #[test]
+    fn test_subscribe() {
+        let backend = Arc::new(MockClipboardBackend::new());
+        let notification = MockNotification::default();
+        let mut mgr = ClipboardManager::with_capacity(backend, 1, notification);
+        let mut events = mgr.subscribe();
+
+        let first_id = mgr.insert(ClipEntry::from_string("first", ClipboardKind::Clipboard));
+        assert!(matches!(events.try_recv(), Ok(Event::Inserted(clip)) if clip.id() == first_id));
+
+        let second_id = mgr.insert(ClipEntry::from_string("second", ClipboardKind::Clipboard));
+        assert!(matches!(events.try_recv(), Ok(Event::Inserted(clip)) if clip.id() == second_id));
+        assert!(matches!(events.try_recv(), Ok(Event::Removed { id }) if id == first_id));
+
+        assert_eq!(mgr.remove(second_id), Some(second_id));
+        assert!(matches!(events.try_recv(), Ok(Event::Removed { id }) if id == second_id));
+
+        mgr.clear();
+        assert!(matches!(events.try_recv(), Ok(Event::Cleared)));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        let backend = Arc::new(MockClipboardBackend::new());
+        let notification = MockNotification::default();
+        let mut mgr = ClipboardManager::new(backend, notification);
+        let _id = mgr.insert(ClipEntry::from_string("Hello World", ClipboardKind::Clipboard));
+        let _id = mgr.insert(ClipEntry::from_string("hello clipcat", ClipboardKind::Primary));
+        let _id = mgr.insert(ClipEntry::from_string("goodbye", ClipboardKind::Clipboard));
+
+        let query = SearchQuery {
+            pattern: "hello".to_string(),
+            preview_length: 20,
+            ..SearchQuery::default()
+        };
+        let (metadata, total) = mgr.search(&query).unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(metadata.len(), 2);
+
+        let query = SearchQuery { case_sensitive: true, ..query };
+        let (metadata, total) = mgr.search(&query).unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(metadata[0].preview, "hello clipcat");
+
+        let query = SearchQuery {
+            pattern: "^(hello|goodbye)$|world".to_string(),
+            mode: SearchMode::Regex,
+            kinds: vec![ClipboardKind::Clipboard],
+            ..SearchQuery::default()
+        };
+        let (metadata, total) = mgr.search(&query).unwrap();
+        assert_eq!(total, 2);
+        assert!(metadata.iter().all(|m| m.kind == ClipboardKind::Clipboard));
+
+        let query = SearchQuery { offset: 1, limit: Some(1), ..query };
+        let (metadata, total) = mgr.search(&query).unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(metadata.len(), 1);
+
+        let query = SearchQuery {
+            pattern: "(".to_string(),
+            mode: SearchMode::Regex,
+            ..SearchQuery::default()
+        };
+        assert!(mgr.search(&query).is_err());
+    }
+
+    #[test]
+    fn test_pin() {
+        let backend = Arc::new(MockClipboardBackend::new());
+        let notification = MockNotification::default();
+        let mut mgr = ClipboardManager::with_capacity(backend, 2, notification);
+
+        let pinned_id = mgr.insert(ClipEntry::from_string("pinned", ClipboardKind::Clipboard));
+        assert_eq!(mgr.pin(pinned_id), Some(pinned_id));
+        assert!(mgr.get(pinned_id).unwrap().is_pinned());
+        assert_eq!(mgr.pin(0), None);
+
+        for i in 0..5 {
+            let _id = mgr.insert(ClipEntry::from_string(i, ClipboardKind::Clipboard));
+        }
+        assert_eq!(mgr.len(), 3);
+        assert!(mgr.get(pinned_id).is_some());
+
+        assert_eq!(mgr.remove(pinned_id), None);
+        mgr.clear();
+        assert_eq!(mgr.len(), 1);
+        assert!(mgr.list(10, &[]).iter().all(|metadata| metadata.pinned));
+
+        assert_eq!(mgr.unpin(pinned_id), Some(pinned_id));
+        assert_eq!(mgr.unpin(pinned_id), None);
+        assert!(!mgr.get(pinned_id).unwrap().is_pinned());
+        assert_eq!(mgr.remove(pinned_id), Some(pinned_id));
+        assert!(mgr.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_remove_expired() {
+        let backend = Arc::new(MockClipboardBackend::new());
+        let notification = MockNotification::default();
+        let mut mgr = ClipboardManager::new(backend, notification);
+        mgr.set_max_age(Some(Duration::from_secs(60)));
+
+        let now = OffsetDateTime::now_utc();
+        let old_id = mgr.insert(
+            ClipEntry::new(
+                b"old",
+                &mime::TEXT_PLAIN_UTF_8,
+                ClipboardKind::Primary,
+                Some(now - Duration::from_secs(120)),
+            )
+            .unwrap(),
+        );
+        let mut secret = ClipEntry::from_string("secret", ClipboardKind::Clipboard);
+        secret.set_expires_at(Some(now + Duration::from_secs(30)));
+        let secret_id = mgr.insert(secret);
+        let pinned_id = mgr.insert(
+            ClipEntry::new(
+                b"pinned",
+                &mime::TEXT_PLAIN_UTF_8,
+                ClipboardKind::Primary,
+                Some(now - Duration::from_secs(120)),
+            )
+            .unwrap(),
+        );
+        assert_eq!(mgr.pin(pinned_id), Some(pinned_id));
+        let fresh_id = mgr.insert(ClipEntry::from_string("fresh", ClipboardKind::Primary));
+
+        assert_eq!(mgr.remove_expired(now).await, vec![old_id]);
+        assert_eq!(mgr.len(), 3);
+
+        // capturing the same content again keeps its time-to-live
+        let _id = mgr.insert(ClipEntry::from_string("secret", ClipboardKind::Clipboard));
+        assert!(mgr.remove_expired(now + Duration::from_secs(31)).await.contains(&secret_id));
+        assert!(mgr.get(secret_id).is_none());
+        assert!(mgr.get_current_clip(ClipboardKind::Clipboard).is_none());
+        assert!(mgr.get(pinned_id).is_some());
+        assert!(mgr.get(fresh_id).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_restore_current_clip() {
+        let backend = Arc::new(MockClipboardBackend::new());
+        let notification = MockNotification::default();
+        let mut mgr = ClipboardManager::new(backend.clone(), notification);
+        assert!(!mgr.restore_current_clip(ClipboardKind::Clipboard).await.unwrap());
+
+        let clip = ClipEntry::from_string("owner is gone", ClipboardKind::Clipboard);
+        let _id = mgr.insert(clip.clone());
+        backend.clear(ClipboardKind::Clipboard).await.unwrap();
+        assert!(backend.load(ClipboardKind::Clipboard, None).await.is_err());
+
+        assert!(mgr.restore_current_clip(ClipboardKind::Clipboard).await.unwrap());
+        assert_eq!(
+            backend.load(ClipboardKind::Clipboard, None).await.unwrap(),
+            clip.to_clipboard_content()
+        );
+    }
+
+    #[test]
+    fn test_legacy_id() {
+        let backend = Arc::new(MockClipboardBackend::new());
+        let notification = MockNotification::default();
+        let mut mgr = ClipboardManager::new(backend, notification);
+
+        let clip = ClipEntry::from_string("legacy", ClipboardKind::Clipboard);
+        let legacy_id = ClipEntry::compute_legacy_id(clip.as_ref());
+        assert_ne!(legacy_id, clip.id());
+        let id = mgr.insert(clip);
+        mgr.set_id_aliases([(legacy_id, id)].into());
+
+        assert_eq!(mgr.get(legacy_id).map(|clip| clip.id()), Some(id));
+        assert_eq!(mgr.pin(legacy_id), Some(id));
+        assert_eq!(mgr.unpin(legacy_id), Some(id));
+        assert_eq!(mgr.remove(legacy_id), Some(id));
+        assert!(mgr.get(id).is_none());
+    }
+
+    #[test]
+    fn test_recopy() {
+        let backend = Arc::new(MockClipboardBackend::new());
+        let notification = MockNotification::default();
+        let mut mgr = ClipboardManager::with_capacity(backend, 2, notification);
+
+        let now = OffsetDateTime::now_utc();
+        let clip = |data: &str, seconds: u64| {
+            ClipEntry::new(
+                data.as_bytes(),
+                &mime::TEXT_PLAIN_UTF_8,
+                ClipboardKind::Clipboard,
+                Some(now + Duration::from_secs(seconds)),
+            )
+            .unwrap()
+        };
+        let first_id = mgr.insert(clip("first", 0));
+        let second_id = mgr.insert(clip("second", 1));
+
+        // copying the first clip again moves it to the front, so the second
+        // clip becomes the oldest one
+        assert_eq!(mgr.insert(clip("first", 2)), first_id);
+        assert_eq!(mgr.timestamp_to_id.len(), 2);
+        let third_id = mgr.insert(clip("third", 3));
+        assert!(mgr.get(first_id).is_some());
+        assert!(mgr.get(second_id).is_none());
+        assert!(mgr.get(third_id).is_some());
+        assert_consistent(&mgr);
+    }
+
+    #[derive(Clone, Debug)]
+    enum Operation {
+        // insert one of a few texts so that the same text is copied again
+        Insert { text: u8, kind: ClipboardKind, seconds_ago: Option<u8> },
+        Remove { text: u8 },
+        Mark { text: u8, kind: ClipboardKind },
+        Replace { text: u8, new_text: u8 },
+        Pin { text: u8 },
+        Unpin { text: u8 },
+        Clear,
+    }
+
+    fn clipboard_kind() -> impl Strategy<Value = ClipboardKind> {
+        prop_oneof![
+            Just(ClipboardKind::Clipboard),
+            Just(ClipboardKind::Primary),
+            Just(ClipboardKind::Secondary),
+        ]
+    }
+
+    fn operation() -> impl Strategy<Value = Operation> {
+        let text = 0..8_u8;
+        prop_oneof![
+            6 => (text.clone(), clipboard_kind(), proptest::option::of(0..4_u8)).prop_map(
+                |(text, kind, seconds_ago)| Operation::Insert { text, kind, seconds_ago }
+            ),
+            2 => text.clone().prop_map(|text| Operation::Remove { text }),
+            2 => (text.clone(), clipboard_kind())
+                .prop_map(|(text, kind)| Operation::Mark { text, kind }),
+            2 => (text.clone(), text.clone())
+                .prop_map(|(text, new_text)| Operation::Replace { text, new_text }),
+            1 => text.clone().prop_map(|text| Operation::Pin { text }),
+            1 => text.prop_map(|text| Operation::Unpin { text }),
+            1 => Just(Operation::Clear),
+        ]
+    }
+
+    fn text_id(text: u8) -> u64 {
+        ClipEntry::from_string(format!("text {text}"), ClipboardKind::Clipboard).id()
+    }
+
+    fn assert_consistent(mgr: &ClipboardManager<MockNotification>) {
+        assert_eq!(mgr.timestamp_to_id.len(), mgr.clips.len());
+        for (timestamp, id) in &mgr.timestamp_to_id {
+            assert_eq!(mgr.clips.get(id).map(ClipEntry::timestamp), Some(*timestamp));
+        }
+        for kind in ClipboardKind::all_kinds() {
+            let unpinned = mgr
+                .clips
+                .values()
+                .filter(|clip| clip.kind() == kind && !mgr.is_pinned(clip.id()))
+                .count();
+            assert!(unpinned <= mgr.capacity().get(kind));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_ordering_index(operations in proptest::collection::vec(operation(), 1..64)) {
+            let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+            let backend = Arc::new(MockClipboardBackend::new());
+            let notification = MockNotification::default();
+            let mut mgr = ClipboardManager::with_capacity(backend, 4, notification);
+
+            for operation in operations {
+                match operation {
+                    Operation::Insert { text, kind, seconds_ago } => {
+                        let timestamp = seconds_ago.map(|seconds| {
+                            OffsetDateTime::now_utc() - Duration::from_secs(u64::from(seconds))
+                        });
+                        let clip = ClipEntry::new(
+                            format!("text {text}").as_bytes(),
+                            &mime::TEXT_PLAIN_UTF_8,
+                            kind,
+                            timestamp,
+                        )
+                        .unwrap();
+                        let id = mgr.insert(clip);
+                        if seconds_ago.is_none() {
+                            // the latest clip is never removed right away
+                            prop_assert!(mgr.get(id).is_some());
+                        }
+                    }
+                    Operation::Remove { text } => {
+                        let _unused = mgr.remove(text_id(text));
+                    }
+                    Operation::Mark { text, kind } => {
+                        if let Err(err) = runtime.block_on(mgr.mark(text_id(text), kind)) {
+                            prop_assert!(matches!(err, Error::ClipNotFound { .. }), "{err}");
+                        }
+                    }
+                    Operation::Replace { text, new_text } => {
+                        let data = format!("text {new_text}");
+                        let _unused =
+                            mgr.replace(text_id(text), data.as_bytes(), &mime::TEXT_PLAIN_UTF_8);
+                    }
+                    Operation::Pin { text } => {
+                        let _unused = mgr.pin(text_id(text));
+                    }
+                    Operation::Unpin { text } => {
+                        let _unused = mgr.unpin(text_id(text));
+                    }
+                    Operation::Clear => mgr.clear(),
+                }
+                assert_consistent(&mgr);
+            }
+        }
+    }
+
+    #[test]
+    fn test_capacity_of_kinds() {
+        let backend = Arc::new(MockClipboardBackend::new());
+        let notification = MockNotification::default();
+        let capacity = HistoryCapacity { clipboard: 3, primary: 1, secondary: 1 };
+        let mut mgr = ClipboardManager::with_capacity(backend, capacity, notification);
+
+        let clipboard_ids = (0..3)
+            .map(|i| mgr.insert(ClipEntry::from_string(i, ClipboardKind::Clipboard)))
+            .collect::<Vec<_>>();
+        // changes of primary selection do not evict clips of clipboard
+        for i in 3..10 {
+            let _id = mgr.insert(ClipEntry::from_string(i, ClipboardKind::Primary));
+        }
+        assert_eq!(mgr.len(), 4);
+        assert!(clipboard_ids.iter().all(|&id| mgr.get(id).is_some()));
+        assert_eq!(mgr.list(10, &[ClipboardKind::Primary]).len(), 1);
+        assert_eq!(mgr.list(10, &[ClipboardKind::Clipboard]).len(), 3);
+        assert_eq!(mgr.list(10, &[]).len(), 4);
+
+        let _id = mgr.insert(ClipEntry::from_string("newest", ClipboardKind::Clipboard));
+        assert!(mgr.get(clipboard_ids[0]).is_none());
+        assert_eq!(mgr.list(10, &[ClipboardKind::Clipboard]).len(), 3);
+        assert_eq!(mgr.list(10, &[ClipboardKind::Primary]).len(), 1);
+    }
 }
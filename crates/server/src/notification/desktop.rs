@@ -17,7 +17,7 @@ enum Event {
     WatcherDisabled,
     X11Connected { connection_info: String },
     WaylandConnected { connection_info: String },
-    ImageFetched { size: usize, width: usize, height: usize },
+    ImageFetched { size: usize, dimensions: Option<(u32, u32)> },
     Shutdown,
 }
 
@@ -42,8 +42,8 @@ impl Notification {
 impl traits::Notification for Notification {
     fn on_started(&self) { drop(self.event_sender.send(Event::DaemonStarted)); }
 
-    fn on_image_fetched(&self, size: usize, width: usize, height: usize) {
-        drop(self.event_sender.send(Event::ImageFetched { size, width, height }));
+    fn on_image_fetched(&self, size: usize, dimensions: Option<(u32, u32)>) {
+        drop(self.event_sender.send(Event::ImageFetched { size, dimensions }));
     }
 
     fn on_history_cleared(&self) { drop(self.event_sender.send(Event::HistoryCleared)); }
@@ -112,11 +112,16 @@ impl Worker {
                 Some(Event::WaylandConnected { connection_info }) => {
                     format!("Connected to Wayland server ({connection_info}).")
                 }
-                Some(Event::ImageFetched { size, width, height }) => {
-                    format!(
-                        "Fetched a new image.\n(size: {size}, width: {width}, height: {height})",
-                        size = humansize::format_size(size, humansize::BINARY)
-                    )
+                Some(Event::ImageFetched { size, dimensions }) => {
+                    let size = humansize::format_size(size, humansize::BINARY);
+                    if let Some((width, height)) = dimensions {
+                        format!(
+                            "Fetched a new image.\n(size: {size}, width: {width}, height: \
+                             {height})"
+                        )
+                    } else {
+                        format!("Fetched a new image.\n(size: {size})")
+                    }
                 }
                 Some(Event::Shutdown) | None => {
                     prepare_to_shutdown = true;
@@ -1,9 +1,11 @@
 mod desktop;
+#[cfg(test)]
 mod mock;
 mod traits;
 
+#[cfg(test)]
+pub use self::mock::Notification as MockNotification;
 pub use self::{
     desktop::{Notification as DesktopNotification, Worker as DesktopNotificationWorker},
-    mock::Notification as MockNotification,
     traits::Notification,
 };
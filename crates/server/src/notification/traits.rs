@@ -3,7 +3,7 @@ use std::fmt;
 pub trait Notification: Send + Sync {
     fn on_started(&self) {}
 
-    fn on_image_fetched(&self, _size: usize, _width: usize, _height: usize) {}
+    fn on_image_fetched(&self, _size: usize, _dimensions: Option<(u32, u32)>) {}
 
     fn on_history_cleared(&self) {}
 
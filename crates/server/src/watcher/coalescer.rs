@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+use clipcat_base::{ClipEntry, ClipboardKind};
+
+/// Merges primary selections which are made while the user is still
+/// selecting, e.g. dragging over a terminal produces `a`, `ab`, `abc`...
+#[derive(Debug)]
+pub struct PrimaryCoalescer {
+    window: Duration,
+
+    // text, ID and capture time of the latest primary selection
+    latest: Option<(String, u64, Instant)>,
+}
+
+impl PrimaryCoalescer {
+    pub const fn new(window: Duration) -> Self { Self { window, latest: None } }
+
+    /// Returns the ID of the previous primary clip which is superseded by
+    /// `clip`. The clip might have been in history before it was selected,
+    /// it should only be removed if it was added by the previous capture.
+    pub fn supersede(&mut self, clip: &ClipEntry) -> Option<u64> {
+        self.supersede_at(clip, Instant::now())
+    }
+
+    fn supersede_at(&mut self, clip: &ClipEntry, now: Instant) -> Option<u64> {
+        if clip.kind() != ClipboardKind::Primary {
+            return None;
+        }
+        if !clip.is_utf8_string() {
+            self.latest = None;
+            return None;
+        }
+
+        let text = clip.as_utf8_string();
+        let superseded = self
+            .latest
+            .take()
+            .filter(|(latest, id, captured_at)| {
+                *id != clip.id()
+                    && now.saturating_duration_since(*captured_at) <= self.window
+                    && (text.starts_with(latest.as_str()) || latest.starts_with(text.as_str()))
+            })
+            .map(|(_, id, _)| id);
+        self.latest = Some((text, clip.id(), now));
+        superseded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use clipcat_base::{ClipEntry, ClipboardKind};
+
+    use crate::watcher::coalescer::PrimaryCoalescer;
+
+    #[test]
+    fn test_supersede() {
+        let mut coalescer = PrimaryCoalescer::new(Duration::from_millis(500));
+        let now = Instant::now();
+
+        // kind, text, elapsed milliseconds and the text of the superseded clip
+        let selections = [
+            (ClipboardKind::Primary, "ab", 0, None),
+            // extending and shrinking the selection
+            (ClipboardKind::Primary, "abc", 100, Some("ab")),
+            (ClipboardKind::Primary, "abcd", 200, Some("abc")),
+            (ClipboardKind::Primary, "abc", 300, Some("abcd")),
+            // unrelated selection
+            (ClipboardKind::Primary, "xyz", 400, None),
+            // clipboard does not interrupt selecting
+            (ClipboardKind::Clipboard, "xy", 500, None),
+            (ClipboardKind::Primary, "xyz!", 600, Some("xyz")),
+            // too late to be merged
+            (ClipboardKind::Primary, "xyz!?", 1200, None),
+        ];
+        for (kind, text, elapsed, superseded) in selections {
+            let clip = ClipEntry::from_string(text, kind);
+            let id = |text| ClipEntry::from_string(text, ClipboardKind::Primary).id();
+            assert_eq!(
+                coalescer.supersede_at(&clip, now + Duration::from_millis(elapsed)),
+                superseded.map(id)
+            );
+        }
+    }
+}
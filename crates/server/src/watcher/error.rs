@@ -11,6 +11,9 @@ pub enum Error {
 
     #[snafu(display("Subscriber is closed"))]
     SubscriberClosed,
+
+    #[snafu(display("Could not build filter pattern, error: {source}"))]
+    BuildFilterPattern { source: regex::Error },
 }
 
 impl From<crate::backend::Error> for Error {
@@ -1,4 +1,6 @@
+mod coalescer;
 mod error;
+mod filter;
 mod options;
 
 use std::sync::{
@@ -7,18 +9,45 @@ use std::sync::{
 };
 
 use clipcat_base::{ClipEntry, ClipboardContent, ClipboardKind, ClipboardWatcherState};
+use clipcat_clipboard::ClipboardEvent;
 use snafu::OptionExt;
-use tokio::{sync::broadcast, task};
+use tokio::{
+    sync::{broadcast, watch},
+    task,
+};
 
-pub use self::{error::Error, options::Options as ClipboardWatcherOptions};
+use self::{
+    coalescer::PrimaryCoalescer,
+    filter::{ApplicationFilter, ClipFilter},
+};
+pub use self::{
+    error::Error,
+    options::{FilterOptions, Options as ClipboardWatcherOptions},
+};
 use crate::{
     backend::{ClipboardBackend, Error as BackendError},
     notification,
 };
 
+/// A clip captured from clipboard.
+#[derive(Clone, Debug)]
+pub struct CapturedClip {
+    pub clip: ClipEntry,
+
+    /// ID of the previous clip which should be replaced by this one, it is
+    /// a primary selection made while the user was still selecting.
+    pub supersedes: Option<u64>,
+}
+
+impl From<ClipEntry> for CapturedClip {
+    fn from(clip: ClipEntry) -> Self { Self { clip, supersedes: None } }
+}
+
 pub struct ClipboardWatcher<Notification> {
     is_watching: Arc<AtomicBool>,
-    clip_sender: broadcast::Sender<ClipEntry>,
+    state_sender: Arc<watch::Sender<ClipboardWatcherState>>,
+    clip_sender: broadcast::Sender<CapturedClip>,
+    owner_lost_sender: broadcast::Sender<ClipboardKind>,
     _join_handle: task::JoinHandle<Result<(), Error>>,
     notification: Notification,
 }
@@ -29,74 +58,87 @@ where
 {
     pub fn new(
         backend: Arc<dyn ClipboardBackend>,
-        opts: ClipboardWatcherOptions,
+        opts: &ClipboardWatcherOptions,
         notification: Notification,
     ) -> Result<Self, Error> {
         let enabled_kinds = opts.get_enable_kinds();
+        let reclaim_kinds = opts.get_reclaim_kinds();
         let check_content = opts.generate_content_checker();
-        let ClipboardWatcherOptions { load_current, .. } = opts;
+        let filter = ClipFilter::new(&opts.filters)?;
+        let application_filter = ApplicationFilter::new(&opts.ignore_applications);
+        let ClipboardWatcherOptions { load_current, primary_coalescing_window, .. } = *opts;
+        let mut coalescer = primary_coalescing_window.map(PrimaryCoalescer::new);
 
         let (clip_sender, _event_receiver) = broadcast::channel(16);
+        let (owner_lost_sender, _event_receiver) = broadcast::channel(16);
         let is_watching = Arc::new(AtomicBool::new(true));
+        let (state_sender, _state_receiver) = watch::channel(ClipboardWatcherState::Enabled);
 
         let join_handle = task::spawn({
             let clip_sender = clip_sender.clone();
+            let owner_lost_sender = owner_lost_sender.clone();
             let is_watching = is_watching.clone();
 
             let mut subscriber = backend.subscribe()?;
 
             async move {
-                let mut current_contents: [ClipboardContent; ClipboardKind::MAX_LENGTH] = [
-                    ClipboardContent::default(),
-                    ClipboardContent::default(),
-                    ClipboardContent::default(),
-                ];
-                if load_current {
-                    for (kind, enable) in enabled_kinds
-                        .iter()
-                        .enumerate()
-                        .map(|(kind, &enable)| (ClipboardKind::from(kind), enable))
-                    {
-                        if enable {
-                            match backend.load(kind, None).await {
-                                Ok(data) => {
-                                    if check_content(&data) {
-                                        current_contents[usize::from(kind)] = data.clone();
-                                        if let Err(_err) = clip_sender.send(
-                                            ClipEntry::from_clipboard_content(data, kind, None),
-                                        ) {
-                                            tracing::info!("ClipEntry receiver is closed.");
-                                            return Err(Error::SendClipEntry);
-                                        }
-                                    }
-                                }
-                                Err(
-                                    BackendError::EmptyClipboard
-                                    | BackendError::MatchMime { .. }
-                                    | BackendError::UnknownContentType
-                                    | BackendError::UnsupportedClipboardKind { .. },
-                                ) => continue,
-                                Err(error) => {
-                                    tracing::error!("Failed to load clipboard, error: {error}");
-                                }
-                            }
-                        }
-                    }
-                }
+                let mut current_contents = if load_current {
+                    load_current_contents(
+                        backend.as_ref(),
+                        enabled_kinds,
+                        &check_content,
+                        &filter,
+                        &clip_sender,
+                    )
+                    .await?
+                } else {
+                    Default::default()
+                };
 
                 loop {
-                    let (kind, mime) =
+                    let (kind, event) =
                         subscriber.next().await.context(error::SubscriberClosedSnafu)?;
                     if is_watching.load(Ordering::Relaxed) && enabled_kinds[usize::from(kind)] {
+                        let (mime, source) = match event {
+                            ClipboardEvent::Changed { mime, source } => (mime, source),
+                            ClipboardEvent::OwnerLost => {
+                                if reclaim_kinds[usize::from(kind)] {
+                                    tracing::info!("Owner of {kind} is gone, take it over");
+                                    let _unused = owner_lost_sender.send(kind);
+                                }
+                                continue;
+                            }
+                        };
+                        if let Some(source) = source
+                            .as_ref()
+                            .filter(|source| application_filter.filter_source(source))
+                        {
+                            // do not even load the content, it might be sensitive
+                            tracing::info!(
+                                "Clip from {source} is ignored, because of the application filters"
+                            );
+                            continue;
+                        }
+                        if filter.filter_mime(&mime) {
+                            tracing::info!("Clip ({mime}) is ignored, because of the MIME filters");
+                            continue;
+                        }
                         match backend.load(kind, Some(mime)).await {
                             Ok(new_content)
                                 if check_content(&new_content)
+                                    && !filter_content(&filter, &new_content)
                                     && current_contents[usize::from(kind)] != new_content =>
                             {
                                 current_contents[usize::from(kind)] = new_content.clone();
-                                let clip =
+                                let mut clip =
                                     ClipEntry::from_clipboard_content(new_content, kind, None);
-                                if let Err(_err) = clip_sender.send(clip) {
+                                clip.set_source(source);
+                                let supersedes = coalescer
+                                    .as_mut()
+                                    .and_then(|coalescer| coalescer.supersede(&clip));
+                                if let Err(_err) =
+                                    clip_sender.send(CapturedClip { clip, supersedes })
+                                {
                                     tracing::info!("ClipEntry receiver is closed.");
                                     return Err(Error::SendClipEntry);
                                 }
@@ -116,20 +158,89 @@ where
             }
         });
 
-        Ok(Self { is_watching, clip_sender, _join_handle: join_handle, notification })
+        Ok(Self {
+            is_watching,
+            state_sender: Arc::new(state_sender),
+            clip_sender,
+            owner_lost_sender,
+            _join_handle: join_handle,
+            notification,
+        })
     }
 
     #[inline]
-    pub fn subscribe(&self) -> broadcast::Receiver<ClipEntry> { self.clip_sender.subscribe() }
+    pub fn subscribe(&self) -> broadcast::Receiver<CapturedClip> { self.clip_sender.subscribe() }
+
+    /// Subscribes to clipboard kinds whose owner is gone and should be taken
+    /// over.
+    #[inline]
+    pub fn subscribe_owner_lost(&self) -> broadcast::Receiver<ClipboardKind> {
+        self.owner_lost_sender.subscribe()
+    }
 
     #[inline]
     pub fn get_toggle(&self) -> Toggle<Notification> {
-        Toggle { is_watching: self.is_watching.clone(), notification: self.notification.clone() }
+        Toggle {
+            is_watching: self.is_watching.clone(),
+            state_sender: self.state_sender.clone(),
+            notification: self.notification.clone(),
+        }
     }
 }
 
+async fn load_current_contents(
+    backend: &dyn ClipboardBackend,
+    enabled_kinds: [bool; ClipboardKind::MAX_LENGTH],
+    check_content: &(impl Fn(&ClipboardContent) -> bool + Sync),
+    filter: &ClipFilter,
+    clip_sender: &broadcast::Sender<CapturedClip>,
+) -> Result<[ClipboardContent; ClipboardKind::MAX_LENGTH], Error> {
+    let mut current_contents: [ClipboardContent; ClipboardKind::MAX_LENGTH] = Default::default();
+    for (kind, enable) in
+        enabled_kinds.iter().enumerate().map(|(kind, &enable)| (ClipboardKind::from(kind), enable))
+    {
+        if enable {
+            match backend.load(kind, None).await {
+                Ok(data) => {
+                    if check_content(&data) && !filter_content(filter, &data) {
+                        current_contents[usize::from(kind)] = data.clone();
+                        let clip = ClipEntry::from_clipboard_content(data, kind, None);
+                        if let Err(_err) = clip_sender.send(clip.into()) {
+                            tracing::info!("ClipEntry receiver is closed.");
+                            return Err(Error::SendClipEntry);
+                        }
+                    }
+                }
+                Err(
+                    BackendError::EmptyClipboard
+                    | BackendError::MatchMime { .. }
+                    | BackendError::UnknownContentType
+                    | BackendError::UnsupportedClipboardKind { .. },
+                ) => continue,
+                Err(error) => {
+                    tracing::error!("Failed to load clipboard, error: {error}");
+                }
+            }
+        }
+    }
+    Ok(current_contents)
+}
+
+fn filter_content(filter: &ClipFilter, content: &ClipboardContent) -> bool {
+    let filtered = filter.filter_content(content);
+    if filtered {
+        // do not print the content, it might be sensitive
+        tracing::info!(
+            "Clip ({info}) is ignored, because of the content filters",
+            info = content.basic_information()
+        );
+    }
+    filtered
+}
+
 pub struct Toggle<Notification> {
     is_watching: Arc<AtomicBool>,
+    state_sender: Arc<watch::Sender<ClipboardWatcherState>>,
     notification: Notification,
 }
 
@@ -140,6 +251,7 @@ where
     #[inline]
     pub fn enable(&self) {
         self.is_watching.store(true, Ordering::Release);
+        self.publish_state(ClipboardWatcherState::Enabled);
         self.notification.on_watcher_enabled();
         tracing::info!("ClipboardWatcher is watching for clipboard event");
     }
@@ -147,6 +259,7 @@ where
     #[inline]
     pub fn disable(&self) {
         self.is_watching.store(false, Ordering::Release);
+        self.publish_state(ClipboardWatcherState::Disabled);
         self.notification.on_watcher_disabled();
         tracing::info!("ClipboardWatcher is not watching for clipboard event");
     }
@@ -173,4 +286,98 @@ where
             ClipboardWatcherState::Disabled
         }
     }
+
+    /// Subscribes to changes of the state, it is changed by any of the
+    /// toggles.
+    #[inline]
+    #[must_use]
+    pub fn subscribe(&self) -> watch::Receiver<ClipboardWatcherState> {
+        self.state_sender.subscribe()
+    }
+
+    // subscribers are only woken up if the state is actually changed
+    fn publish_state(&self, new_state: ClipboardWatcherState) {
+        let _modified = self
+            .state_sender
+            .send_if_modified(|state| std::mem::replace(state, new_state) != new_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use clipcat_base::{ClipEntry, ClipboardContent, ClipboardKind};
+    use tokio::sync::broadcast;
+
+    use crate::{
+        backend::{ClipboardBackend, MockClipboardBackend},
+        notification::MockNotification,
+        watcher::{CapturedClip, ClipboardWatcher, ClipboardWatcherOptions},
+    };
+
+    const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+    // the mock clipboard drops events which are published before the watcher
+    // waits for the next one, so the text is selected again until it is
+    // captured, the watcher ignores the same content selected again
+    async fn select(
+        backend: &MockClipboardBackend,
+        clips: &mut broadcast::Receiver<CapturedClip>,
+        text: &str,
+    ) -> CapturedClip {
+        for _ in 0..50 {
+            backend
+                .store(ClipboardKind::Primary, ClipboardContent::Plaintext(text.to_string()))
+                .await
+                .unwrap();
+            if let Ok(captured) = tokio::time::timeout(RETRY_INTERVAL, clips.recv()).await {
+                return captured.unwrap();
+            }
+        }
+        panic!("Selection `{text}` is not captured");
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_primary_selections() {
+        let backend = MockClipboardBackend::with_kind(ClipboardKind::Primary);
+        let opts = ClipboardWatcherOptions {
+            load_current: false,
+            primary_coalescing_window: Some(Duration::from_secs(10)),
+            ..ClipboardWatcherOptions::default()
+        };
+        let watcher =
+            ClipboardWatcher::new(Arc::new(backend.clone()), &opts, MockNotification::default())
+                .unwrap();
+        let mut clips = watcher.subscribe();
+
+        let id = |text: &str| ClipEntry::from_string(text, ClipboardKind::Primary).id();
+        for (text, supersedes) in [
+            ("ab", None),
+            ("abc", Some("ab")),
+            ("abcd", Some("abc")),
+            ("abc", Some("abcd")),
+            ("xyz", None),
+        ] {
+            let CapturedClip { clip, supersedes: superseded } =
+                select(&backend, &mut clips, text).await;
+            assert_eq!(clip.kind(), ClipboardKind::Primary);
+            assert_eq!((clip.id(), superseded), (id(text), supersedes.map(id)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_coalescing_by_default() {
+        let backend = MockClipboardBackend::with_kind(ClipboardKind::Primary);
+        let opts = ClipboardWatcherOptions { load_current: false, ..Default::default() };
+        let watcher =
+            ClipboardWatcher::new(Arc::new(backend.clone()), &opts, MockNotification::default())
+                .unwrap();
+        let mut clips = watcher.subscribe();
+
+        for text in ["ab", "abc", "abcd"] {
+            let CapturedClip { supersedes, .. } = select(&backend, &mut clips, text).await;
+            assert_eq!(supersedes, None);
+        }
+    }
 }
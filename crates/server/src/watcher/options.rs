@@ -1,8 +1,10 @@
+use std::{collections::HashSet, time::Duration};
+
 use clipcat_base::{ClipboardContent, ClipboardKind};
 
 // SAFETY: user may use bool to enable/disable the functions
 #[allow(clippy::struct_excessive_bools)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Options {
     pub load_current: bool,
 
@@ -12,9 +14,53 @@ pub struct Options {
 
     pub capture_image: bool,
 
+    // take over the clipboard with the current clip when its owner exits, it is
+    // only supported on X11
+    pub reclaim_clipboard: bool,
+
+    pub reclaim_primary: bool,
+
+    // a primary selection replaces the previous one if it extends or shrinks the
+    // previous one within this window, it is disabled if `None`
+    pub primary_coalescing_window: Option<Duration>,
+
+    // clips copied from these applications are ignored, they are matched with
+    // window class or process name
+    pub ignore_applications: Vec<String>,
+
     pub filter_min_size: usize,
 
     pub filter_max_size: usize,
+
+    pub filters: FilterOptions,
+}
+
+#[derive(Clone, Debug)]
+pub struct FilterOptions {
+    // text matching any of these regular expressions is ignored
+    pub denied_text_patterns: Vec<String>,
+
+    // only content with these MIME types is accepted if it is not empty
+    pub allowed_mimes: Vec<mime::Mime>,
+
+    pub denied_mimes: Vec<mime::Mime>,
+
+    // content offered with any of these X11 target atoms or Wayland MIME types is ignored
+    pub sensitive_mime_types: HashSet<String>,
+}
+
+impl Default for FilterOptions {
+    fn default() -> Self {
+        Self {
+            denied_text_patterns: Vec::new(),
+            allowed_mimes: Vec::new(),
+            denied_mimes: Vec::new(),
+            sensitive_mime_types: clipcat_base::DEFAULT_SENSITIVE_MIME_TYPES
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
 }
 
 impl Default for Options {
@@ -28,9 +74,19 @@ impl Default for Options {
 
             capture_image: true,
 
+            reclaim_clipboard: false,
+
+            reclaim_primary: false,
+
+            primary_coalescing_window: None,
+
+            ignore_applications: Vec::new(),
+
             filter_min_size: 1,
             // 5 MiB
             filter_max_size: 5 * (1 << 20),
+
+            filters: FilterOptions::default(),
         }
     }
 }
@@ -51,10 +107,20 @@ impl Options {
         kinds
     }
 
+    #[inline]
+    pub(crate) fn get_reclaim_kinds(&self) -> [bool; ClipboardKind::MAX_LENGTH] {
+        let mut kinds = [false; ClipboardKind::MAX_LENGTH];
+        kinds[usize::from(ClipboardKind::Clipboard)] = self.reclaim_clipboard;
+        kinds[usize::from(ClipboardKind::Primary)] = self.reclaim_primary;
+        kinds
+    }
+
     pub(crate) fn generate_content_checker(&self) -> impl Fn(&ClipboardContent) -> bool {
         let Self { capture_image, filter_max_size, filter_min_size, .. } = *self;
         move |data: &ClipboardContent| -> bool {
-            let ret = (data.is_plaintext() || (capture_image && data.is_image()))
+            let ret = (data.is_plaintext()
+                || (capture_image && data.is_image())
+                || (data.is_rich() && (capture_image || data.mime().type_() != mime::IMAGE)))
                 && data.len() > filter_min_size
                 && data.len() <= filter_max_size;
             if !ret {
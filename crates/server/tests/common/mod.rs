@@ -0,0 +1,37 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clipcat_server::{
+    config::{DesktopNotificationConfig, HistoryBackend, LocalSocketAccess},
+    ClipboardWatcherOptions, Config,
+};
+
+/// Creates a config which serves nothing, history is kept in `dir`.
+pub fn new_config(dir: &Path) -> Config {
+    Config {
+        grpc_listen_address: None,
+        grpc_local_socket: None,
+        local_socket_access: LocalSocketAccess::default(),
+        grpc_tls: None,
+        grpc_access_token: None,
+        webui_listen_address: None,
+        webui_access_token: None,
+        rest_listen_address: None,
+        rest_access_token: None,
+        rest_local_socket: None,
+        dbus_name: None,
+        max_history: 50.into(),
+        max_age: None,
+        history_file_path: dir.join("history"),
+        history_backend: HistoryBackend::FileSystem,
+        history_encryption_key: None,
+        watcher: ClipboardWatcherOptions::default(),
+        desktop_notification: DesktopNotificationConfig {
+            enable: false,
+            icon: PathBuf::new(),
+            timeout: Duration::from_secs(1),
+        },
+    }
+}
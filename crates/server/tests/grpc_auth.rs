@@ -0,0 +1,86 @@
+use std::{net::SocketAddr, path::Path, sync::Arc, time::Duration};
+
+use clipcat_client::{error::GetSystemVersionError, Client, Credentials, System};
+use clipcat_server::{
+    backend::MockClipboardBackend,
+    config::{AccessToken, GrpcTlsConfig},
+    Config,
+};
+use tonic::Code;
+
+mod common;
+
+const ACCESS_TOKEN: &str = "0123456789abcdef";
+
+fn unused_local_address() -> SocketAddr {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+fn write_self_signed_certificate(dir: &Path) -> GrpcTlsConfig {
+    let cert = rcgen::generate_simple_self_signed(vec!["127.0.0.1".to_string()]).unwrap();
+    let tls = GrpcTlsConfig { cert_file: dir.join("cert.pem"), key_file: dir.join("key.pem") };
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::write(&tls.cert_file, cert.serialize_pem().unwrap()).unwrap();
+    std::fs::write(&tls.key_file, cert.serialize_private_key_pem()).unwrap();
+    tls
+}
+
+// `Err(None)` means that the server is not connected
+async fn get_version(endpoint: &http::Uri, credentials: &Credentials) -> Result<(), Option<Code>> {
+    let client = Client::with_credentials(endpoint.clone(), credentials).await.map_err(|_| None)?;
+    let result = client.get_version().await;
+    drop(client);
+    result.map(|_| ()).map_err(|GetSystemVersionError::Status { source }| Some(source.code()))
+}
+
+async fn wait_for_server(endpoint: &http::Uri, credentials: &Credentials) {
+    for _ in 0..100 {
+        if get_version(endpoint, credentials).await != Err(None) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("gRPC server is not started");
+}
+
+#[tokio::test]
+async fn test_access_token_over_tls() {
+    let dir = std::env::temp_dir()
+        .join(format!("clipcat-grpc-auth-test-{pid}", pid = std::process::id()));
+    let listen_address = unused_local_address();
+    let config = Config {
+        grpc_listen_address: Some(listen_address),
+        grpc_tls: Some(write_self_signed_certificate(&dir)),
+        grpc_access_token: Some(AccessToken::new(ACCESS_TOKEN.to_string())),
+        ..common::new_config(&dir)
+    };
+    let server = tokio::spawn(clipcat_server::serve_with_backend(
+        config,
+        &[],
+        Arc::new(MockClipboardBackend::new()),
+    ));
+
+    let endpoint: http::Uri = format!("https://{listen_address}").parse().unwrap();
+    let credentials = Credentials {
+        access_token: Some(ACCESS_TOKEN.to_string()),
+        ca_certificate: Some(dir.join("cert.pem")),
+    };
+    wait_for_server(&endpoint, &credentials).await;
+    assert_eq!(get_version(&endpoint, &credentials).await, Ok(()));
+
+    for access_token in [None, Some("fedcba9876543210".to_string())] {
+        let credentials = Credentials { access_token, ..credentials.clone() };
+        assert_eq!(get_version(&endpoint, &credentials).await, Err(Some(Code::Unauthenticated)));
+    }
+
+    // the certificate of server is not trusted
+    let credentials = Credentials { ca_certificate: None, ..credentials };
+    assert!(get_version(&endpoint, &credentials).await.is_err());
+
+    // plaintext is rejected by server
+    let endpoint: http::Uri = format!("http://{listen_address}").parse().unwrap();
+    assert!(get_version(&endpoint, &credentials).await.is_err());
+
+    server.abort();
+    drop(std::fs::remove_dir_all(dir));
+}
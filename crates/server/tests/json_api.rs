@@ -0,0 +1,229 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
+use clipcat_server::{backend::MockClipboardBackend, config::AccessToken, Config};
+use hyper::{Body, Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tokio::net::{TcpStream, UnixStream};
+
+mod common;
+
+async fn connect(socket: &Path) -> hyper::client::conn::SendRequest<Body> {
+    let stream = UnixStream::connect(socket).await.unwrap();
+    let (sender, connection) = hyper::client::conn::handshake(stream).await.unwrap();
+    drop(tokio::spawn(connection));
+    sender
+}
+
+async fn send(
+    socket: &Path,
+    method: Method,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let request = Request::builder().method(method).uri(uri).header("host", "localhost");
+    let request = match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+        None => request.body(Body::empty()).unwrap(),
+    };
+    let response = connect(socket).await.send_request(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body = if body.is_empty() { Value::Null } else { serde_json::from_slice(&body).unwrap() };
+    (status, body)
+}
+
+async fn wait_for_socket(socket: &Path) {
+    for _ in 0..100 {
+        if UnixStream::connect(socket).await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("JSON API server is not started");
+}
+
+#[tokio::test]
+async fn test_json_api() {
+    let dir =
+        std::env::temp_dir().join(format!("clipcat-json-api-test-{pid}", pid = std::process::id()));
+    let socket = dir.join("rest.sock");
+    let config = Config { rest_local_socket: Some(socket.clone()), ..common::new_config(&dir) };
+    let server = tokio::spawn(clipcat_server::serve_with_backend(
+        config,
+        &[],
+        Arc::new(MockClipboardBackend::new()),
+    ));
+    wait_for_socket(&socket).await;
+
+    // System
+    let (status, version) = send(&socket, Method::GET, "/api/version", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(version["major"].is_u64() && version["minor"].is_u64() && version["patch"].is_u64());
+
+    // Watcher
+    let (_, state) = send(&socket, Method::GET, "/api/watcher", None).await;
+    assert_eq!(state, json!({ "state": "Enabled" }));
+    let (_, state) = send(&socket, Method::POST, "/api/watcher/toggle", None).await;
+    assert_eq!(state, json!({ "state": "Disabled" }));
+    let (_, state) = send(&socket, Method::POST, "/api/watcher/enable", None).await;
+    assert_eq!(state, json!({ "state": "Enabled" }));
+    // clips marked below would be captured again from the mock clipboard
+    let (_, state) = send(&socket, Method::POST, "/api/watcher/disable", None).await;
+    assert_eq!(state, json!({ "state": "Disabled" }));
+
+    // Manager
+    let (status, body) =
+        send(&socket, Method::POST, "/api/clips", Some(json!({ "text": "Alpha" }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let text_id = body["id"].as_str().unwrap().to_string();
+    let html = json!({ "kind": "primary", "mime": "text/html", "data": "PGI+QmV0YTwvYj4=" });
+    let (status, body) = send(&socket, Method::POST, "/api/clips", Some(html)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let html_id = body["id"].as_str().unwrap().to_string();
+    let (status, _) = send(&socket, Method::POST, "/api/clips", Some(json!({}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, body) = send(&socket, Method::GET, "/api/clips/length", None).await;
+    assert_eq!(body, json!({ "length": 2 }));
+
+    let (_, clips) = send(&socket, Method::GET, "/api/clips?kind=clipboard", None).await;
+    assert_eq!(clips.as_array().unwrap().len(), 1);
+    assert_eq!(clips[0]["id"], text_id.as_str());
+    assert_eq!(clips[0]["kind"], "Clipboard");
+    assert_eq!(clips[0]["mime"], "text/plain");
+    assert_eq!(clips[0]["preview"], "Alpha");
+
+    let (_, clip) = send(&socket, Method::GET, &format!("/api/clips/{html_id}"), None).await;
+    assert_eq!(clip["kind"], "Primary");
+    assert_eq!(clip["mime"], "text/html");
+    assert_eq!(clip["representations"]["text/html"], "PGI+QmV0YTwvYj4=");
+
+    let (_, result) =
+        send(&socket, Method::GET, "/api/clips/search?pattern=ALP&case_sensitive=true", None).await;
+    assert_eq!(result["total"], 0);
+    let (_, result) = send(&socket, Method::GET, "/api/clips/search?pattern=ALP", None).await;
+    assert_eq!(result["total"], 1);
+    assert_eq!(result["clips"][0]["id"], text_id.as_str());
+
+    let uri = format!("/api/clips/{text_id}/mark");
+    let (status, _) = send(&socket, Method::POST, &uri, Some(json!({ "kind": "clipboard" }))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, clip) = send(&socket, Method::GET, "/api/clips/current?kind=clipboard", None).await;
+    assert_eq!(clip["text"], "Alpha");
+    let (status, _) = send(&socket, Method::POST, "/api/clips/0000000000000000/mark", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let uri = format!("/api/clips/{text_id}/pin");
+    let (status, _) = send(&socket, Method::POST, &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, clip) = send(&socket, Method::GET, &format!("/api/clips/{text_id}"), None).await;
+    assert_eq!(clip["pinned"], true);
+    let (status, _) = send(&socket, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&socket, Method::POST, "/api/clips/0000000000000000/pin", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let ids = json!({ "ids": [html_id] });
+    let (_, body) = send(&socket, Method::POST, "/api/clips/batch-remove", Some(ids.clone())).await;
+    assert_eq!(body, ids);
+
+    let (status, _) = send(&socket, Method::POST, "/api/clips/clear", None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, body) = send(&socket, Method::GET, "/api/clips/length", None).await;
+    assert_eq!(body, json!({ "length": 0 }));
+
+    server.abort();
+    drop(std::fs::remove_dir_all(dir));
+}
+
+#[tokio::test]
+async fn test_json_api_events() {
+    let dir = std::env::temp_dir()
+        .join(format!("clipcat-json-api-events-test-{pid}", pid = std::process::id()));
+    let socket = dir.join("rest.sock");
+    let config = Config { rest_local_socket: Some(socket.clone()), ..common::new_config(&dir) };
+    let server = tokio::spawn(clipcat_server::serve_with_backend(
+        config,
+        &[],
+        Arc::new(MockClipboardBackend::new()),
+    ));
+    wait_for_socket(&socket).await;
+
+    let request = Request::get("/api/events").header("host", "localhost").body(Body::empty());
+    let response = connect(&socket).await.send_request(request.unwrap()).await.unwrap();
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    let mut events = response.into_body();
+
+    let (_, body) =
+        send(&socket, Method::POST, "/api/clips", Some(json!({ "text": "Alpha" }))).await;
+    let chunk =
+        tokio::time::timeout(Duration::from_secs(5), hyper::body::HttpBody::data(&mut events))
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    let line = std::str::from_utf8(&chunk).unwrap().lines().next().unwrap();
+    let event: Value = serde_json::from_str(line).unwrap();
+    assert_eq!(event["event"], "inserted");
+    assert_eq!(event["id"], body["id"]);
+    assert_eq!(event["preview"], "Alpha");
+
+    server.abort();
+    drop(std::fs::remove_dir_all(dir));
+}
+
+#[tokio::test]
+async fn test_json_api_over_tcp_requires_access_token() {
+    let dir = std::env::temp_dir()
+        .join(format!("clipcat-json-api-tcp-test-{pid}", pid = std::process::id()));
+    let listen_address = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let config = Config { rest_listen_address: Some(listen_address), ..common::new_config(&dir) };
+    let result =
+        clipcat_server::serve_with_backend(config, &[], Arc::new(MockClipboardBackend::new()))
+            .await;
+    assert!(result.is_err());
+
+    let config = Config {
+        rest_listen_address: Some(listen_address),
+        rest_access_token: Some(AccessToken::new("0123456789abcdef".to_string())),
+        ..common::new_config(&dir)
+    };
+    let server = tokio::spawn(clipcat_server::serve_with_backend(
+        config,
+        &[],
+        Arc::new(MockClipboardBackend::new()),
+    ));
+
+    let get_length = |authorization: Option<&'static str>| async move {
+        let stream = TcpStream::connect(listen_address).await.ok()?;
+        let (mut sender, connection) = hyper::client::conn::handshake(stream).await.ok()?;
+        drop(tokio::spawn(connection));
+        let request = authorization
+            .into_iter()
+            .fold(Request::get("/api/clips/length"), |request, authorization| {
+                request.header("authorization", authorization)
+            })
+            .header("host", listen_address.to_string())
+            .body(Body::empty())
+            .unwrap();
+        sender.send_request(request).await.ok().map(|response| response.status())
+    };
+    let mut status = None;
+    for _ in 0..100 {
+        status = get_length(Some("Bearer 0123456789abcdef")).await;
+        if status.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(status, Some(StatusCode::OK));
+    for authorization in [None, Some("Bearer fedcba9876543210")] {
+        assert_eq!(get_length(authorization).await, Some(StatusCode::UNAUTHORIZED));
+    }
+
+    server.abort();
+    drop(std::fs::remove_dir_all(dir));
+}